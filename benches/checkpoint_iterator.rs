@@ -0,0 +1,143 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use previous::CheckpointIterator as PreviousIterator;
+use rascii::{
+    checkpoint_iterator::CheckpointIterator,
+    parser::traits::{DocParser, DocSyntaxParser},
+};
+
+#[path = "checkpoint_iterator/previous.rs"]
+mod previous;
+
+/// A document of a few hundred kilobytes with sections, lists and delimited blocks
+fn document() -> String {
+    let mut text = String::from("= Benchmark\nJane Doe <jane@example.com>\n\n");
+    for i in 0..2_000 {
+        text += &format!(
+            "== Section {i}\n\nSome paragraph text with {{attribute}} references in it.\n\
+             Second line of the paragraph, with ünïcödé.\n\n* item one\n* item two\n\
+             ** nested item\n\n----\ncode line\n----\n\n"
+        );
+    }
+    text
+}
+
+fn parse(c: &mut Criterion) {
+    let text = document();
+    let mut group = c.benchmark_group(format!("{} bytes", text.len()));
+    group.sample_size(20);
+    group.bench_function("syntax tree", |b| {
+        b.iter(|| CheckpointIterator::new(text.chars()).parse_syntax_tree())
+    });
+    group.bench_function("document", |b| {
+        b.iter(|| CheckpointIterator::new(text.chars()).parse_document())
+    });
+    group.bench_function("syntax tree, memoized", |b| {
+        b.iter(|| CheckpointIterator::with_memo(text.chars()).parse_syntax_tree())
+    });
+    group.bench_function("document, memoized", |b| {
+        b.iter(|| CheckpointIterator::with_memo(text.chars()).parse_document())
+    });
+    group.finish();
+}
+
+/// Blocks with long runs of metadata lines, which are parsed again for each attempt at a
+/// section heading or block
+fn metadata_heavy(c: &mut Criterion) {
+    let mut text = String::new();
+    for i in 0..200 {
+        text += &format!("== Section {i}\n\n");
+        for _ in 0..5 {
+            text += &".Title\n[[anchor]]\n[source,rust,linenums]\n".repeat(20);
+            text += "Text of the block.\n\n";
+        }
+    }
+    let mut group = c.benchmark_group("metadata heavy");
+    group.sample_size(20);
+    group.bench_function("syntax tree", |b| {
+        b.iter(|| CheckpointIterator::new(text.chars()).parse_syntax_tree())
+    });
+    group.bench_function("syntax tree, memoized", |b| {
+        b.iter(|| CheckpointIterator::with_memo(text.chars()).parse_syntax_tree())
+    });
+    group.finish();
+}
+
+/// Each line read twice, first by an attempt which is rewound, like a parser trying the
+/// rules of a line in turn
+fn lines(c: &mut Criterion) {
+    let text = document();
+    let mut group = c.benchmark_group("read lines twice");
+    group.sample_size(20);
+    group.bench_function("previous", |b| {
+        b.iter(|| {
+            let mut iter = PreviousIterator::new(text.chars());
+            let mut read = 0;
+            while iter.peek().is_some() {
+                iter.push();
+                read += iter.take_while_ref(|c| *c != '\n').count();
+                iter.pop();
+                iter.push();
+                read += iter.take_while_ref(|c| *c != '\n').count();
+                iter.next();
+                iter.drop();
+            }
+            read
+        })
+    });
+    group.bench_function("current", |b| {
+        b.iter(|| {
+            let mut iter = CheckpointIterator::new(text.chars());
+            let mut read = 0;
+            while iter.peek().is_some() {
+                iter.push();
+                read += iter.take_while_ref(|c| *c != '\n').chars().count();
+                iter.pop().unwrap();
+                iter.push();
+                read += iter.take_while_ref(|c| *c != '\n').chars().count();
+                iter.next();
+                iter.drop().unwrap();
+            }
+            read
+        })
+    });
+    group.finish();
+}
+
+/// Nested checkpoints each reading a line before all of them are rewound, like a parser
+/// backtracking out of deeply nested blocks
+fn backtracking(c: &mut Criterion) {
+    let text = "some line of text\n".repeat(10_000);
+    for depth in [10, 100, 1_000] {
+        let mut group = c.benchmark_group(format!("backtrack {depth} checkpoints"));
+        group.bench_function("previous", |b| {
+            b.iter(|| {
+                let mut iter = PreviousIterator::new(text.chars());
+                for _ in 0..depth {
+                    iter.push();
+                    while iter.next().is_some_and(|c| c != '\n') {}
+                }
+                for _ in 0..depth {
+                    iter.pop().unwrap();
+                }
+                iter.peek()
+            })
+        });
+        group.bench_function("current", |b| {
+            b.iter(|| {
+                let mut iter = CheckpointIterator::new(text.chars());
+                for _ in 0..depth {
+                    iter.push();
+                    while iter.next().is_some_and(|c| c != '\n') {}
+                }
+                for _ in 0..depth {
+                    iter.pop().unwrap();
+                }
+                iter.peek()
+            })
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, parse, metadata_heavy, lines, backtracking);
+criterion_main!(benches);
@@ -0,0 +1,117 @@
+//! The `CheckpointIterator` before it kept the text in a buffer, which saves the chars read
+//! since each checkpoint and replays them when going back to it
+
+use std::collections::VecDeque;
+
+pub struct CheckpointIterator<T: Iterator> {
+    inner: T,
+    stack: Vec<(usize, Vec<T::Item>)>,
+    buf: VecDeque<T::Item>,
+    current_pos: usize,
+}
+
+impl<T> CheckpointIterator<T>
+where
+    T: Iterator,
+    T::Item: Clone,
+{
+    pub fn new(iterator: T) -> Self {
+        Self {
+            inner: iterator,
+            stack: Vec::new(),
+            buf: VecDeque::new(),
+            current_pos: 0,
+        }
+    }
+
+    pub fn push(&mut self) -> usize {
+        self.stack.push((self.current_pos, Vec::new()));
+        self.current_pos
+    }
+
+    pub fn pop(&mut self) -> Option<usize> {
+        let (pos, last) = self.stack.pop()?;
+        self.current_pos = pos;
+        for e in last.into_iter().rev() {
+            self.buf.push_front(e);
+        }
+        Some(pos)
+    }
+
+    pub fn drop(&mut self) -> Option<usize> {
+        let (pos, last) = self.stack.pop()?;
+        if let Some(v) = self.stack.last_mut() {
+            v.1.extend(last);
+        }
+        Some(pos)
+    }
+
+    pub fn peek(&mut self) -> Option<T::Item> {
+        let item = self.step()?;
+        self.unstep(item.clone());
+        Some(item)
+    }
+
+    pub fn step(&mut self) -> Option<T::Item> {
+        let item = self.buf.pop_front().or_else(|| self.inner.next())?;
+        if let Some(e) = self.stack.last_mut() {
+            e.1.push(item.clone());
+        }
+        self.current_pos += 1;
+        Some(item)
+    }
+
+    fn unstep(&mut self, item: T::Item) {
+        if let Some(e) = self.stack.last_mut() {
+            e.1.pop();
+        }
+        self.current_pos -= 1;
+        self.buf.push_front(item);
+    }
+
+    pub fn take_while_ref<P: Fn(&T::Item) -> bool>(
+        &mut self,
+        predicate: P,
+    ) -> TakeWhileRef<'_, T, P> {
+        TakeWhileRef {
+            inner: self,
+            predicate,
+        }
+    }
+}
+
+impl<T> Iterator for CheckpointIterator<T>
+where
+    T: Iterator,
+    T::Item: Clone,
+{
+    type Item = T::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.step()
+    }
+}
+
+pub struct TakeWhileRef<'a, T: Iterator, P: Fn(&T::Item) -> bool> {
+    inner: &'a mut CheckpointIterator<T>,
+    predicate: P,
+}
+
+impl<T, P> Iterator for TakeWhileRef<'_, T, P>
+where
+    T: Iterator,
+    T::Item: Clone,
+    P: Fn(&T::Item) -> bool,
+{
+    type Item = T::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let e = self.inner.next()?;
+        if (self.predicate)(&e) {
+            Some(e)
+        } else {
+            self.inner.unstep(e);
+            None
+        }
+    }
+}
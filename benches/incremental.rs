@@ -0,0 +1,48 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use rascii::{
+    checkpoint_iterator::CheckpointIterator,
+    cst::{TextEdit, TextRange},
+    parser::{incremental::reparse, traits::DocSyntaxParser},
+};
+
+/// A document of a few megabytes with sections, lists and delimited blocks
+fn document() -> String {
+    let mut text = String::from("= Benchmark\nJane Doe <jane@example.com>\n\n");
+    for i in 0..12_000 {
+        text += &format!(
+            "== Section {i}\n\nSome paragraph text with {{attribute}} references in it.\n\
+             Second line of the paragraph.\n\n* item one\n* item two\n** nested item\n\n\
+             ----\ncode line\n----\n\n"
+        );
+    }
+    text
+}
+
+fn incremental(c: &mut Criterion) {
+    let text = document();
+    let mut group = c.benchmark_group(format!("{} bytes", text.len()));
+    group.sample_size(10);
+
+    group.bench_function("full parse", |b| {
+        b.iter(|| CheckpointIterator::new(text.chars()).parse_syntax_tree())
+    });
+
+    // Typing a character in a paragraph in the middle of the document, then deleting it
+    let offset = text.find("Section 6000\n\nSome").unwrap() + "Section 6000\n\nSome".len();
+    let insert = TextEdit::new(TextRange::new(offset, offset), "s");
+    let delete = TextEdit::new(TextRange::new(offset, offset + 1), "");
+    let inserted = format!("{}s{}", &text[..offset], &text[offset..]);
+    let mut tree = CheckpointIterator::new(text.chars())
+        .parse_syntax_tree()
+        .unwrap();
+    group.bench_function("reparse edit", |b| {
+        b.iter(|| {
+            reparse(&mut tree, &insert, &inserted).unwrap();
+            reparse(&mut tree, &delete, &text).unwrap();
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, incremental);
+criterion_main!(benches);
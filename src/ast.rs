@@ -1,99 +1,836 @@
+pub mod visit;
+pub mod visit_mut;
+
+use std::{borrow::Cow, collections::HashMap, fmt};
+
 use chrono::NaiveDate;
-use http::Uri;
 
-#[derive(Debug, Clone)]
-pub struct Document {
-    pub header: Option<DocumentHeader>,
-    pub content: DocumentContent,
+/// Text of the tree is borrowed from the parsed input where the parser allows it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document<'a> {
+    pub header: Option<DocumentHeader<'a>>,
+    pub content: DocumentContent<'a>,
 }
 
-#[derive(Debug, Clone)]
-pub struct DocumentHeader {
-    pub title: Option<SectionHeading>,
-    pub authors: Vec<Author>,
-    pub revision: Option<Revision>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentHeader<'a> {
+    pub title: Option<SectionHeading<'a>>,
+    pub authors: Vec<Author<'a>>,
+    pub revision: Option<Revision<'a>>,
+    /// Attribute entries of the header in order, the entries unsetting an attribute have no
+    /// value
+    pub attributes: Vec<Attribute<'a>>,
 }
 
-#[derive(Debug, Clone)]
-pub struct SectionHeading {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionHeading<'a> {
+    /// Number of `=` of the heading, one more than the section level
     pub level: usize,
-    pub text: String,
+    pub text: Cow<'a, str>,
+    /// Id of the section, explicit or generated from the text, see [`crate::sections`]
+    pub id: Option<Cow<'a, str>>,
+    /// Number of the section when `sectnums` is set, like `2.1`, or `A.1` in an appendix
+    pub number: Option<String>,
+}
+
+/// Type of a document, set by the `doctype` attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Doctype {
+    #[default]
+    Article,
+    Book,
+    Manpage,
+    Inline,
+}
+
+impl Doctype {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "article" => Self::Article,
+            "book" => Self::Book,
+            "manpage" => Self::Manpage,
+            "inline" => Self::Inline,
+            _ => return None,
+        })
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct Author {
-    pub first_name: String,
-    pub middle_name: Option<String>,
-    pub last_name: String,
-    pub email: Option<Uri>,
+/// How much a document may read outside of itself, set by the `safe-mode-name` attribute,
+/// from the least to the most restricted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum SafeMode {
+    Unsafe,
+    Safe,
+    Server,
+    #[default]
+    Secure,
 }
 
-#[derive(Debug, Clone)]
-pub struct Revision {
-    pub version: Vec<isize>,
-    pub date: Option<NaiveDate>,
-    pub remark: String,
+impl SafeMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "unsafe" => Self::Unsafe,
+            "safe" => Self::Safe,
+            "server" => Self::Server,
+            "secure" => Self::Secure,
+            _ => return None,
+        })
+    }
+}
+
+/// Placement of the table of contents, set by the `toc` attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TocPlacement {
+    /// Below the header, for an empty value or `auto`
+    Auto,
+    Left,
+    Right,
+    /// Between the preamble and the first section
+    Preamble,
+    /// Where the `toc::[]` macro is
+    Macro,
+}
+
+impl DocumentHeader<'_> {
+    /// Value of the attribute `name` after the entries of the header, an empty string for an
+    /// attribute set without a value
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .rfind(|a| a.key == name)
+            .and_then(|a| a.value.as_deref())
+    }
+
+    /// Values of the attributes after the entries of the header, by name
+    pub fn attribute_values(&self) -> HashMap<String, String> {
+        let mut values = HashMap::new();
+        for attribute in &self.attributes {
+            match &attribute.value {
+                Some(value) => values.insert(attribute.key.to_string(), value.to_string()),
+                None => values.remove(attribute.key.as_ref()),
+            };
+        }
+        values
+    }
+
+    pub fn doctype(&self) -> Doctype {
+        self.attribute("doctype")
+            .and_then(Doctype::from_name)
+            .unwrap_or_default()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.attribute("description").filter(|d| !d.is_empty())
+    }
+
+    /// The comma separated `keywords`
+    pub fn keywords(&self) -> Vec<&str> {
+        self.attribute("keywords")
+            .into_iter()
+            .flat_map(|keywords| keywords.split(','))
+            .map(str::trim)
+            .filter(|keyword| !keyword.is_empty())
+            .collect()
+    }
+
+    pub fn lang(&self) -> Option<&str> {
+        self.attribute("lang").filter(|lang| !lang.is_empty())
+    }
+
+    /// Placement of the table of contents, `None` when there is none
+    pub fn toc(&self) -> Option<TocPlacement> {
+        match self.attribute("toc")? {
+            "" | "auto" => Some(TocPlacement::Auto),
+            "left" => Some(TocPlacement::Left),
+            "right" => Some(TocPlacement::Right),
+            "preamble" => Some(TocPlacement::Preamble),
+            "macro" => Some(TocPlacement::Macro),
+            _ => None,
+        }
+    }
+
+    /// Title without its subtitle
+    pub fn main_title(&self) -> Option<&str> {
+        let title = &self.title.as_ref()?.text;
+        Some(title.rsplit_once(": ").map_or(title, |(main, _)| main))
+    }
+
+    /// Subtitle after the last `: ` of the title, like in `= Title: Subtitle`
+    pub fn subtitle(&self) -> Option<&str> {
+        let (_, subtitle) = self.title.as_ref()?.text.rsplit_once(": ")?;
+        Some(subtitle)
+    }
+
+    /// The `author`, `firstname`, `middlename`, `lastname`, `authorinitials` and `email`
+    /// attributes of the authors, suffixed by `_2`, `_3`, ... after the first one, and the
+    /// `authors` attribute listing their names
+    pub fn author_attributes(&self) -> HashMap<String, String> {
+        let mut attributes = HashMap::new();
+        for (i, author) in self.authors.iter().enumerate() {
+            let suffix = if i == 0 {
+                String::new()
+            } else {
+                format!("_{}", i + 1)
+            };
+            let mut insert = |name: &str, value: String| {
+                attributes.insert(format!("{name}{suffix}"), value);
+            };
+            insert("author", author.name());
+            insert("firstname", author.first_name.to_string());
+            if let Some(middle_name) = &author.middle_name {
+                insert("middlename", middle_name.to_string());
+            }
+            if let Some(last_name) = &author.last_name {
+                insert("lastname", last_name.to_string());
+            }
+            insert("authorinitials", author.initials());
+            if let Some(email) = &author.email {
+                insert("email", email.to_string());
+            }
+        }
+        if !self.authors.is_empty() {
+            let names: Vec<_> = self.authors.iter().map(Author::name).collect();
+            attributes.insert("authors".to_string(), names.join(", "));
+        }
+        attributes
+    }
+
+    /// The `revnumber`, `revdate` and `revremark` attributes of the revision
+    pub fn revision_attributes(&self) -> HashMap<String, String> {
+        let Some(revision) = &self.revision else {
+            return HashMap::new();
+        };
+        [
+            ("revnumber", &revision.number),
+            ("revdate", &revision.date),
+            ("revremark", &revision.remark),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.as_ref()?.to_string())))
+        .collect()
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct DocumentContent {
-    pub blocks: Vec<Block>,
+/// Author of a document, a name that can't be split into at most three parts is kept whole as
+/// the first name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Author<'a> {
+    pub first_name: Cow<'a, str>,
+    pub middle_name: Option<Cow<'a, str>>,
+    pub last_name: Option<Cow<'a, str>>,
+    pub email: Option<Email<'a>>,
 }
 
-#[derive(Debug, Clone)]
-pub struct Block {
-    pub heading: SectionHeading,
-    pub attributes: Vec<Attribute>,
-    pub content: BlockContent,
+impl Author<'_> {
+    /// Full name of the author
+    pub fn name(&self) -> String {
+        [
+            Some(&self.first_name),
+            self.middle_name.as_ref(),
+            self.last_name.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(AsRef::as_ref)
+        .collect::<Vec<&str>>()
+        .join(" ")
+    }
+
+    /// First letters of the first, middle and last names
+    pub fn initials(&self) -> String {
+        [
+            Some(&self.first_name),
+            self.middle_name.as_ref(),
+            self.last_name.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(|name| name.chars().next())
+        .collect()
+    }
+}
+
+/// Email address, split at its last `@`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Email<'a> {
+    pub local_part: Cow<'a, str>,
+    pub domain: Cow<'a, str>,
+}
+
+impl<'a> Email<'a> {
+    /// Parses an address made of a dot separated local part and a domain of dot separated
+    /// labels, quoted local parts and IP address literals are not supported
+    pub fn parse(address: &'a str) -> Option<Self> {
+        let (local_part, domain) = address.rsplit_once('@')?;
+        let atext = |c: char| c.is_alphanumeric() || "!#$%&'*+/=?^_`{|}~-".contains(c);
+        if local_part
+            .split('.')
+            .any(|atom| atom.is_empty() || !atom.chars().all(atext))
+        {
+            return None;
+        }
+        if domain.split('.').any(|label| {
+            label.is_empty()
+                || label.starts_with('-')
+                || label.ends_with('-')
+                || !label.chars().all(|c| c.is_alphanumeric() || c == '-')
+        }) {
+            return None;
+        }
+        Some(Self {
+            local_part: local_part.into(),
+            domain: domain.into(),
+        })
+    }
+}
+
+impl fmt::Display for Email<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.local_part, self.domain)
+    }
+}
+
+/// Revision of a document, from the revision line or the `revnumber`, `revdate` and
+/// `revremark` attributes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Revision<'a> {
+    /// Revision number without the `v` prefix, like `1.0-rc.2`
+    pub number: Option<Cow<'a, str>>,
+    /// Date as written
+    pub date: Option<Cow<'a, str>>,
+    /// The date if it is in one of the formats of [`Revision::parse_date`]
+    pub parsed_date: Option<NaiveDate>,
+    pub remark: Option<Cow<'a, str>>,
+}
+
+impl Revision<'_> {
+    /// Parses an ISO 8601 date like `2020-01-31`, or a date written like `January 31, 2020`,
+    /// `31 January 2020` or `Jan 31, 2020`
+    pub fn parse_date(date: &str) -> Option<NaiveDate> {
+        ["%Y-%m-%d", "%B %d, %Y", "%d %B %Y", "%b %d, %Y", "%d %b %Y"]
+            .into_iter()
+            .find_map(|format| NaiveDate::parse_from_str(date.trim(), format).ok())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentContent<'a> {
+    pub blocks: Vec<Block<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block<'a> {
+    pub heading: Option<SectionHeading<'a>>,
+    pub title: Option<Cow<'a, str>>,
+    pub attributes: Vec<Attribute<'a>>,
+    pub content: BlockContent<'a>,
+}
+
+impl Block<'_> {
+    /// Style of the block, the first positional attribute without its `#id`, `.role` and
+    /// `%option` shorthands
+    pub fn style(&self) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|a| a.value.is_none())
+            .and_then(|a| a.key.split(['#', '.', '%']).next())
+            .filter(|style| !style.is_empty())
+    }
+
+    /// Id of the block, from an `id` attribute, a `[[id]]` anchor or an `#id` shorthand, the
+    /// last one wins
+    pub fn id(&self) -> Option<&str> {
+        self.attributes
+            .iter()
+            .rev()
+            .find_map(|a| match &a.value {
+                Some(value) if a.key == "id" => Some(value.as_ref()),
+                Some(_) => None,
+                None => a
+                    .key
+                    .split_once('#')
+                    .and_then(|(_, id)| id.split(['.', '%']).next()),
+            })
+            .filter(|id| !id.is_empty())
+    }
+
+    /// Numbering of an ordered list, from the style, `start` attribute and `reversed` option
+    /// of the block or else from the marker of its first item
+    pub fn list_numbering(&self) -> Option<ListNumbering> {
+        let BlockContent::List(items) = &self.content else {
+            return None;
+        };
+        let Some(ListContent::OrderedList(first)) = items.first() else {
+            return None;
+        };
+        let start = self
+            .attributes
+            .iter()
+            .find(|a| a.key == "start")
+            .and_then(|a| a.value.as_deref()?.trim().parse().ok());
+        Some(ListNumbering {
+            style: self
+                .style()
+                .and_then(NumberingStyle::from_name)
+                .unwrap_or(first.style),
+            start: start.or(first.number).unwrap_or(1),
+            reversed: self.has_option("reversed"),
+        })
+    }
+
+    /// Kind of a section, a part for a level 0 heading and a special section for some styles
+    pub fn section_kind(&self) -> Option<SectionKind> {
+        let BlockContent::Section(_) = &self.content else {
+            return None;
+        };
+        // A section without a heading is never a part
+        let level = self
+            .heading
+            .as_ref()
+            .map_or(1, |heading| heading.level.saturating_sub(1));
+        Some(SectionKind::new(self.style(), level))
+    }
+
+    /// Whether the option `name` is set, by a `%name` shorthand, an `options` attribute or a
+    /// `name-option` attribute
+    pub fn has_option(&self, name: &str) -> bool {
+        self.attributes
+            .iter()
+            .any(|a| match (a.key.as_ref(), &a.value) {
+                ("options" | "opts", Some(value)) => value.split(',').any(|o| o.trim() == name),
+                (key, Some(_)) => key.strip_suffix("-option") == Some(name),
+                (key, None) => key
+                    .split('%')
+                    .skip(1)
+                    .any(|option| option.split(['#', '.']).next() == Some(name)),
+            })
+    }
 }
 
 // TODO: Make this an enum
-#[derive(Debug, Clone)]
-pub struct Attribute {
-    pub key: String,
-    pub value: Option<String>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute<'a> {
+    pub key: Cow<'a, str>,
+    pub value: Option<Cow<'a, str>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockContent<'a> {
+    List(Vec<ListContent<'a>>),
+    Section(Vec<SectionContent<'a>>),
+    Delimited(DelimitedBlockKind, Vec<DelimitedBlockContent<'a>>),
+    Undelimited(Vec<UndelimitedBlockContent<'a>>),
+    Break(BreakKind),
+    Macro(BlockMacro<'a>),
 }
 
-#[derive(Debug, Clone)]
-pub enum BlockContent {
-    List(Vec<ListContent>),
-    Section(Vec<SectionContent>),
-    Delimited(Vec<DelimitedBlockContent>),
-    Undelimited(Vec<UndelimitedBlockContent>),
+/// `name::target[attributes]` block macro, like `image::diagram.png[Diagram]` or `toc::[]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockMacro<'a> {
+    pub name: Cow<'a, str>,
+    pub target: Cow<'a, str>,
+    /// Attributes between the brackets, kept apart from the attributes of the block
+    pub attributes: Vec<Attribute<'a>>,
 }
 
-#[derive(Debug, Clone)]
-pub enum ListContent {
-    UnorderedList(UnorderedListContent),
-    OrderedList(OrderedListContent),
+/// Kind of a section, see [`Block::section_kind`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionKind {
+    /// Section of a book with a level 0 heading, grouping chapters
+    Part,
+    Section,
+    Abstract,
+    Appendix,
+    Bibliography,
+    Colophon,
+    Dedication,
+    Glossary,
+    Index,
+    /// Introduction of a part, before its chapters
+    PartIntro,
+    Preface,
 }
 
-#[derive(Debug, Clone)]
-pub struct UnorderedListContent {
-    pub text: String,
-    pub sublist: Vec<ListContent>,
+impl SectionKind {
+    /// Kind of a section with the `style` at the section `level`, one less than the number of
+    /// `=` of its heading. A level 0 section without a special section style is a part.
+    pub fn new(style: Option<&str>, level: usize) -> Self {
+        match style.and_then(Self::from_style) {
+            Some(kind) => kind,
+            None if level == 0 => Self::Part,
+            None => Self::Section,
+        }
+    }
+
+    /// The special section of the `style`
+    pub fn from_style(style: &str) -> Option<Self> {
+        Some(match style {
+            "abstract" => Self::Abstract,
+            "appendix" => Self::Appendix,
+            "bibliography" => Self::Bibliography,
+            "colophon" => Self::Colophon,
+            "dedication" => Self::Dedication,
+            "glossary" => Self::Glossary,
+            "index" => Self::Index,
+            "partintro" => Self::PartIntro,
+            "preface" => Self::Preface,
+            _ => return None,
+        })
+    }
+
+    /// Whether the section is one of the special sections
+    pub fn is_special(self) -> bool {
+        !matches!(self, Self::Part | Self::Section)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelimitedBlockKind {
+    Example,
+    Listing,
+    Literal,
+    Sidebar,
+    Quote,
+    Passthrough,
+    Comment,
+    Open,
+}
+
+impl DelimitedBlockKind {
+    /// Verbatim blocks hold their lines as is, instead of nested blocks
+    pub fn is_verbatim(&self) -> bool {
+        matches!(
+            self,
+            Self::Listing | Self::Literal | Self::Passthrough | Self::Comment
+        )
+    }
+}
+
+/// A line on its own breaking the flow of the blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakKind {
+    /// `'''`, or `---` and `***` like in Markdown
+    Thematic,
+    /// `<<<`
+    Page,
+}
+
+impl BreakKind {
+    /// A rule, or an empty block the page breaks after when printed
+    pub fn to_html(self) -> &'static str {
+        match self {
+            Self::Thematic => "<hr>\n",
+            Self::Page => "<div style=\"page-break-after: always;\"></div>\n",
+        }
+    }
+
+    /// The processing instructions the DocBook stylesheets turn into a rule or a page break
+    pub fn to_docbook(self) -> &'static str {
+        match self {
+            Self::Thematic => "<simpara><?asciidoc-hr?></simpara>\n",
+            Self::Page => "<simpara><?asciidoc-pagebreak?></simpara>\n",
+        }
+    }
+
+    /// A rule across the line length, or the `.bp` request starting a new page
+    pub fn to_roff(self) -> &'static str {
+        match self {
+            Self::Thematic => ".sp\n\\l'\\n(.lu'\n.sp\n",
+            Self::Page => ".bp\n",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListKind {
+    Unordered,
+    Ordered,
+    /// `term:: description`, with `:::`, `::::` and `;;` for the nested lists
+    Description,
 }
 
-#[derive(Debug, Clone)]
-pub struct OrderedListContent {
-    pub text: String,
-    pub sublist: Vec<ListContent>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListContent<'a> {
+    UnorderedList(UnorderedListContent<'a>),
+    OrderedList(OrderedListContent<'a>),
+    DescriptionList(DescriptionListContent<'a>),
 }
 
-#[derive(Debug, Clone)]
-pub enum SectionContent {
-    Text(String),
-    Block(Block),
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnorderedListContent<'a> {
+    /// State of the `[x]` or `[ ]` checkbox of a checklist item
+    pub checked: Option<bool>,
+    pub text: Cow<'a, str>,
+    /// Blocks attached to the item by `+` continuation lines
+    pub blocks: Vec<Block<'a>>,
+    pub sublist: Vec<ListContent<'a>>,
 }
 
-#[derive(Debug, Clone)]
-pub enum DelimitedBlockContent {
-    Text(String),
-    Block(Block),
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderedListContent<'a> {
+    /// Style of the numbering given by the marker of the item
+    pub style: NumberingStyle,
+    /// Number of an explicit numeral like `2.`, `b.` or `ii)`
+    pub number: Option<u32>,
+    pub text: Cow<'a, str>,
+    /// Blocks attached to the item by `+` continuation lines
+    pub blocks: Vec<Block<'a>>,
+    pub sublist: Vec<ListContent<'a>>,
 }
 
-#[derive(Debug, Clone)]
-pub enum UndelimitedBlockContent {
-    Text(String),
-    Block(Block),
+/// Style of the numbering of an ordered list, named like the style of its block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberingStyle {
+    Arabic,
+    Decimal,
+    LowerAlpha,
+    UpperAlpha,
+    LowerRoman,
+    UpperRoman,
+    LowerGreek,
+}
+
+impl NumberingStyle {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "arabic" => Self::Arabic,
+            "decimal" => Self::Decimal,
+            "loweralpha" => Self::LowerAlpha,
+            "upperalpha" => Self::UpperAlpha,
+            "lowerroman" => Self::LowerRoman,
+            "upperroman" => Self::UpperRoman,
+            "lowergreek" => Self::LowerGreek,
+            _ => return None,
+        })
+    }
+
+    /// Style of the lists with implicit numerals at `depth`, from 1 for `.` to 5 for `.....`
+    pub fn for_depth(depth: usize) -> Self {
+        match depth {
+            2 => Self::LowerAlpha,
+            3 => Self::LowerRoman,
+            4 => Self::UpperAlpha,
+            5 => Self::UpperRoman,
+            _ => Self::Arabic,
+        }
+    }
+
+    /// Value of the `type` attribute of an HTML `<ol>`, Greek letters need CSS instead
+    pub fn html_type(self) -> Option<&'static str> {
+        match self {
+            Self::Arabic | Self::Decimal => Some("1"),
+            Self::LowerAlpha => Some("a"),
+            Self::UpperAlpha => Some("A"),
+            Self::LowerRoman => Some("i"),
+            Self::UpperRoman => Some("I"),
+            Self::LowerGreek => None,
+        }
+    }
+
+    /// The numeral of `number` in this style, letters wrap around after `z`
+    pub fn format(self, number: u32) -> String {
+        let letter = |first: u8, count: u8| {
+            let offset = (number.max(1) - 1) % count as u32;
+            char::from_u32(first as u32 + offset).map_or_else(String::new, String::from)
+        };
+        match self {
+            Self::Arabic => number.to_string(),
+            Self::Decimal => format!("{number:02}"),
+            Self::LowerAlpha => letter(b'a', 26),
+            Self::UpperAlpha => letter(b'A', 26),
+            Self::LowerRoman => roman(number).to_lowercase(),
+            Self::UpperRoman => roman(number),
+            Self::LowerGreek => {
+                let offset = (number.max(1) - 1) % 24;
+                char::from_u32(0x3b1 + offset + u32::from(offset >= 17))
+                    .map_or_else(String::new, String::from)
+            }
+        }
+    }
+}
+
+fn roman(number: u32) -> String {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut number = number;
+    let mut result = String::new();
+    for (value, numeral) in NUMERALS {
+        while number >= value {
+            result.push_str(numeral);
+            number -= value;
+        }
+    }
+    result
+}
+
+/// Numbering of an ordered list block, see [`Block::list_numbering`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListNumbering {
+    pub style: NumberingStyle,
+    pub start: u32,
+    pub reversed: bool,
+}
+
+/// Item of a description list, the style of the list like `horizontal` or `qanda` is kept
+/// in the attributes of its block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescriptionListContent<'a> {
+    pub term: Cow<'a, str>,
+    /// Text of the description, empty when it only has blocks or a nested list
+    pub text: Cow<'a, str>,
+    /// Blocks attached to the description by `+` continuation lines
+    pub blocks: Vec<Block<'a>>,
+    pub sublist: Vec<ListContent<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SectionContent<'a> {
+    Text(Cow<'a, str>),
+    Block(Block<'a>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DelimitedBlockContent<'a> {
+    Text(Cow<'a, str>),
+    Block(Block<'a>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UndelimitedBlockContent<'a> {
+    Text(Cow<'a, str>),
+    Block(Block<'a>),
+}
+
+/// Inline content of a text, see [`crate::subs::inlines`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inline<'a> {
+    Text(Cow<'a, str>),
+    Replacement(Replacement),
+    CharacterReference(CharacterReference<'a>),
+    /// ` +` at the end of a line
+    HardBreak,
+}
+
+/// Characters written in place of a typographic symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Replacement {
+    /// `(C)`
+    Copyright,
+    /// `(R)`
+    Registered,
+    /// `(TM)`
+    Trademark,
+    /// `--` between words, or set off by spaces when `spaced`
+    EmDash { spaced: bool },
+    /// `...`
+    Ellipsis,
+    /// `->`
+    RightArrow,
+    /// `=>`
+    RightDoubleArrow,
+    /// `<-`
+    LeftArrow,
+    /// `<=`
+    LeftDoubleArrow,
+    /// `'` between letters
+    Apostrophe,
+}
+
+impl Replacement {
+    /// The symbol, an em dash between words is followed by a zero width space so lines can
+    /// break after it
+    pub fn text(self) -> &'static str {
+        match self {
+            Self::Copyright => "\u{a9}",
+            Self::Registered => "\u{ae}",
+            Self::Trademark => "\u{2122}",
+            Self::EmDash { spaced: true } => "\u{2009}\u{2014}\u{2009}",
+            Self::EmDash { spaced: false } => "\u{2014}\u{200b}",
+            Self::Ellipsis => "\u{2026}\u{200b}",
+            Self::RightArrow => "\u{2192}",
+            Self::RightDoubleArrow => "\u{21d2}",
+            Self::LeftArrow => "\u{2190}",
+            Self::LeftDoubleArrow => "\u{21d0}",
+            Self::Apostrophe => "\u{2019}",
+        }
+    }
+
+    /// The symbol as HTML character references
+    pub fn entity(self) -> &'static str {
+        match self {
+            Self::Copyright => "&#169;",
+            Self::Registered => "&#174;",
+            Self::Trademark => "&#8482;",
+            Self::EmDash { spaced: true } => "&#8201;&#8212;&#8201;",
+            Self::EmDash { spaced: false } => "&#8212;&#8203;",
+            Self::Ellipsis => "&#8230;&#8203;",
+            Self::RightArrow => "&#8594;",
+            Self::RightDoubleArrow => "&#8658;",
+            Self::LeftArrow => "&#8592;",
+            Self::LeftDoubleArrow => "&#8656;",
+            Self::Apostrophe => "&#8217;",
+        }
+    }
+}
+
+/// Character reference written in the text, which is kept as is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CharacterReference<'a> {
+    /// `&amp;`
+    Named(Cow<'a, str>),
+    /// `&#169;`
+    Decimal(u32),
+    /// `&#xA9;`
+    Hexadecimal(u32),
+}
+
+impl CharacterReference<'_> {
+    /// The reference as written in HTML
+    pub fn entity(&self) -> String {
+        match self {
+            Self::Named(name) => format!("&{name};"),
+            Self::Decimal(code) => format!("&#{code};"),
+            Self::Hexadecimal(code) => format!("&#x{code:X};"),
+        }
+    }
+
+    /// The referenced character, only the most common names are known
+    pub fn char(&self) -> Option<char> {
+        match self {
+            Self::Named(name) => Some(match name.as_ref() {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                "copy" => '\u{a9}',
+                "reg" => '\u{ae}',
+                "trade" => '\u{2122}',
+                "deg" => '\u{b0}',
+                "hellip" => '\u{2026}',
+                "mdash" => '\u{2014}',
+                "ndash" => '\u{2013}',
+                "lsquo" => '\u{2018}',
+                "rsquo" => '\u{2019}',
+                "ldquo" => '\u{201c}',
+                "rdquo" => '\u{201d}',
+                _ => return None,
+            }),
+            Self::Decimal(code) | Self::Hexadecimal(code) => char::from_u32(*code),
+        }
+    }
 }
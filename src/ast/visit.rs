@@ -0,0 +1,231 @@
+use super::{
+    Attribute, Author, Block, BlockContent, BlockMacro, DelimitedBlockContent,
+    DescriptionListContent, Document, DocumentContent, DocumentHeader, ListContent,
+    OrderedListContent, Revision, SectionContent, SectionHeading, UndelimitedBlockContent,
+    UnorderedListContent,
+};
+
+/// Traversal of the syntax tree by reference
+///
+/// Each `visit_*` method defaults to the matching `walk_*` function, which visits the
+/// children of the node. Calling the `walk_*` function from an overridden method keeps
+/// the traversal going below the node.
+pub trait Visitor {
+    fn visit_document(&mut self, document: &Document<'_>) {
+        walk_document(self, document);
+    }
+
+    fn visit_document_header(&mut self, header: &DocumentHeader<'_>) {
+        walk_document_header(self, header);
+    }
+
+    fn visit_section_heading(&mut self, heading: &SectionHeading<'_>) {
+        walk_section_heading(self, heading);
+    }
+
+    fn visit_author(&mut self, _author: &Author<'_>) {}
+
+    fn visit_revision(&mut self, _revision: &Revision<'_>) {}
+
+    fn visit_document_content(&mut self, content: &DocumentContent<'_>) {
+        walk_document_content(self, content);
+    }
+
+    fn visit_block(&mut self, block: &Block<'_>) {
+        walk_block(self, block);
+    }
+
+    fn visit_attribute(&mut self, _attribute: &Attribute<'_>) {}
+
+    fn visit_block_content(&mut self, content: &BlockContent<'_>) {
+        walk_block_content(self, content);
+    }
+
+    fn visit_block_macro(&mut self, block_macro: &BlockMacro<'_>) {
+        walk_block_macro(self, block_macro);
+    }
+
+    fn visit_list_content(&mut self, content: &ListContent<'_>) {
+        walk_list_content(self, content);
+    }
+
+    fn visit_unordered_list_content(&mut self, content: &UnorderedListContent<'_>) {
+        walk_unordered_list_content(self, content);
+    }
+
+    fn visit_ordered_list_content(&mut self, content: &OrderedListContent<'_>) {
+        walk_ordered_list_content(self, content);
+    }
+
+    fn visit_description_list_content(&mut self, content: &DescriptionListContent<'_>) {
+        walk_description_list_content(self, content);
+    }
+
+    fn visit_section_content(&mut self, content: &SectionContent<'_>) {
+        walk_section_content(self, content);
+    }
+
+    fn visit_delimited_block_content(&mut self, content: &DelimitedBlockContent<'_>) {
+        walk_delimited_block_content(self, content);
+    }
+
+    fn visit_undelimited_block_content(&mut self, content: &UndelimitedBlockContent<'_>) {
+        walk_undelimited_block_content(self, content);
+    }
+
+    /// Text of headings, titles, list items and blocks
+    fn visit_text(&mut self, _text: &str) {}
+}
+
+pub fn walk_document<V: Visitor + ?Sized>(visitor: &mut V, document: &Document<'_>) {
+    if let Some(header) = &document.header {
+        visitor.visit_document_header(header);
+    }
+    visitor.visit_document_content(&document.content);
+}
+
+pub fn walk_document_header<V: Visitor + ?Sized>(visitor: &mut V, header: &DocumentHeader<'_>) {
+    if let Some(title) = &header.title {
+        visitor.visit_section_heading(title);
+    }
+    for author in &header.authors {
+        visitor.visit_author(author);
+    }
+    for attribute in &header.attributes {
+        visitor.visit_attribute(attribute);
+    }
+    if let Some(revision) = &header.revision {
+        visitor.visit_revision(revision);
+    }
+}
+
+pub fn walk_section_heading<V: Visitor + ?Sized>(visitor: &mut V, heading: &SectionHeading<'_>) {
+    visitor.visit_text(&heading.text);
+}
+
+pub fn walk_document_content<V: Visitor + ?Sized>(visitor: &mut V, content: &DocumentContent<'_>) {
+    for block in &content.blocks {
+        visitor.visit_block(block);
+    }
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, block: &Block<'_>) {
+    if let Some(heading) = &block.heading {
+        visitor.visit_section_heading(heading);
+    }
+    if let Some(title) = &block.title {
+        visitor.visit_text(title);
+    }
+    for attribute in &block.attributes {
+        visitor.visit_attribute(attribute);
+    }
+    visitor.visit_block_content(&block.content);
+}
+
+pub fn walk_block_content<V: Visitor + ?Sized>(visitor: &mut V, content: &BlockContent<'_>) {
+    match content {
+        BlockContent::List(items) => {
+            for item in items {
+                visitor.visit_list_content(item);
+            }
+        }
+        BlockContent::Section(content) => {
+            for content in content {
+                visitor.visit_section_content(content);
+            }
+        }
+        BlockContent::Delimited(_, content) => {
+            for content in content {
+                visitor.visit_delimited_block_content(content);
+            }
+        }
+        BlockContent::Undelimited(content) => {
+            for content in content {
+                visitor.visit_undelimited_block_content(content);
+            }
+        }
+        BlockContent::Break(_) => {}
+        BlockContent::Macro(block_macro) => visitor.visit_block_macro(block_macro),
+    }
+}
+
+pub fn walk_block_macro<V: Visitor + ?Sized>(visitor: &mut V, block_macro: &BlockMacro<'_>) {
+    for attribute in &block_macro.attributes {
+        visitor.visit_attribute(attribute);
+    }
+}
+
+pub fn walk_list_content<V: Visitor + ?Sized>(visitor: &mut V, content: &ListContent<'_>) {
+    match content {
+        ListContent::UnorderedList(item) => visitor.visit_unordered_list_content(item),
+        ListContent::OrderedList(item) => visitor.visit_ordered_list_content(item),
+        ListContent::DescriptionList(item) => visitor.visit_description_list_content(item),
+    }
+}
+
+pub fn walk_unordered_list_content<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    content: &UnorderedListContent<'_>,
+) {
+    visitor.visit_text(&content.text);
+    for block in &content.blocks {
+        visitor.visit_block(block);
+    }
+    for item in &content.sublist {
+        visitor.visit_list_content(item);
+    }
+}
+
+pub fn walk_ordered_list_content<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    content: &OrderedListContent<'_>,
+) {
+    visitor.visit_text(&content.text);
+    for block in &content.blocks {
+        visitor.visit_block(block);
+    }
+    for item in &content.sublist {
+        visitor.visit_list_content(item);
+    }
+}
+
+pub fn walk_description_list_content<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    content: &DescriptionListContent<'_>,
+) {
+    visitor.visit_text(&content.term);
+    visitor.visit_text(&content.text);
+    for block in &content.blocks {
+        visitor.visit_block(block);
+    }
+    for item in &content.sublist {
+        visitor.visit_list_content(item);
+    }
+}
+
+pub fn walk_section_content<V: Visitor + ?Sized>(visitor: &mut V, content: &SectionContent<'_>) {
+    match content {
+        SectionContent::Text(text) => visitor.visit_text(text),
+        SectionContent::Block(block) => visitor.visit_block(block),
+    }
+}
+
+pub fn walk_delimited_block_content<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    content: &DelimitedBlockContent<'_>,
+) {
+    match content {
+        DelimitedBlockContent::Text(text) => visitor.visit_text(text),
+        DelimitedBlockContent::Block(block) => visitor.visit_block(block),
+    }
+}
+
+pub fn walk_undelimited_block_content<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    content: &UndelimitedBlockContent<'_>,
+) {
+    match content {
+        UndelimitedBlockContent::Text(text) => visitor.visit_text(text),
+        UndelimitedBlockContent::Block(block) => visitor.visit_block(block),
+    }
+}
@@ -0,0 +1,254 @@
+use std::borrow::Cow;
+
+use super::{
+    Attribute, Author, Block, BlockContent, BlockMacro, DelimitedBlockContent,
+    DescriptionListContent, Document, DocumentContent, DocumentHeader, ListContent,
+    OrderedListContent, Revision, SectionContent, SectionHeading, UndelimitedBlockContent,
+    UnorderedListContent,
+};
+
+/// Traversal of the syntax tree by mutable reference, to transform it in place
+///
+/// Each `visit_*_mut` method defaults to the matching `walk_*_mut` function, which visits the
+/// children of the node. Calling the `walk_*_mut` function from an overridden method keeps
+/// the traversal going below the node.
+pub trait VisitorMut {
+    fn visit_document_mut(&mut self, document: &mut Document<'_>) {
+        walk_document_mut(self, document);
+    }
+
+    fn visit_document_header_mut(&mut self, header: &mut DocumentHeader<'_>) {
+        walk_document_header_mut(self, header);
+    }
+
+    fn visit_section_heading_mut(&mut self, heading: &mut SectionHeading<'_>) {
+        walk_section_heading_mut(self, heading);
+    }
+
+    fn visit_author_mut(&mut self, _author: &mut Author<'_>) {}
+
+    fn visit_revision_mut(&mut self, _revision: &mut Revision<'_>) {}
+
+    fn visit_document_content_mut(&mut self, content: &mut DocumentContent<'_>) {
+        walk_document_content_mut(self, content);
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block<'_>) {
+        walk_block_mut(self, block);
+    }
+
+    fn visit_attribute_mut(&mut self, _attribute: &mut Attribute<'_>) {}
+
+    fn visit_block_content_mut(&mut self, content: &mut BlockContent<'_>) {
+        walk_block_content_mut(self, content);
+    }
+
+    fn visit_block_macro_mut(&mut self, block_macro: &mut BlockMacro<'_>) {
+        walk_block_macro_mut(self, block_macro);
+    }
+
+    fn visit_list_content_mut(&mut self, content: &mut ListContent<'_>) {
+        walk_list_content_mut(self, content);
+    }
+
+    fn visit_unordered_list_content_mut(&mut self, content: &mut UnorderedListContent<'_>) {
+        walk_unordered_list_content_mut(self, content);
+    }
+
+    fn visit_ordered_list_content_mut(&mut self, content: &mut OrderedListContent<'_>) {
+        walk_ordered_list_content_mut(self, content);
+    }
+
+    fn visit_description_list_content_mut(&mut self, content: &mut DescriptionListContent<'_>) {
+        walk_description_list_content_mut(self, content);
+    }
+
+    fn visit_section_content_mut(&mut self, content: &mut SectionContent<'_>) {
+        walk_section_content_mut(self, content);
+    }
+
+    fn visit_delimited_block_content_mut(&mut self, content: &mut DelimitedBlockContent<'_>) {
+        walk_delimited_block_content_mut(self, content);
+    }
+
+    fn visit_undelimited_block_content_mut(&mut self, content: &mut UndelimitedBlockContent<'_>) {
+        walk_undelimited_block_content_mut(self, content);
+    }
+
+    /// Text of headings, titles, list items and blocks
+    fn visit_text_mut(&mut self, _text: &mut Cow<'_, str>) {}
+}
+
+pub fn walk_document_mut<V: VisitorMut + ?Sized>(visitor: &mut V, document: &mut Document<'_>) {
+    if let Some(header) = &mut document.header {
+        visitor.visit_document_header_mut(header);
+    }
+    visitor.visit_document_content_mut(&mut document.content);
+}
+
+pub fn walk_document_header_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    header: &mut DocumentHeader<'_>,
+) {
+    if let Some(title) = &mut header.title {
+        visitor.visit_section_heading_mut(title);
+    }
+    for author in &mut header.authors {
+        visitor.visit_author_mut(author);
+    }
+    for attribute in &mut header.attributes {
+        visitor.visit_attribute_mut(attribute);
+    }
+    if let Some(revision) = &mut header.revision {
+        visitor.visit_revision_mut(revision);
+    }
+}
+
+pub fn walk_section_heading_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    heading: &mut SectionHeading<'_>,
+) {
+    visitor.visit_text_mut(&mut heading.text);
+}
+
+pub fn walk_document_content_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    content: &mut DocumentContent<'_>,
+) {
+    for block in &mut content.blocks {
+        visitor.visit_block_mut(block);
+    }
+}
+
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(visitor: &mut V, block: &mut Block<'_>) {
+    if let Some(heading) = &mut block.heading {
+        visitor.visit_section_heading_mut(heading);
+    }
+    if let Some(title) = &mut block.title {
+        visitor.visit_text_mut(title);
+    }
+    for attribute in &mut block.attributes {
+        visitor.visit_attribute_mut(attribute);
+    }
+    visitor.visit_block_content_mut(&mut block.content);
+}
+
+pub fn walk_block_content_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    content: &mut BlockContent<'_>,
+) {
+    match content {
+        BlockContent::List(items) => {
+            for item in items {
+                visitor.visit_list_content_mut(item);
+            }
+        }
+        BlockContent::Section(content) => {
+            for content in content {
+                visitor.visit_section_content_mut(content);
+            }
+        }
+        BlockContent::Delimited(_, content) => {
+            for content in content {
+                visitor.visit_delimited_block_content_mut(content);
+            }
+        }
+        BlockContent::Undelimited(content) => {
+            for content in content {
+                visitor.visit_undelimited_block_content_mut(content);
+            }
+        }
+        BlockContent::Break(_) => {}
+        BlockContent::Macro(block_macro) => visitor.visit_block_macro_mut(block_macro),
+    }
+}
+
+pub fn walk_block_macro_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    block_macro: &mut BlockMacro<'_>,
+) {
+    for attribute in &mut block_macro.attributes {
+        visitor.visit_attribute_mut(attribute);
+    }
+}
+
+pub fn walk_list_content_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    content: &mut ListContent<'_>,
+) {
+    match content {
+        ListContent::UnorderedList(item) => visitor.visit_unordered_list_content_mut(item),
+        ListContent::OrderedList(item) => visitor.visit_ordered_list_content_mut(item),
+        ListContent::DescriptionList(item) => visitor.visit_description_list_content_mut(item),
+    }
+}
+
+pub fn walk_unordered_list_content_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    content: &mut UnorderedListContent<'_>,
+) {
+    visitor.visit_text_mut(&mut content.text);
+    for block in &mut content.blocks {
+        visitor.visit_block_mut(block);
+    }
+    for item in &mut content.sublist {
+        visitor.visit_list_content_mut(item);
+    }
+}
+
+pub fn walk_ordered_list_content_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    content: &mut OrderedListContent<'_>,
+) {
+    visitor.visit_text_mut(&mut content.text);
+    for block in &mut content.blocks {
+        visitor.visit_block_mut(block);
+    }
+    for item in &mut content.sublist {
+        visitor.visit_list_content_mut(item);
+    }
+}
+
+pub fn walk_description_list_content_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    content: &mut DescriptionListContent<'_>,
+) {
+    visitor.visit_text_mut(&mut content.term);
+    visitor.visit_text_mut(&mut content.text);
+    for block in &mut content.blocks {
+        visitor.visit_block_mut(block);
+    }
+    for item in &mut content.sublist {
+        visitor.visit_list_content_mut(item);
+    }
+}
+
+pub fn walk_section_content_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    content: &mut SectionContent<'_>,
+) {
+    match content {
+        SectionContent::Text(text) => visitor.visit_text_mut(text),
+        SectionContent::Block(block) => visitor.visit_block_mut(block),
+    }
+}
+
+pub fn walk_delimited_block_content_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    content: &mut DelimitedBlockContent<'_>,
+) {
+    match content {
+        DelimitedBlockContent::Text(text) => visitor.visit_text_mut(text),
+        DelimitedBlockContent::Block(block) => visitor.visit_block_mut(block),
+    }
+}
+
+pub fn walk_undelimited_block_content_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    content: &mut UndelimitedBlockContent<'_>,
+) {
+    match content {
+        UndelimitedBlockContent::Text(text) => visitor.visit_text_mut(text),
+        UndelimitedBlockContent::Block(block) => visitor.visit_block_mut(block),
+    }
+}
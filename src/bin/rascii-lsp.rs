@@ -0,0 +1,11 @@
+use std::error::Error;
+
+use lsp_server::Connection;
+
+fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    pretty_env_logger::init();
+    let (connection, io_threads) = Connection::stdio();
+    rascii::lsp::run(connection)?;
+    io_threads.join()?;
+    Ok(())
+}
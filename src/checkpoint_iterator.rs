@@ -1,69 +1,103 @@
-use std::fmt::Debug;
-use std::{collections::VecDeque, fmt::Display};
+use std::{collections::HashMap, mem, rc::Rc};
 
-use crate::parser::ParseError;
+use crate::{
+    cst::SyntaxNode,
+    parser::{ParseError, Rule},
+};
 
-pub struct CheckpointIterator<T: Iterator> {
+/// Iterator over the chars of a text which can go back to the positions it was at
+///
+/// The chars read from the inner iterator are kept in a buffer, so checkpoints are just byte
+/// offsets into it. The text before the current position is dropped once no checkpoint refers
+/// to it anymore, which keeps the memory bounded when parsing a stream.
+pub struct CheckpointIterator<T: Iterator<Item = char>> {
     inner: T,
-    stack: Vec<(usize, Vec<T::Item>)>,
-    buf: VecDeque<T::Item>,
-    current_pos: usize,
+    buf: String,
+    /// Byte offset of the start of `buf` in the text
+    base: usize,
+    /// Byte offset of the current position in `buf`
+    offset: usize,
+    stack: Vec<usize>,
+    memo: Option<Memo>,
+    stats: [RuleStats; Rule::ALL.len()],
 }
 
+/// How often a [`Rule`] was attempted, see [`CheckpointIterator::rule_stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuleStats {
+    pub attempts: usize,
+    /// Attempts which failed and rewound the iterator
+    pub backtracks: usize,
+    /// Attempts answered by the memo table without parsing, successful or not. Successes share
+    /// their node with the tree instead of copying it
+    pub memo_hits: usize,
+}
+
+/// Results of the rules attempted at each position, with the position they end at. The nodes
+/// are shared with the tree, so answering from the memo table doesn't copy them.
+type Memo = HashMap<(Rule, usize), Result<(Rc<SyntaxNode>, usize), ParseError>>;
+
 #[derive(Debug, Clone, Copy)]
 pub enum PopError {
     PopOnEmptyStack,
 }
 
+/// Number of chars read from the inner iterator at once
+const CHUNK: usize = 1024;
+
 impl<T> CheckpointIterator<T>
 where
-    T: Iterator,
-    T::Item: Clone,
+    T: Iterator<Item = char>,
 {
     pub fn new(iterator: T) -> Self {
         Self {
             inner: iterator,
+            buf: String::new(),
+            base: 0,
+            offset: 0,
             stack: Vec::new(),
-            buf: VecDeque::new(),
-            current_pos: 0,
+            memo: None,
+            stats: Default::default(),
         }
     }
+
+    /// Iterator remembering the result of every rule attempted with [`Self::memo_parse`], so
+    /// attempting it again at the same position doesn't parse the text again
+    pub fn with_memo(iterator: T) -> Self {
+        Self {
+            memo: Some(HashMap::new()),
+            ..Self::new(iterator)
+        }
+    }
+
     pub fn push(&mut self) -> usize {
-        self.stack.push((self.current_pos, Vec::new()));
-        self.current_pos
+        // The text before the outermost checkpoint can't be gone back to
+        if self.stack.is_empty() && self.offset > self.buf.len() / 2 {
+            self.buf.drain(..self.offset);
+            self.base += self.offset;
+            self.offset = 0;
+            if let Some(memo) = &mut self.memo {
+                memo.retain(|(_, pos), _| *pos >= self.base);
+            }
+        }
+        let pos = self.current_position();
+        self.stack.push(pos);
+        pos
     }
 
+    /// Byte offset of the current position in the text
     pub fn current_position(&self) -> usize {
-        self.current_pos
+        self.base + self.offset
     }
 
     pub fn pop(&mut self) -> Result<usize, PopError> {
-        let (pos, last) = match self.stack.pop() {
-            Some(v) => v,
-            None => return Err(PopError::PopOnEmptyStack),
-        };
-
-        let last_pos = pos;
-        self.current_pos = pos;
-
-        for e in last.iter() {
-            self.buf.push_back(e.clone());
-        }
-
-        if let Some(v) = self.stack.last_mut() {
-            for e in last.iter() {
-                v.1.push(e.clone());
-            }
-        }
-        Ok(last_pos)
+        let pos = self.stack.pop().ok_or(PopError::PopOnEmptyStack)?;
+        self.offset = pos - self.base;
+        Ok(pos)
     }
 
     pub fn drop(&mut self) -> Result<usize, PopError> {
-        let (pos, _) = match self.stack.pop() {
-            Some(v) => v,
-            None => return Err(PopError::PopOnEmptyStack),
-        };
-        return Ok(pos);
+        self.stack.pop().ok_or(PopError::PopOnEmptyStack)
     }
 
     pub fn opt_parse<V, E: std::fmt::Debug, F: FnOnce(&mut Self) -> Result<V, E>>(
@@ -77,30 +111,152 @@ where
                 Some(v)
             }
             Err(e) => {
-                log::info!("Could not parse heading for the document {e:?}");
+                log::debug!("Optional parse failed, backtracking: {e:?}");
                 let _ = self.pop().expect("Expected a push before pop call");
                 None
             }
         }
     }
 
-    pub fn step(&mut self) -> Option<T::Item> {
-        self.buf.pop_front().or_else(|| {
-            let item = self.inner.next();
-            if let Some(e) = self.stack.last_mut() {
-                if let Some(v) = item.clone() {
-                    e.1.push(v)
+    /// Parses the given `rule` with `f`, rewinding the iterator if it fails. The attempt is
+    /// counted in [`Self::rule_stats`] and goes through the memo table if there is one
+    pub fn memo_parse<F: FnOnce(&mut Self) -> Result<SyntaxNode, ParseError>>(
+        &mut self,
+        rule: Rule,
+        f: F,
+    ) -> Result<Rc<SyntaxNode>, ParseError> {
+        let start = self.current_position();
+        self.stats[rule as usize].attempts += 1;
+        let memoized = self.memo.as_ref().and_then(|memo| memo.get(&(rule, start)));
+        if let Some(result) = memoized.cloned() {
+            self.stats[rule as usize].memo_hits += 1;
+            return match result {
+                Ok((node, end)) => {
+                    self.offset = end - self.base;
+                    Ok(node)
                 }
+                Err(e) => {
+                    self.stats[rule as usize].backtracks += 1;
+                    Err(e)
+                }
+            };
+        }
+        self.push();
+        let result = f(self).map(Rc::new);
+        let end = self.current_position();
+        if let Some(memo) = &mut self.memo {
+            memo.insert((rule, start), result.clone().map(|node| (node, end)));
+        }
+        match result {
+            Ok(node) => {
+                let _ = self.drop().expect("Expected a push before drop call");
+                Ok(node)
+            }
+            Err(e) => {
+                log::debug!("Parsing {rule:?} failed, backtracking: {e:?}");
+                self.stats[rule as usize].backtracks += 1;
+                let _ = self.pop().expect("Expected a push before pop call");
+                Err(e)
             }
-            item
-        })
+        }
+    }
+
+    /// Like [`Self::opt_parse`], through [`Self::memo_parse`]
+    pub fn opt_memo<F: FnOnce(&mut Self) -> Result<SyntaxNode, ParseError>>(
+        &mut self,
+        rule: Rule,
+        f: F,
+    ) -> Option<Rc<SyntaxNode>> {
+        self.memo_parse(rule, f).ok()
     }
 
-    pub fn take_while_ref<P: Fn(&T::Item) -> bool>(&mut self, predicate: P) -> TakeWhileRef<T, P> {
-        TakeWhileRef {
-            inner: self,
-            predicate,
+    /// Runs `f` on an iterator reading the rest of the text through this one, which takes
+    /// back the position, memo table and rule statistics of the iterator `f` returns
+    pub(crate) fn reborrow<V, F>(&mut self, f: F) -> V
+    where
+        F: for<'a> FnOnce(CheckpointIterator<&'a mut T>) -> (V, CheckpointIterator<&'a mut T>),
+    {
+        let reborrowed = CheckpointIterator {
+            inner: &mut self.inner,
+            buf: mem::take(&mut self.buf),
+            base: self.base,
+            offset: self.offset,
+            stack: mem::take(&mut self.stack),
+            memo: self.memo.take(),
+            stats: self.stats,
+        };
+        let (v, reborrowed) = f(reborrowed);
+        let CheckpointIterator {
+            buf,
+            base,
+            offset,
+            stack,
+            memo,
+            stats,
+            ..
+        } = reborrowed;
+        (self.buf, self.base, self.offset) = (buf, base, offset);
+        (self.stack, self.memo, self.stats) = (stack, memo, stats);
+        v
+    }
+
+    pub fn is_memoized(&self) -> bool {
+        self.memo.is_some()
+    }
+
+    /// Attempts and backtracks of `rule` parsed with [`Self::memo_parse`] so far
+    pub fn rule_stats(&self, rule: Rule) -> RuleStats {
+        self.stats[rule as usize]
+    }
+
+    /// Runs `f` and rewinds the iterator afterwards, regardless of the result
+    pub fn lookahead<V, F: FnOnce(&mut Self) -> V>(&mut self, f: F) -> V {
+        self.push();
+        let v = f(self);
+        let _ = self.pop().expect("Expected a push before pop call");
+        v
+    }
+
+    /// Reads more of the inner iterator into the buffer, returns false at its end
+    fn fill(&mut self) -> bool {
+        let len = self.buf.len();
+        self.buf.extend(self.inner.by_ref().take(CHUNK));
+        self.buf.len() > len
+    }
+
+    pub fn peek(&mut self) -> Option<char> {
+        if self.offset == self.buf.len() && !self.fill() {
+            return None;
         }
+        self.buf[self.offset..].chars().next()
+    }
+
+    pub fn step(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+
+    /// Steps over the next char if it matches `predicate`
+    pub fn next_if<P: Fn(&char) -> bool>(&mut self, predicate: P) -> Option<char> {
+        self.peek()
+            .filter(predicate)
+            .inspect(|c| self.offset += c.len_utf8())
+    }
+
+    /// Steps over the chars matching `predicate`, returning the text stepped over
+    pub fn take_while_ref<P: Fn(&char) -> bool>(&mut self, predicate: P) -> &str {
+        let start = self.offset;
+        loop {
+            if self.offset == self.buf.len() && !self.fill() {
+                break;
+            }
+            match self.buf[self.offset..].chars().next() {
+                Some(c) if predicate(&c) => self.offset += c.len_utf8(),
+                _ => break,
+            }
+        }
+        &self.buf[start..self.offset]
     }
 
     pub fn error(&self, message: String) -> ParseError {
@@ -114,37 +270,11 @@ where
 
 impl<T> Iterator for CheckpointIterator<T>
 where
-    T: Iterator,
-    T::Item: Clone,
+    T: Iterator<Item = char>,
 {
-    type Item = T::Item;
+    type Item = char;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.step()
     }
 }
-
-pub struct TakeWhileRef<'a, T: Iterator, P: Fn(&T::Item) -> bool> {
-    inner: &'a mut CheckpointIterator<T>,
-    predicate: P,
-}
-
-impl<'a, T, P> Iterator for TakeWhileRef<'a, T, P>
-where
-    T: Iterator,
-    P: Fn(&T::Item) -> bool,
-    T::Item: Display + Debug + Clone,
-{
-    type Item = T::Item;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().and_then(|e| {
-            if (self.predicate)(&e) {
-                Some(e)
-            } else {
-                self.inner.buf.push_back(e);
-                None
-            }
-        })
-    }
-}
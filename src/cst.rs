@@ -0,0 +1,330 @@
+use std::{
+    fmt::{self, Display},
+    rc::Rc,
+};
+
+/// Kinds of the nodes and tokens making up the concrete syntax tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    // Tokens
+    Whitespace,
+    Newline,
+    Text,
+    HeadingMarker,
+    ListMarker,
+    /// Term of a description list item
+    Term,
+    /// `[x]`, `[*]` or `[ ]` of a checklist item
+    Checkbox,
+    /// `+` line attaching the next block to a list item
+    ListContinuation,
+    Delimiter,
+    BreakMarker,
+    BlockTitleMarker,
+    LBracket,
+    RBracket,
+    Comma,
+    Eq,
+    Colon,
+    Bang,
+    AnchorOpen,
+    AnchorClose,
+    AnchorId,
+    AttributeName,
+    AttributeValue,
+    /// Name of a block macro, like `image` in `image::target[]`
+    MacroName,
+    MacroTarget,
+    Comment,
+
+    // Nodes
+    Document,
+    Header,
+    Heading,
+    AuthorLine,
+    RevisionLine,
+    AttributeEntry,
+    Section,
+    BlockTitle,
+    BlockAnchor,
+    AttributeList,
+    Attribute,
+    Paragraph,
+    Line,
+    DelimitedBlock,
+    Break,
+    /// `name::target[attributes]` line of a block macro
+    BlockMacro,
+    List,
+    ListItem,
+    BlankLine,
+    LineComment,
+}
+
+/// Byte range into the source text, `end` is exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TextRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl TextRange {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset < self.end
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxToken {
+    pub kind: SyntaxKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxElement {
+    /// Shared with the memo table of the parser, see
+    /// [`crate::checkpoint_iterator::CheckpointIterator::memo_parse`]
+    Node(Rc<SyntaxNode>),
+    Token(SyntaxToken),
+}
+
+impl SyntaxToken {
+    pub fn new(kind: SyntaxKind, text: impl Into<String>) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+        }
+    }
+}
+
+impl SyntaxNode {
+    pub fn new(kind: SyntaxKind, children: Vec<SyntaxElement>) -> Self {
+        Self { kind, children }
+    }
+
+    /// Length of the source text covered by this node, in bytes
+    pub fn text_len(&self) -> usize {
+        self.children.iter().map(SyntaxElement::text_len).sum()
+    }
+
+    pub fn child_nodes(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(|c| match c {
+            SyntaxElement::Node(n) => Some(n.as_ref()),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    pub fn child_tokens(&self) -> impl Iterator<Item = &SyntaxToken> {
+        self.children.iter().filter_map(|c| match c {
+            SyntaxElement::Token(t) => Some(t),
+            SyntaxElement::Node(_) => None,
+        })
+    }
+
+    pub fn child_node(&self, kind: SyntaxKind) -> Option<&SyntaxNode> {
+        self.child_nodes().find(|n| n.kind == kind)
+    }
+
+    pub fn child_token(&self, kind: SyntaxKind) -> Option<&SyntaxToken> {
+        self.child_tokens().find(|t| t.kind == kind)
+    }
+
+    /// All nodes below and including this one in preorder, with their range relative to
+    /// the start of this node
+    pub fn descendants(&self) -> Vec<(&SyntaxNode, TextRange)> {
+        let mut nodes = Vec::new();
+        self.collect_descendants(0, &mut nodes);
+        nodes
+    }
+
+    fn collect_descendants<'a>(
+        &'a self,
+        offset: usize,
+        nodes: &mut Vec<(&'a SyntaxNode, TextRange)>,
+    ) {
+        nodes.push((self, TextRange::new(offset, offset + self.text_len())));
+        let mut offset = offset;
+        for child in self.children.iter() {
+            if let SyntaxElement::Node(n) = child {
+                n.collect_descendants(offset, nodes);
+            }
+            offset += child.text_len();
+        }
+    }
+
+    /// All leaf tokens in source order, with their range relative to the start of this node
+    pub fn tokens(&self) -> Vec<(&SyntaxToken, TextRange)> {
+        let mut tokens = Vec::new();
+        self.collect_tokens(0, &mut tokens);
+        tokens
+    }
+
+    fn collect_tokens<'a>(
+        &'a self,
+        offset: usize,
+        tokens: &mut Vec<(&'a SyntaxToken, TextRange)>,
+    ) -> usize {
+        let mut offset = offset;
+        for child in self.children.iter() {
+            match child {
+                SyntaxElement::Token(t) => {
+                    tokens.push((t, TextRange::new(offset, offset + t.text.len())));
+                    offset += t.text.len();
+                }
+                SyntaxElement::Node(n) => offset = n.collect_tokens(offset, tokens),
+            }
+        }
+        offset
+    }
+
+    pub fn token_at_offset(&self, offset: usize) -> Option<(&SyntaxToken, TextRange)> {
+        self.tokens()
+            .into_iter()
+            .find(|(_, range)| range.contains(offset))
+    }
+
+    /// Concatenated text of the direct tokens of the given kind, useful for extracting the
+    /// value of a line without its markers and trivia
+    pub fn token_text(&self, kind: SyntaxKind) -> String {
+        self.child_tokens()
+            .filter(|t| t.kind == kind)
+            .map(|t| t.text.as_str())
+            .collect()
+    }
+}
+
+impl SyntaxElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            SyntaxElement::Node(n) => n.kind,
+            SyntaxElement::Token(t) => t.kind,
+        }
+    }
+
+    pub fn as_node(&self) -> Option<&SyntaxNode> {
+        match self {
+            SyntaxElement::Node(n) => Some(n),
+            SyntaxElement::Token(_) => None,
+        }
+    }
+
+    pub fn text_len(&self) -> usize {
+        match self {
+            SyntaxElement::Node(n) => n.text_len(),
+            SyntaxElement::Token(t) => t.text.len(),
+        }
+    }
+}
+
+impl From<SyntaxNode> for SyntaxElement {
+    fn from(value: SyntaxNode) -> Self {
+        SyntaxElement::Node(Rc::new(value))
+    }
+}
+
+impl From<Rc<SyntaxNode>> for SyntaxElement {
+    fn from(value: Rc<SyntaxNode>) -> Self {
+        SyntaxElement::Node(value)
+    }
+}
+
+impl From<SyntaxToken> for SyntaxElement {
+    fn from(value: SyntaxToken) -> Self {
+        SyntaxElement::Token(value)
+    }
+}
+
+impl Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for child in self.children.iter() {
+            write!(f, "{child}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl Display for SyntaxElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyntaxElement::Node(n) => n.fmt(f),
+            SyntaxElement::Token(t) => t.fmt(f),
+        }
+    }
+}
+
+/// Replacement of a range of the source text, ranges are taken from the syntax tree so that
+/// only the bytes that are meant to change are touched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: TextRange,
+    pub replacement: String,
+}
+
+impl TextEdit {
+    pub fn new(range: TextRange, replacement: impl Into<String>) -> Self {
+        Self {
+            range,
+            replacement: replacement.into(),
+        }
+    }
+
+    /// Applies non overlapping edits to `text`, edits may be given in any order. `text` is
+    /// left unchanged if any of the edits can't be applied.
+    pub fn apply_all(text: &mut String, edits: &[TextEdit]) -> Result<(), EditError> {
+        let mut edits: Vec<&TextEdit> = edits.iter().collect();
+        edits.sort_by_key(|e| std::cmp::Reverse(e.range.start));
+        let mut next_start = text.len();
+        for edit in edits.iter() {
+            let TextRange { start, end } = edit.range;
+            if start > end || end > text.len() {
+                return Err(EditError::OutOfBounds(edit.range));
+            }
+            if !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+                return Err(EditError::NotCharBoundary(edit.range));
+            }
+            if end > next_start {
+                return Err(EditError::Overlapping(edit.range));
+            }
+            next_start = start;
+        }
+        for edit in edits {
+            text.replace_range(edit.range.start..edit.range.end, &edit.replacement);
+        }
+        Ok(())
+    }
+}
+
+/// Why edits can't be applied, see [`TextEdit::apply_all`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditError {
+    /// The range ends before it starts or after the end of the text
+    OutOfBounds(TextRange),
+    /// The range starts or ends inside a char
+    NotCharBoundary(TextRange),
+    /// The range overlaps the range of another edit
+    Overlapping(TextRange),
+}
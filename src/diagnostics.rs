@@ -0,0 +1,421 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ast::{Block, BlockContent, Doctype, SectionKind},
+    checkpoint_iterator::CheckpointIterator,
+    cst::{SyntaxElement, SyntaxKind, SyntaxNode, TextRange},
+    media::{self, Media},
+    parser::{
+        body::{delimiter_kind, marker_numbering},
+        events::block_macro,
+        header,
+        traits::header::AuthorsParser,
+    },
+    xref,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub range: TextRange,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn warning(range: TextRange, message: String) -> Self {
+        Self {
+            range,
+            severity: Severity::Warning,
+            message,
+        }
+    }
+
+    pub fn error(range: TextRange, message: String) -> Self {
+        Self {
+            range,
+            severity: Severity::Error,
+            message,
+        }
+    }
+}
+
+/// Checks a parsed document for problems that don't prevent parsing it, like unterminated
+/// blocks, section levels out of sequence or references to missing ids
+pub fn check(root: &SyntaxNode) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    check_sections(root, 0, None, doctype(root), 0, &mut diagnostics);
+    check_ids(root, &mut diagnostics);
+    for (node, range) in root.descendants() {
+        match node.kind {
+            SyntaxKind::DelimitedBlock => {
+                let delimiters: Vec<_> = node
+                    .child_tokens()
+                    .filter(|t| t.kind == SyntaxKind::Delimiter)
+                    .collect();
+                if delimiters.len() < 2 {
+                    diagnostics.push(Diagnostic::warning(
+                        TextRange::new(range.start, range.start + delimiters[0].text.len()),
+                        format!("Unterminated delimited block, expected '{}'", delimiters[0]),
+                    ));
+                }
+            }
+            SyntaxKind::AuthorLine => {
+                let text = node.token_text(SyntaxKind::Text);
+                if CheckpointIterator::new(text.chars())
+                    .parse_authors()
+                    .is_ok_and(|authors| authors.is_empty())
+                {
+                    diagnostics.push(Diagnostic::warning(
+                        range,
+                        format!("Could not parse any author from '{text}'"),
+                    ));
+                }
+            }
+            SyntaxKind::RevisionLine => {
+                let line = node.token_text(SyntaxKind::Text);
+                let revision = header::revision(&line);
+                if let Some(date) = revision.date
+                    && revision.parsed_date.is_none()
+                    && header::is_iso_date_like(&date)
+                {
+                    diagnostics.push(Diagnostic::warning(
+                        range,
+                        format!("Invalid revision date '{date}'"),
+                    ));
+                }
+            }
+            SyntaxKind::List => check_numbering(node, range.start, &mut diagnostics),
+            _ => {}
+        }
+    }
+    diagnostics
+}
+
+/// Checks the references of a parsed document to other documents, `anchors` gives the ids of
+/// the document at a path relative to this one, or `None` when it can't be read
+pub fn check_external(
+    root: &SyntaxNode,
+    mut anchors: impl FnMut(&str) -> Option<Vec<String>>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (xref, range) in xref::references(root) {
+        let Some(path) = xref.path else {
+            continue;
+        };
+        match (anchors(path), xref.id) {
+            (None, _) => {
+                diagnostics.push(Diagnostic::warning(
+                    range,
+                    format!("Could not read the referenced document '{path}'"),
+                ));
+            }
+            (Some(ids), Some(id)) if !ids.iter().any(|i| i == id) => {
+                diagnostics.push(Diagnostic::warning(
+                    range,
+                    format!("Unresolved reference to '{path}#{id}'"),
+                ));
+            }
+            _ => {}
+        }
+    }
+    diagnostics
+}
+
+/// Checks that the local files of the image, video and audio macros exist, `exists` is given
+/// their path in `imagesdir`
+pub fn check_media(root: &SyntaxNode, mut exists: impl FnMut(&str) -> bool) -> Vec<Diagnostic> {
+    let attributes = attribute_entries(root)
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect();
+    let mut media = Vec::new();
+    for (node, range) in root.descendants() {
+        if node.kind == SyntaxKind::BlockMacro {
+            let block = Block {
+                heading: None,
+                title: None,
+                attributes: Vec::new(),
+                content: BlockContent::Macro(block_macro(node)),
+            };
+            media.extend(Media::new(&block).map(|m| (m, range)));
+        }
+    }
+    for (text, offset) in texts(root) {
+        for (range, image) in media::inline_images(text) {
+            let range = TextRange::new(offset + range.start, offset + range.end);
+            media.push((Media::Image(image), range));
+        }
+    }
+    let mut diagnostics = Vec::new();
+    for (media, range) in media {
+        let Some(target) = media.local_target() else {
+            continue;
+        };
+        let path = media::resolve(target, &attributes);
+        if !exists(&path) {
+            let kind = match media {
+                Media::Image(_) => "image",
+                Media::Video(_) => "video",
+                Media::Audio(_) => "audio",
+            };
+            diagnostics.push(Diagnostic::warning(
+                range,
+                format!("Missing {kind} file '{path}'"),
+            ));
+        }
+    }
+    diagnostics.sort_by_key(|d| d.range.start);
+    diagnostics
+}
+
+/// Ids must be unique, and the references within the document must point to one of them
+fn check_ids(root: &SyntaxNode, diagnostics: &mut Vec<Diagnostic>) {
+    let anchors = xref::anchors(root);
+    let mut ids = HashSet::new();
+    for (id, range) in &anchors {
+        if !ids.insert(id.as_str()) {
+            diagnostics.push(Diagnostic::warning(*range, format!("Duplicate id '{id}'")));
+        }
+    }
+    for (xref, range) in xref::references(root) {
+        if xref.path.is_none()
+            && let Some(id) = xref.id
+            && !ids.contains(id)
+        {
+            diagnostics.push(Diagnostic::warning(
+                range,
+                format!("Unresolved reference to '{id}'"),
+            ));
+        }
+    }
+}
+
+/// Explicit numerals of an ordered list must follow the numeral of the previous item
+fn check_numbering(list: &SyntaxNode, offset: usize, diagnostics: &mut Vec<Diagnostic>) {
+    let mut offset = offset;
+    let mut expected = None;
+    for child in list.children.iter() {
+        if let SyntaxElement::Node(item) = child
+            && item.kind == SyntaxKind::ListItem
+        {
+            let marker = item.token_text(SyntaxKind::ListMarker);
+            let (style, Some(number)) = marker_numbering(&marker) else {
+                return;
+            };
+            let expected = expected.get_or_insert(number);
+            if number != *expected {
+                let start = offset
+                    + item
+                        .children
+                        .iter()
+                        .take_while(|c| c.kind() != SyntaxKind::ListMarker)
+                        .map(SyntaxElement::text_len)
+                        .sum::<usize>();
+                let suffix = if marker.ends_with(')') { ")" } else { "." };
+                diagnostics.push(Diagnostic::warning(
+                    TextRange::new(start, start + marker.len()),
+                    format!(
+                        "List item out of sequence: expected '{}{suffix}', got '{marker}'",
+                        style.format(*expected)
+                    ),
+                ));
+            }
+            *expected = number + 1;
+        }
+        offset += child.text_len();
+    }
+}
+
+/// Sections may only be nested one level deeper than their parent, and parts and special
+/// sections only appear where the doctype allows them. Levels are section levels, one less
+/// than the number of `=` of the heading.
+fn check_sections(
+    node: &SyntaxNode,
+    parent_level: usize,
+    parent: Option<SectionKind>,
+    doctype: Doctype,
+    offset: usize,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut offset = offset;
+    let mut sections = 0;
+    for child in node.children.iter() {
+        if let SyntaxElement::Node(n) = child {
+            if n.kind == SyntaxKind::Section {
+                let level = n
+                    .child_node(SyntaxKind::Heading)
+                    .map_or(1, |h| h.token_text(SyntaxKind::HeadingMarker).len() - 1);
+                let heading = n
+                    .descendants()
+                    .into_iter()
+                    .find(|(h, _)| h.kind == SyntaxKind::Heading)
+                    .map(|(_, r)| TextRange::new(offset + r.start, offset + r.end))
+                    .unwrap_or_default();
+                if level > parent_level + 1 {
+                    diagnostics.push(Diagnostic::warning(
+                        heading,
+                        format!(
+                            "Section title out of sequence: expected level {}, got level {}",
+                            parent_level + 1,
+                            level
+                        ),
+                    ));
+                }
+                let kind = section_kind(n, level);
+                if let Some(message) = misplaced_section(kind, level, parent, doctype, sections) {
+                    diagnostics.push(Diagnostic::warning(heading, message));
+                }
+                sections += 1;
+                check_sections(n, level, Some(kind), doctype, offset, diagnostics);
+            } else if n.kind != SyntaxKind::Header {
+                check_sections(n, parent_level, parent, doctype, offset, diagnostics);
+            }
+        }
+        offset += child.text_len();
+    }
+}
+
+/// Doctype set by the attribute entries of the header
+fn doctype(root: &SyntaxNode) -> Doctype {
+    let doctype = root.child_node(SyntaxKind::Header).and_then(|header| {
+        header
+            .child_nodes()
+            .filter(|n| {
+                n.kind == SyntaxKind::AttributeEntry
+                    && n.token_text(SyntaxKind::AttributeName) == "doctype"
+            })
+            .last()
+            .map(|n| n.token_text(SyntaxKind::AttributeValue))
+    });
+    doctype
+        .as_deref()
+        .map(str::trim)
+        .and_then(Doctype::from_name)
+        .unwrap_or_default()
+}
+
+/// Kind of a section from its style, the first positional attribute, and heading level.
+/// Positional attributes are an attribute name or a quoted value on their own.
+fn section_kind(section: &SyntaxNode, level: usize) -> SectionKind {
+    let style = section
+        .child_nodes()
+        .filter(|n| n.kind == SyntaxKind::AttributeList)
+        .flat_map(SyntaxNode::child_nodes)
+        .find(|n| {
+            n.kind == SyntaxKind::Attribute
+                && (n.child_token(SyntaxKind::AttributeName).is_none()
+                    || n.child_token(SyntaxKind::AttributeValue).is_none())
+        })
+        .map(|n| {
+            n.token_text(SyntaxKind::AttributeName) + &n.token_text(SyntaxKind::AttributeValue)
+        });
+    let style = style
+        .as_deref()
+        .and_then(|style| style.split(['#', '.', '%']).next());
+    SectionKind::new(style, level)
+}
+
+/// Why a section of `kind` can't be at `level` below a `parent` section after `preceding`
+/// sibling sections
+fn misplaced_section(
+    kind: SectionKind,
+    level: usize,
+    parent: Option<SectionKind>,
+    doctype: Doctype,
+    preceding: usize,
+) -> Option<String> {
+    let name = match kind {
+        SectionKind::Part | SectionKind::Section => "",
+        SectionKind::Abstract => "Abstract",
+        SectionKind::Appendix => "Appendix",
+        SectionKind::Bibliography => "Bibliography",
+        SectionKind::Colophon => "Colophon",
+        SectionKind::Dedication => "Dedication",
+        SectionKind::Glossary => "Glossary",
+        SectionKind::Index => "Index",
+        SectionKind::PartIntro => "Part introduction",
+        SectionKind::Preface => "Preface",
+    };
+    if level == 0 {
+        return if doctype != Doctype::Book {
+            Some("Level 0 sections (parts) are only allowed in books".to_string())
+        } else if !matches!(kind, SectionKind::Part | SectionKind::Appendix) {
+            Some(format!("{name} sections can't be parts"))
+        } else {
+            None
+        };
+    }
+    match kind {
+        SectionKind::Preface | SectionKind::Dedication | SectionKind::Colophon
+            if doctype != Doctype::Book =>
+        {
+            Some(format!("{name} sections are only allowed in books"))
+        }
+        SectionKind::Preface
+        | SectionKind::Dedication
+        | SectionKind::Colophon
+        | SectionKind::Abstract
+        | SectionKind::Appendix
+            if level != 1 =>
+        {
+            Some(format!("{name} sections must be at level 1"))
+        }
+        SectionKind::PartIntro if parent != Some(SectionKind::Part) || preceding > 0 => Some(
+            format!("{name} sections are only allowed at the start of a part"),
+        ),
+        _ => None,
+    }
+}
+
+/// Text tokens outside of verbatim blocks with their offset
+pub(crate) fn texts(root: &SyntaxNode) -> Vec<(&str, usize)> {
+    fn collect<'n>(node: &'n SyntaxNode, offset: usize, texts: &mut Vec<(&'n str, usize)>) {
+        if node.kind == SyntaxKind::DelimitedBlock
+            && node
+                .child_token(SyntaxKind::Delimiter)
+                .and_then(|d| delimiter_kind(&d.text))
+                .is_some_and(|kind| kind.is_verbatim())
+        {
+            return;
+        }
+        let mut offset = offset;
+        for child in node.children.iter() {
+            match child {
+                SyntaxElement::Token(t) if t.kind == SyntaxKind::Text => {
+                    texts.push((&t.text, offset))
+                }
+                SyntaxElement::Node(n) => collect(n, offset, texts),
+                SyntaxElement::Token(_) => {}
+            }
+            offset += child.text_len();
+        }
+    }
+    let mut texts = Vec::new();
+    collect(root, 0, &mut texts);
+    texts
+}
+
+/// Value of every attribute after the last of its entries, `None` when it is unset
+pub(crate) fn attribute_entries(root: &SyntaxNode) -> HashMap<String, Option<String>> {
+    root.descendants()
+        .into_iter()
+        .filter(|(node, _)| node.kind == SyntaxKind::AttributeEntry)
+        .map(|(node, _)| {
+            let unset = node.child_token(SyntaxKind::Bang).is_some();
+            (
+                node.token_text(SyntaxKind::AttributeName),
+                (!unset).then(|| {
+                    node.token_text(SyntaxKind::AttributeValue)
+                        .trim()
+                        .to_string()
+                }),
+            )
+        })
+        .collect()
+}
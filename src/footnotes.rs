@@ -0,0 +1,192 @@
+//! Footnotes of a document, numbered in the order they appear
+
+use std::ops::Range;
+
+use crate::subs::macros::closing_bracket;
+
+/// `footnote:[text]`, `footnote:name[text]` or `footnote:name[]` inline macro
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FootnoteMacro<'t> {
+    /// Name the footnote is reused by
+    pub name: Option<&'t str>,
+    /// `None` for a reference to a named footnote defined before
+    pub text: Option<&'t str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Footnote {
+    pub number: usize,
+    pub name: Option<String>,
+    pub text: String,
+}
+
+impl Footnote {
+    /// Id of a named footnote, which its DocBook references link to
+    fn id(&self) -> Option<String> {
+        self.name.as_ref().map(|name| format!("_footnote_{name}"))
+    }
+}
+
+/// Where a footnote macro is in relation to its footnote
+enum Occurrence<'f, 't> {
+    /// The first macro of the footnote, with its text
+    Definition(&'f Footnote),
+    /// Another macro with the name of the footnote
+    Reference(&'f Footnote),
+    /// `footnote:name[]` before any footnote with the name
+    Unresolved(&'t str),
+}
+
+/// Footnotes of a document in order, collected while replacing their macros
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Footnotes {
+    pub entries: Vec<Footnote>,
+}
+
+impl Footnotes {
+    /// Replaces the footnote macros of substituted `text` with superscript numbers linking
+    /// to the footnotes and back, adding the footnotes they define
+    pub fn replace_html(&mut self, text: &str) -> Option<String> {
+        self.replace(text, |occurrence| match occurrence {
+            Occurrence::Definition(footnote) => {
+                let id = footnote
+                    .id()
+                    .map(|id| format!(" id=\"{id}\""))
+                    .unwrap_or_default();
+                let number = footnote.number;
+                format!(
+                    "<sup class=\"footnote\"{id}>[<a id=\"_footnoteref_{number}\" \
+                     class=\"footnote\" href=\"#_footnotedef_{number}\" \
+                     title=\"View footnote.\">{number}</a>]</sup>"
+                )
+            }
+            Occurrence::Reference(footnote) => format!(
+                "<sup class=\"footnoteref\">[<a class=\"footnote\" \
+                 href=\"#_footnotedef_{0}\" title=\"View footnote.\">{0}</a>]</sup>",
+                footnote.number
+            ),
+            Occurrence::Unresolved(name) => format!(
+                "<sup class=\"footnoteref red\" \
+                 title=\"Unresolved footnote reference.\">[{name}]</sup>"
+            ),
+        })
+    }
+
+    /// Replaces the footnote macros of substituted `text` with DocBook `<footnote>` elements,
+    /// and `<footnoteref>` elements for the reused ones
+    pub fn replace_docbook(&mut self, text: &str) -> Option<String> {
+        self.replace(text, |occurrence| match occurrence {
+            Occurrence::Definition(footnote) => {
+                let id = footnote
+                    .id()
+                    .map(|id| format!(" xml:id=\"{id}\""))
+                    .unwrap_or_default();
+                format!(
+                    "<footnote{id}><simpara>{}</simpara></footnote>",
+                    footnote.text
+                )
+            }
+            Occurrence::Reference(footnote) => format!(
+                "<footnoteref linkend=\"{}\"/>",
+                footnote.id().unwrap_or_default()
+            ),
+            Occurrence::Unresolved(name) => format!("[{name}]"),
+        })
+    }
+
+    /// The footnotes at the end of an HTML document, linking back to their first reference,
+    /// empty without footnotes
+    pub fn to_html(&self) -> String {
+        if self.entries.is_empty() {
+            return String::new();
+        }
+        let mut html = "<div id=\"footnotes\">\n<hr>\n".to_string();
+        for footnote in &self.entries {
+            html.push_str(&format!(
+                "<div class=\"footnote\" id=\"_footnotedef_{0}\">\n\
+                 <a href=\"#_footnoteref_{0}\">{0}</a>. {1}\n</div>\n",
+                footnote.number, footnote.text
+            ));
+        }
+        html.push_str("</div>\n");
+        html
+    }
+
+    fn replace(
+        &mut self,
+        text: &str,
+        render: impl Fn(Occurrence<'_, '_>) -> String,
+    ) -> Option<String> {
+        let mut result = String::new();
+        let mut copied = 0;
+        for (range, footnote) in scan_footnotes(text) {
+            if text[..range.start].ends_with('\\') {
+                result.push_str(&text[copied..range.start - 1]);
+                result.push_str(&text[range.clone()]);
+                copied = range.end;
+                continue;
+            }
+            result.push_str(&text[copied..range.start]);
+            let existing = footnote.name.and_then(|name| {
+                self.entries
+                    .iter()
+                    .position(|f| f.name.as_deref() == Some(name))
+            });
+            let occurrence = match (existing, footnote.text) {
+                (Some(i), _) => Occurrence::Reference(&self.entries[i]),
+                (None, Some(text)) => {
+                    self.entries.push(Footnote {
+                        number: self.entries.len() + 1,
+                        name: footnote.name.map(str::to_string),
+                        text: text.replace("\\]", "]"),
+                    });
+                    Occurrence::Definition(&self.entries[self.entries.len() - 1])
+                }
+                (None, None) => Occurrence::Unresolved(footnote.name.unwrap_or_default()),
+            };
+            result.push_str(&render(occurrence));
+            copied = range.end;
+        }
+        (copied > 0).then(|| result + &text[copied..])
+    }
+}
+
+/// Byte range and macro of the footnotes in `text`, `\` escapes them
+pub fn footnotes(text: &str) -> Vec<(Range<usize>, FootnoteMacro<'_>)> {
+    scan_footnotes(text)
+        .into_iter()
+        .filter(|(range, _)| !text[..range.start].ends_with('\\'))
+        .collect()
+}
+
+fn scan_footnotes(text: &str) -> Vec<(Range<usize>, FootnoteMacro<'_>)> {
+    let mut found = Vec::new();
+    let mut from = 0;
+    while let Some(start) = text[from..].find("footnote:").map(|i| from + i) {
+        from = start + "footnote:".len();
+        let rest = &text[from..];
+        let name_len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(rest.len());
+        if !rest[name_len..].starts_with('[') {
+            continue;
+        }
+        let Some(text_len) = closing_bracket(&rest[name_len + 1..]) else {
+            continue;
+        };
+        let inner = &rest[name_len + 1..name_len + 1 + text_len];
+        if name_len == 0 && inner.trim().is_empty() {
+            continue;
+        }
+        let end = from + name_len + text_len + 2;
+        found.push((
+            start..end,
+            FootnoteMacro {
+                name: (name_len > 0).then_some(&rest[..name_len]),
+                text: Some(inner.trim()).filter(|t| !t.is_empty()),
+            },
+        ));
+        from = end;
+    }
+    found
+}
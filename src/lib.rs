@@ -0,0 +1,12 @@
+pub mod ast;
+pub mod checkpoint_iterator;
+pub mod cst;
+pub mod diagnostics;
+pub mod footnotes;
+pub mod lsp;
+pub mod media;
+pub mod parser;
+pub mod sections;
+pub mod subs;
+pub mod toc;
+pub mod xref;
@@ -0,0 +1,312 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+};
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    DiagnosticSeverity, DocumentSymbolParams, DocumentSymbolResponse, GotoDefinitionParams,
+    GotoDefinitionResponse, Hover, HoverContents, HoverParams, HoverProviderCapability, Location,
+    MarkupContent, MarkupKind, OneOf, PublishDiagnosticsParams, Range, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as LspNotification, PublishDiagnostics,
+    },
+    request::{Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest, Request as _},
+};
+
+use crate::{
+    cst::{TextEdit, TextRange},
+    diagnostics::Severity,
+};
+
+use analysis::{Analysis, Reference};
+
+pub mod analysis;
+pub mod line_index;
+
+pub fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(
+            TextDocumentSyncKind::INCREMENTAL,
+        )),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["{".to_string(), "<".to_string(), ":".to_string()]),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Runs the language server on `connection` until the client asks it to shut down, the
+/// connection can be over stdio or in memory for tests
+pub fn run(connection: Connection) -> Result<(), Box<dyn Error + Send + Sync>> {
+    connection.initialize(serde_json::to_value(capabilities())?)?;
+    let mut server = Server::default();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                connection
+                    .sender
+                    .send(Message::Response(server.handle_request(request)))?;
+            }
+            Message::Notification(notification) => {
+                for message in server.handle_notification(notification) {
+                    connection.sender.send(Message::Notification(message))?;
+                }
+            }
+            Message::Response(response) => log::info!("Ignoring response {response:?}"),
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+pub struct Server {
+    documents: HashMap<Url, Analysis>,
+}
+
+impl Server {
+    pub fn handle_request(&self, request: Request) -> Response {
+        let id = request.id.clone();
+        let result = match request.method.as_str() {
+            DocumentSymbolRequest::METHOD => {
+                self.dispatch::<DocumentSymbolRequest>(request, Self::document_symbol)
+            }
+            GotoDefinition::METHOD => self.dispatch::<GotoDefinition>(request, Self::definition),
+            HoverRequest::METHOD => self.dispatch::<HoverRequest>(request, Self::hover),
+            Completion::METHOD => self.dispatch::<Completion>(request, Self::completion),
+            method => {
+                return Response::new_err(
+                    id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("Unhandled method {method}"),
+                );
+            }
+        };
+        match result {
+            Ok(value) => Response::new_ok(id, value),
+            Err(e) => Response::new_err(id, ErrorCode::InvalidParams as i32, e.to_string()),
+        }
+    }
+
+    fn dispatch<R: lsp_types::request::Request>(
+        &self,
+        request: Request,
+        handler: impl FnOnce(&Self, R::Params) -> R::Result,
+    ) -> Result<serde_json::Value, serde_json::Error> {
+        let params = serde_json::from_value(request.params)?;
+        serde_json::to_value(handler(self, params))
+    }
+
+    pub fn handle_notification(&mut self, notification: Notification) -> Vec<Notification> {
+        let uri = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                match notification.extract::<<DidOpenTextDocument as LspNotification>::Params>(
+                    DidOpenTextDocument::METHOD,
+                ) {
+                    Ok(params) => {
+                        let uri = params.text_document.uri;
+                        self.documents
+                            .insert(uri.clone(), Analysis::new(params.text_document.text));
+                        uri
+                    }
+                    Err(e) => {
+                        log::error!("Invalid didOpen notification: {e:?}");
+                        return Vec::new();
+                    }
+                }
+            }
+            DidChangeTextDocument::METHOD => {
+                match notification.extract::<<DidChangeTextDocument as LspNotification>::Params>(
+                    DidChangeTextDocument::METHOD,
+                ) {
+                    Ok(params) => {
+                        let uri = params.text_document.uri;
+                        let Some(analysis) = self.documents.get_mut(&uri) else {
+                            log::error!("Change to a document that isn't open: {uri}");
+                            return Vec::new();
+                        };
+                        for change in params.content_changes {
+                            let Some(range) = change.range else {
+                                *analysis = Analysis::new(change.text);
+                                continue;
+                            };
+                            let range = TextRange::new(
+                                analysis.line_index.offset(range.start),
+                                analysis.line_index.offset(range.end),
+                            );
+                            // The following changes are relative to this one
+                            if let Err(e) = analysis.edit(TextEdit::new(range, change.text)) {
+                                log::error!("Invalid change to {uri}: {e:?}");
+                                break;
+                            }
+                        }
+                        uri
+                    }
+                    Err(e) => {
+                        log::error!("Invalid didChange notification: {e:?}");
+                        return Vec::new();
+                    }
+                }
+            }
+            DidCloseTextDocument::METHOD => match notification
+                .extract::<<DidCloseTextDocument as LspNotification>::Params>(
+                DidCloseTextDocument::METHOD,
+            ) {
+                Ok(params) => {
+                    self.documents.remove(&params.text_document.uri);
+                    return vec![Self::publish_diagnostics(
+                        params.text_document.uri,
+                        Vec::new(),
+                    )];
+                }
+                Err(e) => {
+                    log::error!("Invalid didClose notification: {e:?}");
+                    return Vec::new();
+                }
+            },
+            _ => return Vec::new(),
+        };
+        let analysis = &self.documents[&uri];
+        let diagnostics = analysis
+            .diagnostics
+            .iter()
+            .map(|d| lsp_types::Diagnostic {
+                range: analysis.line_index.range(d.range),
+                severity: Some(match d.severity {
+                    Severity::Error => DiagnosticSeverity::ERROR,
+                    Severity::Warning => DiagnosticSeverity::WARNING,
+                }),
+                source: Some("rascii".to_string()),
+                message: d.message.clone(),
+                ..Default::default()
+            })
+            .collect();
+        vec![Self::publish_diagnostics(uri, diagnostics)]
+    }
+
+    fn publish_diagnostics(uri: Url, diagnostics: Vec<lsp_types::Diagnostic>) -> Notification {
+        Notification::new(
+            PublishDiagnostics::METHOD.to_string(),
+            PublishDiagnosticsParams {
+                uri,
+                diagnostics,
+                version: None,
+            },
+        )
+    }
+
+    fn document_symbol(&self, params: DocumentSymbolParams) -> Option<DocumentSymbolResponse> {
+        let analysis = self.documents.get(&params.text_document.uri)?;
+        Some(DocumentSymbolResponse::Nested(analysis.document_symbols()))
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let position = params.text_document_position_params;
+        let uri = position.text_document.uri;
+        let analysis = self.documents.get(&uri)?;
+        let offset = analysis.line_index.offset(position.position);
+        let location = match analysis.reference_at(offset)?.0 {
+            Reference::Xref(target) => match target.split_once('#') {
+                Some((path, id)) if !path.is_empty() => {
+                    let uri = uri.join(path).ok()?;
+                    let range = self
+                        .documents
+                        .get(&uri)
+                        .and_then(|other| other.anchor_range(id))
+                        .unwrap_or_default();
+                    Location::new(uri, range)
+                }
+                Some((_, id)) => Location::new(uri.clone(), analysis.anchor_range(id)?),
+                None if target.ends_with(".adoc") => {
+                    Location::new(uri.join(&target).ok()?, Range::default())
+                }
+                None => Location::new(uri.clone(), analysis.anchor_range(&target)?),
+            },
+            Reference::Include(path) => {
+                let path = analysis.substitute_attributes(&path, offset);
+                Location::new(uri.join(&path).ok()?, Range::default())
+            }
+            Reference::Attribute(name) => {
+                let entry = analysis.attribute_value(&name, offset)?;
+                Location::new(uri.clone(), analysis.line_index.range(entry.range))
+            }
+        };
+        Some(GotoDefinitionResponse::Scalar(location))
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let position = params.text_document_position_params;
+        let analysis = self.documents.get(&position.text_document.uri)?;
+        let offset = analysis.line_index.offset(position.position);
+        let (Reference::Attribute(name), range) = analysis.reference_at(offset)? else {
+            return None;
+        };
+        let value = match analysis.attribute_value(&name, offset) {
+            Some(entry) => match entry.value {
+                Some(value) => format!("`{{{name}}}` = `{value}`"),
+                None => format!("`{{{name}}}` is unset"),
+            },
+            None => format!("`{{{name}}}` is not defined"),
+        };
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(analysis.line_index.range(range)),
+        })
+    }
+
+    fn completion(&self, params: CompletionParams) -> Option<CompletionResponse> {
+        let position = params.text_document_position;
+        let analysis = self.documents.get(&position.text_document.uri)?;
+        let offset = analysis.line_index.offset(position.position);
+        let (line, line_start) = analysis.line_index.line_at(offset);
+        let before = line.get(..offset - line_start).unwrap_or(line);
+        let is_open = |open: &str, close: &str| {
+            before
+                .rfind(open)
+                .is_some_and(|start| !before[start..].contains(close))
+        };
+        let items = if is_open("<<", ">>") || is_open("xref:", "[") {
+            analysis
+                .anchors()
+                .into_iter()
+                .map(|(id, _)| CompletionItem {
+                    label: id,
+                    kind: Some(CompletionItemKind::REFERENCE),
+                    ..Default::default()
+                })
+                .collect()
+        } else if is_open("{", "}") {
+            let mut names = HashSet::new();
+            let mut entries = analysis.attribute_entries();
+            // Later entries override the earlier ones
+            entries.reverse();
+            entries.retain(|entry| names.insert(entry.name.clone()));
+            entries
+                .into_iter()
+                .rev()
+                .map(|entry| CompletionItem {
+                    label: entry.name,
+                    kind: Some(CompletionItemKind::VARIABLE),
+                    detail: entry.value,
+                    ..Default::default()
+                })
+                .collect()
+        } else {
+            return None;
+        };
+        Some(CompletionResponse::Array(items))
+    }
+}
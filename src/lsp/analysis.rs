@@ -0,0 +1,246 @@
+use lsp_types::{DocumentSymbol, Range, SymbolKind};
+
+use crate::{
+    checkpoint_iterator::CheckpointIterator,
+    cst::{EditError, SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken, TextEdit, TextRange},
+    diagnostics::{self, Diagnostic},
+    parser::{ParseError, incremental::reparse, traits::DocSyntaxParser},
+    xref,
+};
+
+use super::line_index::LineIndex;
+
+/// A reference found in the text of the document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reference {
+    /// `<<id>>` or `xref:target[]`, the target may point to another document
+    Xref(String),
+    /// `include::path[]`
+    Include(String),
+    /// `{name}`
+    Attribute(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct AttributeEntry {
+    pub name: String,
+    pub value: Option<String>,
+    pub range: TextRange,
+}
+
+/// Parsed state of a single open document
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub text: String,
+    pub tree: SyntaxNode,
+    pub line_index: LineIndex,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Analysis {
+    pub fn new(text: String) -> Self {
+        let line_index = LineIndex::new(&text);
+        let (tree, diagnostics) = Self::check(
+            CheckpointIterator::new(text.chars()).parse_syntax_tree(),
+            &text,
+        );
+        Self {
+            text,
+            tree,
+            line_index,
+            diagnostics,
+        }
+    }
+
+    /// Applies `edit` to the text, only reparsing the blocks it touches
+    pub fn edit(&mut self, edit: TextEdit) -> Result<(), EditError> {
+        TextEdit::apply_all(&mut self.text, std::slice::from_ref(&edit))?;
+        self.line_index = LineIndex::new(&self.text);
+        let mut tree = std::mem::replace(
+            &mut self.tree,
+            SyntaxNode::new(SyntaxKind::Document, Vec::new()),
+        );
+        let result = reparse(&mut tree, &edit, &self.text).map(|()| tree);
+        (self.tree, self.diagnostics) = Self::check(result, &self.text);
+        Ok(())
+    }
+
+    fn check(result: Result<SyntaxNode, ParseError>, text: &str) -> (SyntaxNode, Vec<Diagnostic>) {
+        match result {
+            Ok(tree) => {
+                let diagnostics = diagnostics::check(&tree);
+                (tree, diagnostics)
+            }
+            Err(e) => {
+                let range = TextRange::new(e.start, e.end);
+                (
+                    SyntaxNode::new(
+                        SyntaxKind::Document,
+                        vec![SyntaxToken::new(SyntaxKind::Text, text).into()],
+                    ),
+                    vec![Diagnostic::error(range, e.message)],
+                )
+            }
+        }
+    }
+
+    /// Hierarchy of the sections of the document
+    pub fn document_symbols(&self) -> Vec<DocumentSymbol> {
+        self.section_symbols(&self.tree, 0)
+    }
+
+    #[allow(deprecated)]
+    fn section_symbols(&self, node: &SyntaxNode, offset: usize) -> Vec<DocumentSymbol> {
+        let mut symbols = Vec::new();
+        let mut offset = offset;
+        for child in node.children.iter() {
+            if let SyntaxElement::Node(n) = child {
+                if n.kind == SyntaxKind::Section {
+                    let heading = n
+                        .descendants()
+                        .into_iter()
+                        .find(|(h, _)| h.kind == SyntaxKind::Heading);
+                    let selection = heading
+                        .map(|(_, r)| TextRange::new(offset + r.start, offset + r.end))
+                        .unwrap_or_default();
+                    let name = heading
+                        .map(|(h, _)| h.token_text(SyntaxKind::Text))
+                        .unwrap_or_default();
+                    symbols.push(DocumentSymbol {
+                        name,
+                        detail: None,
+                        kind: SymbolKind::STRING,
+                        tags: None,
+                        deprecated: None,
+                        range: self
+                            .line_index
+                            .range(TextRange::new(offset, offset + n.text_len())),
+                        selection_range: self.line_index.range(selection),
+                        children: Some(self.section_symbols(n, offset)),
+                    });
+                } else {
+                    symbols.extend(self.section_symbols(n, offset));
+                }
+            }
+            offset += child.text_len();
+        }
+        symbols
+    }
+
+    /// Ids of all the anchors in the document, explicit or generated for the sections, with
+    /// the range of their definition
+    pub fn anchors(&self) -> Vec<(String, TextRange)> {
+        xref::anchors(&self.tree)
+    }
+
+    pub fn anchor_range(&self, id: &str) -> Option<Range> {
+        self.anchors()
+            .into_iter()
+            .find(|(anchor, _)| anchor == id)
+            .map(|(_, range)| self.line_index.range(range))
+    }
+
+    /// Attribute entries of the document, in the order they are defined
+    pub fn attribute_entries(&self) -> Vec<AttributeEntry> {
+        self.tree
+            .descendants()
+            .into_iter()
+            .filter(|(node, _)| node.kind == SyntaxKind::AttributeEntry)
+            .map(|(node, range)| {
+                let unset = node.child_token(SyntaxKind::Bang).is_some();
+                AttributeEntry {
+                    name: node.token_text(SyntaxKind::AttributeName),
+                    value: (!unset).then(|| node.token_text(SyntaxKind::AttributeValue)),
+                    range,
+                }
+            })
+            .collect()
+    }
+
+    /// Value of the attribute as defined at the given offset, later definitions don't apply
+    pub fn attribute_value(&self, name: &str, offset: usize) -> Option<AttributeEntry> {
+        self.attribute_entries()
+            .into_iter()
+            .rfind(|e| e.name == name && e.range.start < offset)
+    }
+
+    /// Replaces the `{name}` references in `text` with the value of the attributes
+    pub fn substitute_attributes(&self, text: &str, offset: usize) -> String {
+        let mut result = text.to_string();
+        for (start, name) in attribute_references(text).into_iter().rev() {
+            if let Some(value) = self.attribute_value(name, offset).and_then(|e| e.value) {
+                result.replace_range(start..start + name.len() + 2, &value);
+            }
+        }
+        result
+    }
+
+    /// Reference under the given offset, with its range
+    pub fn reference_at(&self, offset: usize) -> Option<(Reference, TextRange)> {
+        let (line, line_start) = self.line_index.line_at(offset);
+        let column = offset - line_start;
+        let range =
+            |start: usize, len: usize| TextRange::new(line_start + start, line_start + start + len);
+        if let Some(path) = line
+            .strip_prefix("include::")
+            .and_then(|rest| rest.split_once('['))
+            .map(|(path, _)| path)
+        {
+            return Some((Reference::Include(path.to_string()), range(0, line.len())));
+        }
+        for (start, inner) in enclosed(line, "<<", ">>") {
+            let target = inner.split(',').next().unwrap_or_default().trim();
+            if (start..=start + inner.len() + 4).contains(&column) {
+                return Some((
+                    Reference::Xref(target.to_string()),
+                    range(start, inner.len() + 4),
+                ));
+            }
+        }
+        for (start, target) in enclosed(line, "xref:", "[") {
+            if (start..=start + target.len() + 5).contains(&column) {
+                return Some((
+                    Reference::Xref(target.to_string()),
+                    range(start, target.len() + 6),
+                ));
+            }
+        }
+        attribute_references(line)
+            .into_iter()
+            .find(|(start, name)| (*start..=start + name.len() + 2).contains(&column))
+            .map(|(start, name)| {
+                (
+                    Reference::Attribute(name.to_string()),
+                    range(start, name.len() + 2),
+                )
+            })
+    }
+}
+
+/// Start and content of the spans of `text` between `open` and `close`
+fn enclosed<'a>(text: &'a str, open: &str, close: &str) -> Vec<(usize, &'a str)> {
+    let mut spans = Vec::new();
+    let mut rest = 0;
+    while let Some(start) = text[rest..].find(open).map(|s| s + rest) {
+        let inner_start = start + open.len();
+        let Some(len) = text[inner_start..].find(close) else {
+            break;
+        };
+        spans.push((start, &text[inner_start..inner_start + len]));
+        rest = inner_start + len + close.len();
+    }
+    spans
+}
+
+/// Start and name of the `{name}` references in `text`
+pub fn attribute_references(text: &str) -> Vec<(usize, &str)> {
+    enclosed(text, "{", "}")
+        .into_iter()
+        .filter(|(_, name)| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        })
+        .collect()
+}
@@ -0,0 +1,67 @@
+use lsp_types::{Position, Range};
+
+use crate::cst::TextRange;
+
+/// Converts between byte offsets into a text and LSP positions, which count UTF-16 code
+/// units from the start of the line
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    text: String,
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    /// Lines end with `\n`, `\r\n` or a lone `\r`, like in LSP positions
+    pub fn new(text: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(
+                text.match_indices(['\n', '\r'])
+                    .filter(|(i, end)| *end == "\n" || !text[i + 1..].starts_with('\n'))
+                    .map(|(i, _)| i + 1),
+            )
+            .collect();
+        Self {
+            text: text.to_string(),
+            line_starts,
+        }
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.text.len());
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let start = self.line_starts[line];
+        let character = self.text[start..offset].encode_utf16().count();
+        Position::new(line as u32, character as u32)
+    }
+
+    pub fn range(&self, range: TextRange) -> Range {
+        Range::new(self.position(range.start), self.position(range.end))
+    }
+
+    pub fn offset(&self, position: Position) -> usize {
+        let Some(start) = self.line_starts.get(position.line as usize) else {
+            return self.text.len();
+        };
+        let mut units = 0;
+        for (i, c) in self.text[*start..].char_indices() {
+            if units >= position.character as usize || matches!(c, '\n' | '\r') {
+                return start + i;
+            }
+            units += c.len_utf16();
+        }
+        self.text.len()
+    }
+
+    /// The line containing `offset` and the start of that line
+    pub fn line_at(&self, offset: usize) -> (&str, usize) {
+        let offset = offset.min(self.text.len());
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let start = self.line_starts[line];
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .copied()
+            .unwrap_or(self.text.len());
+        (self.text[start..end].trim_end_matches(['\n', '\r']), start)
+    }
+}
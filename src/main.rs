@@ -1,21 +1,137 @@
-use std::{fs, io::Read};
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
-use checkpoint_iterator::CheckpointIterator;
-use parser::traits::DocParser;
+use rascii::{
+    checkpoint_iterator::CheckpointIterator,
+    cst::{SyntaxNode, TextRange},
+    diagnostics::{self, Diagnostic, Severity},
+    lsp::line_index::LineIndex,
+    parser::traits::{DocParser, DocSyntaxParser},
+    toc::Toc,
+    xref,
+};
 
-pub mod ast;
-pub mod checkpoint_iterator;
-pub mod parser;
+const USAGE: &str =
+    "Usage: rascii [FILE]\n       rascii outline FILE\n       rascii check [--media] FILE...";
 
-fn main() {
+fn main() -> ExitCode {
     pretty_env_logger::init();
-    let mut test_file = fs::File::open("test.adoc").expect("The test file to be present");
-    let mut buf = String::with_capacity(2048);
-    test_file
-        .read_to_string(&mut buf)
-        .expect("To be able to read the test file");
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.as_slice() {
+        [command, path] if command == "outline" => outline(path),
+        [command, args @ ..] if command == "check" => check(args),
+        [command, ..] if command == "outline" => Err(USAGE.to_string()),
+        [path] => print_document(path),
+        [] => print_document("test.adoc"),
+        _ => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn read(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("Could not read {path}: {e}"))
+}
+
+/// Prints the syntax tree of the document
+fn print_document(path: &str) -> Result<(), String> {
+    let text = read(path)?;
     println!(
         "{:#?}",
-        CheckpointIterator::new(buf.chars()).parse_document()
+        CheckpointIterator::new(text.chars()).parse_document()
     );
+    Ok(())
+}
+
+/// Prints the numbered titles of the sections, indented by their level
+fn outline(path: &str) -> Result<(), String> {
+    let text = read(path)?;
+    let document = CheckpointIterator::new(text.chars())
+        .parse_document()
+        .map_err(|e| format!("{path}: {}", e.message))?;
+    print!("{}", Toc::new(&document));
+    Ok(())
+}
+
+/// Prints the problems found in the documents, with the references to other documents
+/// checked against the anchors of those documents, and the local files of the media with
+/// `--media`
+fn check(args: &[String]) -> Result<(), String> {
+    let (media, paths) = match args {
+        [flag, paths @ ..] if flag == "--media" => (true, paths),
+        paths => (false, paths),
+    };
+    if paths.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    let mut anchors: HashMap<PathBuf, Option<Vec<String>>> = HashMap::new();
+    let mut problems = 0;
+    for path in paths {
+        let text = read(path)?;
+        let (tree, mut diagnostics) =
+            match CheckpointIterator::new(text.chars()).parse_syntax_tree() {
+                Ok(tree) => {
+                    let diagnostics = diagnostics::check(&tree);
+                    (tree, diagnostics)
+                }
+                Err(e) => {
+                    let range = TextRange::new(e.start, e.end);
+                    print_diagnostic(path, &text, &Diagnostic::error(range, e.message));
+                    problems += 1;
+                    continue;
+                }
+            };
+        let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+        diagnostics.extend(diagnostics::check_external(&tree, |other| {
+            anchors
+                .entry(directory.join(other))
+                .or_insert_with_key(|other| {
+                    let text = fs::read_to_string(other).ok()?;
+                    let tree: SyntaxNode = CheckpointIterator::new(text.chars())
+                        .parse_syntax_tree()
+                        .ok()?;
+                    Some(xref::anchors(&tree).into_iter().map(|(id, _)| id).collect())
+                })
+                .clone()
+        }));
+        if media {
+            diagnostics.extend(diagnostics::check_media(&tree, |file| {
+                directory.join(file).is_file()
+            }));
+        }
+        diagnostics.sort_by_key(|d| d.range.start);
+        for diagnostic in &diagnostics {
+            print_diagnostic(path, &text, diagnostic);
+        }
+        problems += diagnostics.len();
+    }
+    match problems {
+        0 => Ok(()),
+        1 => Err("1 problem found".to_string()),
+        n => Err(format!("{n} problems found")),
+    }
+}
+
+/// `path:line:column: severity: message`, counting from 1
+fn print_diagnostic(path: &str, text: &str, diagnostic: &Diagnostic) {
+    let line_index = LineIndex::new(text);
+    let line = line_index.position(diagnostic.range.start).line + 1;
+    let (line_text, start) = line_index.line_at(diagnostic.range.start);
+    let column = line_text
+        .get(..diagnostic.range.start - start)
+        .map_or(1, |before| before.chars().count() + 1);
+    let severity = match diagnostic.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    };
+    println!("{path}:{line}:{column}: {severity}: {}", diagnostic.message);
 }
@@ -0,0 +1,603 @@
+//! Images, videos and audio from the `image::`, `video::` and `audio::` block macros and the
+//! `image:` inline macro
+
+use std::{collections::HashMap, fs, ops::Range, path::Path};
+
+use crate::{
+    ast::{Attribute, Block, BlockContent, SafeMode},
+    checkpoint_iterator::CheckpointIterator,
+    cst::SyntaxKind,
+    parser::{events, traits::body::DocAttributesParser},
+    subs::{escape_html, escape_quotes, macros::closing_bracket},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Media {
+    Image(Image),
+    Video(Video),
+    Audio(Audio),
+}
+
+/// `image::target[alt,width,height]` block or `image:target[alt,width,height]` inline macro
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub target: String,
+    /// Alternative text, from the name of the file when it isn't given
+    pub alt: String,
+    pub width: Option<String>,
+    pub height: Option<String>,
+    /// Address the image links to
+    pub link: Option<String>,
+    /// `left`, `center` or `right` alignment of a block image
+    pub align: Option<String>,
+    /// `left` or `right` side the image floats to
+    pub float: Option<String>,
+    pub title: Option<String>,
+    pub id: Option<String>,
+    pub role: Option<String>,
+}
+
+/// `video::target[poster,width,height]` block macro, the poster is `youtube` or `vimeo` for
+/// the id of a video on those sites
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Video {
+    pub target: String,
+    pub service: Option<VideoService>,
+    /// Image shown before a local video plays
+    pub poster: Option<String>,
+    pub width: Option<String>,
+    pub height: Option<String>,
+    /// Offsets in seconds
+    pub start: Option<String>,
+    pub end: Option<String>,
+    /// `autoplay`, `loop`, `muted` and `nocontrols`
+    pub options: Vec<String>,
+    pub title: Option<String>,
+    pub id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoService {
+    YouTube,
+    Vimeo,
+}
+
+/// `audio::target[]` block macro
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Audio {
+    pub target: String,
+    pub start: Option<String>,
+    pub end: Option<String>,
+    /// `autoplay`, `loop` and `nocontrols`
+    pub options: Vec<String>,
+    pub title: Option<String>,
+    pub id: Option<String>,
+}
+
+impl Media {
+    /// Media of an `image::`, `video::` or `audio::` block macro, with the title, id and role
+    /// of its block
+    pub fn new(block: &Block<'_>) -> Option<Self> {
+        let BlockContent::Macro(block_macro) = &block.content else {
+            return None;
+        };
+        let attributes = &block_macro.attributes;
+        let target = block_macro.target.to_string();
+        let title = block
+            .title
+            .as_deref()
+            .or_else(|| named(attributes, "title"))
+            .map(str::to_string);
+        let id = block
+            .id()
+            .or_else(|| named(attributes, "id"))
+            .map(str::to_string);
+        let options = named(attributes, "options")
+            .or_else(|| named(attributes, "opts"))
+            .map(|options| options.split(',').map(|o| o.trim().to_string()).collect())
+            .unwrap_or_default();
+        Some(match block_macro.name.as_ref() {
+            "image" => {
+                let mut image = Image::new(target, attributes);
+                image.title = title;
+                image.id = id;
+                image.role = image.role.or_else(|| role(&block.attributes));
+                Media::Image(image)
+            }
+            "video" => {
+                let poster = positional(attributes, 0, "poster");
+                let service = match poster {
+                    Some("youtube") => Some(VideoService::YouTube),
+                    Some("vimeo") => Some(VideoService::Vimeo),
+                    _ => None,
+                };
+                Media::Video(Video {
+                    target,
+                    service,
+                    poster: poster.filter(|_| service.is_none()).map(str::to_string),
+                    width: positional(attributes, 1, "width").map(str::to_string),
+                    height: positional(attributes, 2, "height").map(str::to_string),
+                    start: named(attributes, "start").map(str::to_string),
+                    end: named(attributes, "end").map(str::to_string),
+                    options,
+                    title,
+                    id,
+                })
+            }
+            "audio" => Media::Audio(Audio {
+                target,
+                start: named(attributes, "start").map(str::to_string),
+                end: named(attributes, "end").map(str::to_string),
+                options,
+                title,
+                id,
+            }),
+            _ => return None,
+        })
+    }
+
+    /// Local file the media is read from, `None` for URLs and videos on other sites
+    pub fn local_target(&self) -> Option<&str> {
+        let target = match self {
+            Media::Image(image) => &image.target,
+            Media::Video(video) if video.service.is_some() => return None,
+            Media::Video(video) => &video.target,
+            Media::Audio(audio) => &audio.target,
+        };
+        is_local(target).then_some(target.as_str())
+    }
+
+    /// HTML block of the media, local files are found in `imagesdir`
+    pub fn to_html(&self, attributes: &HashMap<String, String>) -> String {
+        match self {
+            Media::Image(image) => image.to_html(attributes),
+            Media::Video(video) => video.to_html(attributes),
+            Media::Audio(audio) => audio.to_html(attributes),
+        }
+    }
+}
+
+impl Image {
+    fn new(target: String, attributes: &[Attribute<'_>]) -> Self {
+        let value = |name| named(attributes, name).map(str::to_string);
+        Self {
+            alt: positional(attributes, 0, "alt")
+                .map(str::to_string)
+                .unwrap_or_else(|| default_alt(&target)),
+            width: positional(attributes, 1, "width").map(str::to_string),
+            height: positional(attributes, 2, "height").map(str::to_string),
+            link: value("link"),
+            align: value("align"),
+            float: value("float"),
+            title: value("title"),
+            id: value("id"),
+            role: value("role"),
+            target,
+        }
+    }
+
+    /// `<div class="imageblock">` with the image, linked when it has a link, and its title
+    pub fn to_html(&self, attributes: &HashMap<String, String>) -> String {
+        let classes = ["imageblock"]
+            .into_iter()
+            .chain(self.float.as_deref())
+            .map(str::to_string)
+            .chain(self.align.as_ref().map(|align| format!("text-{align}")))
+            .chain(self.role.clone())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut html = format!(
+            "<div{} class=\"{}\">\n<div class=\"content\">\n{}\n</div>\n",
+            id_attribute(self.id.as_deref()),
+            escape_html(&classes),
+            self.img(attributes, escape_html)
+        );
+        if let Some(title) = &self.title {
+            html.push_str(&format!(
+                "<div class=\"title\">{}</div>\n",
+                escape_html(title)
+            ));
+        }
+        html.push_str("</div>\n");
+        html
+    }
+
+    /// `<span class="image">` with the image of an inline macro, whose alternative text is
+    /// already substituted
+    fn to_inline_html(&self, attributes: &HashMap<String, String>) -> String {
+        let classes = ["image"]
+            .into_iter()
+            .chain(self.float.as_deref())
+            .chain(self.role.as_deref())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let img = self.img(attributes, escape_quotes);
+        format!("<span class=\"{}\">{img}</span>", escape_quotes(&classes))
+    }
+
+    /// `<img>` element, linked when the image has a link, with its attribute values passed
+    /// through `escape`
+    fn img(&self, attributes: &HashMap<String, String>, escape: fn(&str) -> String) -> String {
+        let mut img = format!(
+            "<img src=\"{}\" alt=\"{}\"",
+            escape(&image_src(&self.target, attributes)),
+            escape(&self.alt)
+        );
+        for (name, value) in [("width", &self.width), ("height", &self.height)] {
+            if let Some(value) = value {
+                img.push_str(&format!(" {name}=\"{}\"", escape(value)));
+            }
+        }
+        img.push('>');
+        match &self.link {
+            Some(link) => format!("<a class=\"image\" href=\"{}\">{img}</a>", escape(link)),
+            None => img,
+        }
+    }
+}
+
+impl Video {
+    /// `<div class="videoblock">` with a `<video>` element, or the player of the site the
+    /// video is on
+    pub fn to_html(&self, attributes: &HashMap<String, String>) -> String {
+        let size: String = [("width", &self.width), ("height", &self.height)]
+            .into_iter()
+            .filter_map(|(name, value)| {
+                Some(format!(" {name}=\"{}\"", escape_html(value.as_ref()?)))
+            })
+            .collect();
+        let target = escape_html(&self.target);
+        let has = |option: &str| self.options.iter().any(|o| o == option);
+        let content = match self.service {
+            Some(service) => {
+                let src = match service {
+                    VideoService::YouTube => {
+                        let mut src = format!("https://www.youtube.com/embed/{target}?rel=0");
+                        for (name, value) in [("start", &self.start), ("end", &self.end)] {
+                            if let Some(value) = value {
+                                src.push_str(&format!("&amp;{name}={}", escape_html(value)));
+                            }
+                        }
+                        if has("autoplay") {
+                            src.push_str("&amp;autoplay=1");
+                        }
+                        if has("loop") {
+                            src.push_str(&format!("&amp;loop=1&amp;playlist={target}"));
+                        }
+                        src
+                    }
+                    VideoService::Vimeo => {
+                        let mut src = format!("https://player.vimeo.com/video/{target}");
+                        let query: Vec<_> = ["autoplay", "loop"]
+                            .into_iter()
+                            .filter(|option| has(option))
+                            .map(|option| format!("{option}=1"))
+                            .collect();
+                        if !query.is_empty() {
+                            src.push_str(&format!("?{}", query.join("&amp;")));
+                        }
+                        if let Some(start) = &self.start {
+                            src.push_str(&format!("#at={}", escape_html(start)));
+                        }
+                        src
+                    }
+                };
+                format!("<iframe{size} src=\"{src}\" frameborder=\"0\" allowfullscreen></iframe>")
+            }
+            None => {
+                let poster = self
+                    .poster
+                    .as_ref()
+                    .map(|poster| {
+                        format!(" poster=\"{}\"", escape_html(&resolve(poster, attributes)))
+                    })
+                    .unwrap_or_default();
+                format!(
+                    "<video src=\"{}\"{size}{poster}{}>\n\
+                     Your browser does not support the video tag.\n</video>",
+                    escape_html(&time_range(
+                        resolve(&self.target, attributes),
+                        &self.start,
+                        &self.end
+                    )),
+                    playback(&self.options, &["autoplay", "loop", "muted"]),
+                )
+            }
+        };
+        media_block(
+            "videoblock",
+            self.id.as_deref(),
+            self.title.as_deref(),
+            &content,
+        )
+    }
+}
+
+impl Audio {
+    /// `<div class="audioblock">` with an `<audio>` element
+    pub fn to_html(&self, attributes: &HashMap<String, String>) -> String {
+        let content = format!(
+            "<audio src=\"{}\"{}>\nYour browser does not support the audio tag.\n</audio>",
+            escape_html(&time_range(
+                resolve(&self.target, attributes),
+                &self.start,
+                &self.end
+            )),
+            playback(&self.options, &["autoplay", "loop"]),
+        );
+        media_block(
+            "audioblock",
+            self.id.as_deref(),
+            self.title.as_deref(),
+            &content,
+        )
+    }
+}
+
+/// Byte range and image of the `image:target[attributes]` inline macros in `text`, `\`
+/// escapes them
+pub fn inline_images(text: &str) -> Vec<(Range<usize>, Image)> {
+    scan_images(text)
+        .into_iter()
+        .filter(|(range, _)| !text[..range.start].ends_with('\\'))
+        .collect()
+}
+
+/// Inline images in `text`, escaped ones included
+pub(crate) fn scan_images(text: &str) -> Vec<(Range<usize>, Image)> {
+    let mut images = Vec::new();
+    let mut from = 0;
+    while let Some(start) = text[from..].find("image:").map(|i| from + i) {
+        from = start + "image:".len();
+        let rest = &text[from..];
+        if rest.starts_with(':')
+            || text[..start]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_')
+        {
+            continue;
+        }
+        let Some(target_len) = rest.find('[') else {
+            continue;
+        };
+        let target = &rest[..target_len];
+        if target.is_empty() || target.contains(char::is_whitespace) {
+            continue;
+        }
+        let Some(list_len) = closing_bracket(&rest[target_len + 1..]) else {
+            continue;
+        };
+        let list = &rest[target_len + 1..target_len + 1 + list_len];
+        let end = from + target_len + list_len + 2;
+        images.push((
+            start..end,
+            Image::new(target.to_string(), &attribute_list(list)),
+        ));
+        from = end;
+    }
+    images
+}
+
+/// Replaces the inline image macros of substituted `text` with the images
+pub(crate) fn replace_inline_images(
+    text: &str,
+    attributes: &HashMap<String, String>,
+) -> Option<String> {
+    let mut result = String::new();
+    let mut copied = 0;
+    for (range, image) in scan_images(text) {
+        if text[..range.start].ends_with('\\') {
+            result.push_str(&text[copied..range.start - 1]);
+            result.push_str(&text[range.clone()]);
+        } else {
+            result.push_str(&text[copied..range.start]);
+            result.push_str(&image.to_inline_html(attributes));
+        }
+        copied = range.end;
+    }
+    (copied > 0).then(|| result + &text[copied..])
+}
+
+/// Location of a target in `imagesdir`, URLs and absolute paths are used as is
+pub fn resolve(target: &str, attributes: &HashMap<String, String>) -> String {
+    match attributes
+        .get("imagesdir")
+        .map(|dir| dir.trim_end_matches('/'))
+    {
+        Some(dir) if !dir.is_empty() && is_local(target) && !target.starts_with('/') => {
+            format!("{dir}/{target}")
+        }
+        _ => target.to_string(),
+    }
+}
+
+/// Source of an image, the contents of the file as a data URI when `data-uri` is set and it
+/// can be read, see [`data_uri`]
+fn image_src(target: &str, attributes: &HashMap<String, String>) -> String {
+    let path = resolve(target, attributes);
+    if attributes.contains_key("data-uri")
+        && is_local(target)
+        && let Some(uri) = data_uri(&path, attributes)
+    {
+        return uri;
+    }
+    path
+}
+
+/// Contents of the image at `path`, relative to `docdir`, as a `data:` URI with the MIME type
+/// of its contents
+///
+/// Unless the safe mode is [`SafeMode::Unsafe`], like for includes, only the files in `docdir`
+/// can be read.
+pub fn data_uri(path: &str, attributes: &HashMap<String, String>) -> Option<String> {
+    let docdir = Path::new(attributes.get("docdir").map_or(".", String::as_str));
+    let file = fs::canonicalize(docdir.join(path)).ok()?;
+    let safe_mode = attributes
+        .get("safe-mode-name")
+        .and_then(|name| SafeMode::from_name(name))
+        .unwrap_or_default();
+    if safe_mode > SafeMode::Unsafe
+        && !fs::canonicalize(docdir).is_ok_and(|dir| file.starts_with(dir))
+    {
+        log::warn!("Image '{path}' is outside of the document directory, it is not embedded");
+        return None;
+    }
+    let bytes = fs::read(&file).ok()?;
+    let Some(mime) = mime_type(&bytes) else {
+        log::warn!("Unknown image type of '{path}', it is not embedded");
+        return None;
+    };
+    Some(format!("data:{mime};base64,{}", base64(&bytes)))
+}
+
+/// MIME type of an image from the magic bytes at its start, or the `<svg` element of an SVG
+pub fn mime_type(bytes: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: [(&[u8], &str); 9] = [
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"BM", "image/bmp"),
+        (b"II*\0", "image/tiff"),
+        (b"MM\0*", "image/tiff"),
+        (b"\0\0\x01\0", "image/x-icon"),
+        (b"\0\0\x02\0", "image/x-icon"),
+    ];
+    if let Some((_, mime)) = SIGNATURES
+        .iter()
+        .find(|(magic, _)| bytes.starts_with(magic))
+    {
+        return Some(mime);
+    }
+    match (bytes.get(..4), bytes.get(4..12)) {
+        (Some(b"RIFF"), Some(rest)) if rest.ends_with(b"WEBP") => return Some("image/webp"),
+        (Some(_), Some(b"ftypavif")) => return Some("image/avif"),
+        _ => {}
+    }
+    // The first kilobyte may end in the middle of a char
+    let head = bytes.get(..1024).unwrap_or(bytes);
+    let text = std::str::from_utf8(head).unwrap_or_else(|e| {
+        std::str::from_utf8(&head[..e.valid_up_to()]).expect("Expected the valid prefix")
+    });
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    (text.starts_with('<') && text.contains("<svg")).then_some("image/svg+xml")
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | u32::from(*byte) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(char::from(ALPHABET[(n >> (18 - 6 * i) & 63) as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+fn is_local(target: &str) -> bool {
+    !target.contains("://") && !target.starts_with("data:")
+}
+
+/// Attributes of the list of an inline macro, given without its brackets
+fn attribute_list(list: &str) -> Vec<Attribute<'static>> {
+    CheckpointIterator::new(format!("[{list}]").chars())
+        .parse_attributes()
+        .map(|node| {
+            node.child_nodes()
+                .filter(|n| n.kind == SyntaxKind::Attribute)
+                .map(events::attribute)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn named<'a>(attributes: &'a [Attribute<'_>], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .rfind(|a| a.key == name)
+        .and_then(|a| a.value.as_deref())
+}
+
+/// Positional attribute at `index`, or else the attribute called `name`
+fn positional<'a>(attributes: &'a [Attribute<'_>], index: usize, name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .filter(|a| a.value.is_none())
+        .nth(index)
+        .map(|a| a.key.as_ref())
+        .filter(|value| !value.is_empty())
+        .or_else(|| named(attributes, name))
+}
+
+/// `role` attribute of a block or the `.role` shorthands of its style
+fn role(attributes: &[Attribute<'_>]) -> Option<String> {
+    named(attributes, "role").map(str::to_string).or_else(|| {
+        let style = attributes.iter().find(|a| a.value.is_none())?;
+        let roles: Vec<_> = style
+            .key
+            .split('.')
+            .skip(1)
+            .map(|role| role.split(['#', '%']).next().unwrap_or_default())
+            .filter(|role| !role.is_empty())
+            .collect();
+        (!roles.is_empty()).then(|| roles.join(" "))
+    })
+}
+
+/// Name of the file without its extension and with `-` and `_` as spaces
+fn default_alt(target: &str) -> String {
+    let name = target.rsplit('/').next().unwrap_or(target);
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    stem.replace(['-', '_'], " ")
+}
+
+fn time_range(src: String, start: &Option<String>, end: &Option<String>) -> String {
+    match (start, end) {
+        (None, None) => src,
+        (start, end) => format!(
+            "{src}#t={}{}",
+            start.as_deref().unwrap_or("0"),
+            end.as_ref()
+                .map(|end| format!(",{end}"))
+                .unwrap_or_default()
+        ),
+    }
+}
+
+/// `controls` unless `nocontrols` is set, and the boolean attributes of the `allowed` options
+fn playback(options: &[String], allowed: &[&str]) -> String {
+    let mut html = String::new();
+    if !options.iter().any(|o| o == "nocontrols") {
+        html.push_str(" controls");
+    }
+    for option in allowed {
+        if options.iter().any(|o| o == option) {
+            html.push_str(&format!(" {option}"));
+        }
+    }
+    html
+}
+
+fn media_block(class: &str, id: Option<&str>, title: Option<&str>, content: &str) -> String {
+    let title = title
+        .map(|title| format!("<div class=\"title\">{}</div>\n", escape_html(title)))
+        .unwrap_or_default();
+    format!(
+        "<div{} class=\"{class}\">\n{title}<div class=\"content\">\n{content}\n</div>\n</div>\n",
+        id_attribute(id)
+    )
+}
+
+fn id_attribute(id: Option<&str>) -> String {
+    id.map(|id| format!(" id=\"{}\"", escape_html(id)))
+        .unwrap_or_default()
+}
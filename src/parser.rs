@@ -1,11 +1,68 @@
-use traits::{DocParser, body::DocContentParser, header::DocHeaderParser};
+use traits::{
+    DocParser, DocSyntaxParser,
+    body::{DocContentParser, DocTriviaParser},
+    header::DocHeaderParser,
+};
 
-use crate::{ast::Document, checkpoint_iterator::CheckpointIterator};
+use events::Events;
+
+use crate::{
+    ast::Document,
+    checkpoint_iterator::CheckpointIterator,
+    cst::{SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken},
+};
 
-pub mod header;
 pub mod body;
+pub mod builder;
+#[cfg(any(feature = "pest", feature = "nom"))]
+mod convert;
+pub mod events;
+#[cfg(feature = "pest")]
+pub mod grammar;
+pub mod header;
+#[cfg(feature = "nom")]
+pub mod in_memory;
+pub mod incremental;
 pub mod traits;
 
+/// Rules of the hand written parser which can be memoized and profiled, see
+/// [`CheckpointIterator::memo_parse`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    BlankLine,
+    LineComment,
+    AttributeEntry,
+    BlockTitle,
+    BlockAnchor,
+    Attributes,
+    SectionHeading,
+    SectionBlock,
+    DelimitedBlock,
+    ListBlock,
+    BreakBlock,
+    MacroBlock,
+    UndelimitedBlockContent,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 13] = [
+        Rule::BlankLine,
+        Rule::LineComment,
+        Rule::AttributeEntry,
+        Rule::BlockTitle,
+        Rule::BlockAnchor,
+        Rule::Attributes,
+        Rule::SectionHeading,
+        Rule::SectionBlock,
+        Rule::DelimitedBlock,
+        Rule::ListBlock,
+        Rule::BreakBlock,
+        Rule::MacroBlock,
+        Rule::UndelimitedBlockContent,
+    ];
+}
+
+/// Error at the byte range `start..end` of the parsed text
 #[derive(Debug, Clone)]
 pub struct ParseError {
     pub start: usize,
@@ -17,10 +74,46 @@ impl<T> DocParser for CheckpointIterator<T>
 where
     T: Iterator<Item = char>,
 {
-    fn parse_document(&mut self) -> Result<Document, ParseError> {
-        Ok(Document {
-            header: self.opt_parse(Self::parse_document_header),
-            content: self.parse_document_content()?,
+    fn parse_document(&mut self) -> Result<Document<'static>, ParseError> {
+        self.reborrow(|iter| {
+            let mut events = Events::new(iter);
+            let document = builder::build_document(events.with_ranges());
+            (document, events.into_inner())
         })
     }
 }
+
+impl<T> DocSyntaxParser for CheckpointIterator<T>
+where
+    T: Iterator<Item = char>,
+{
+    fn parse_syntax_tree(&mut self) -> Result<SyntaxNode, ParseError> {
+        let mut children: Vec<SyntaxElement> = Vec::new();
+        while let Some(trivia) = self
+            .opt_memo(Rule::BlankLine, Self::parse_blank_line)
+            .or_else(|| self.opt_memo(Rule::LineComment, Self::parse_line_comment))
+        {
+            children.push(trivia.into());
+        }
+        if let Some(header) = self.opt_parse(Self::parse_document_header) {
+            children.push(header.into());
+        }
+        children.extend(self.parse_document_content()?);
+        Ok(SyntaxNode::new(SyntaxKind::Document, children))
+    }
+}
+
+/// Splits the trailing whitespace of `text` into its own token, so it doesn't end up in the
+/// value of the line
+pub(crate) fn text_tokens(kind: SyntaxKind, text: String) -> Vec<SyntaxElement> {
+    let trimmed_len = text.trim_end().len();
+    if trimmed_len == text.len() {
+        return vec![SyntaxToken::new(kind, text).into()];
+    }
+    let mut text = text;
+    let whitespace = text.split_off(trimmed_len);
+    vec![
+        SyntaxToken::new(kind, text).into(),
+        SyntaxToken::new(SyntaxKind::Whitespace, whitespace).into(),
+    ]
+}
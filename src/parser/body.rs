@@ -1,27 +1,478 @@
+use std::rc::Rc;
+
 use crate::{
-    ast::{Attribute, Block, BlockContent, DocumentContent, SectionContent},
+    ast::{BreakKind, DelimitedBlockKind, ListKind, NumberingStyle},
     checkpoint_iterator::CheckpointIterator,
+    cst::{SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken},
     parser::traits::header::DocSectionHeading,
 };
 
-use super::traits::{
-    body::{
-        DocAttributeParser, DocAttributesParser, DocBlockParser, DocContentParser,
-        DocSectionContentParser,
+use super::{
+    ParseError, Rule, text_tokens,
+    traits::{
+        body::{
+            DocAttributeEntryParser, DocAttributeParser, DocAttributesParser, DocBlockAnchorParser,
+            DocBlockParser, DocBlockTitleParser, DocContentParser, DocDelimitedBlockContentParser,
+            DocListContentParser, DocSectionContentParser, DocTriviaParser,
+            DocUndelimitedBlockContentParser,
+        },
+        header::{NewlineParser, TextLineParser},
     },
-    header::LineParser,
 };
 
+fn is_line_end(c: &char) -> bool {
+    *c == '\n' || *c == '\r'
+}
+
+fn is_inline_whitespace(c: &char) -> bool {
+    *c == ' ' || *c == '\t'
+}
+
+/// Returns the kind of delimited block opened or closed by `line`, if it is a delimiter line
+pub(crate) fn delimiter_kind(line: &str) -> Option<DelimitedBlockKind> {
+    let line = line.trim_end();
+    if line == "--" {
+        return Some(DelimitedBlockKind::Open);
+    }
+    let first = line.chars().next()?;
+    if line.len() < 4 || line.chars().any(|c| c != first) {
+        return None;
+    }
+    match first {
+        '=' => Some(DelimitedBlockKind::Example),
+        '-' => Some(DelimitedBlockKind::Listing),
+        '.' => Some(DelimitedBlockKind::Literal),
+        '*' => Some(DelimitedBlockKind::Sidebar),
+        '_' => Some(DelimitedBlockKind::Quote),
+        '+' => Some(DelimitedBlockKind::Passthrough),
+        '/' => Some(DelimitedBlockKind::Comment),
+        _ => None,
+    }
+}
+
+/// Returns the kind of break made by `line`, if it is a break line
+pub(crate) fn break_kind(line: &str) -> Option<BreakKind> {
+    let line = line.trim_end();
+    let first = line.chars().next()?;
+    if line.len() < 3 || line.chars().any(|c| c != first) {
+        return None;
+    }
+    match first {
+        '\'' => Some(BreakKind::Thematic),
+        '-' | '*' if line.len() == 3 => Some(BreakKind::Thematic),
+        '<' => Some(BreakKind::Page),
+        _ => None,
+    }
+}
+
+/// Returns the marker of the list item on `line`, if it is one
+pub(crate) fn list_marker(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start_matches(|c| is_inline_whitespace(&c));
+    let is_marker = |marker: &[u8]| match marker {
+        [b'-'] => true,
+        m if m.len() <= 5 && m.iter().all(|c| *c == b'*') => true,
+        m if m.len() <= 5 && m.iter().all(|c| *c == b'.') => true,
+        [c, b'.'] if c.is_ascii_alphabetic() => true,
+        [digits @ .., b'.'] => !digits.is_empty() && digits.iter().all(u8::is_ascii_digit),
+        [numeral @ .., b')'] => roman_case(numeral).is_some(),
+        _ => false,
+    };
+    if let Some(marker_end) = trimmed.find([' ', '\t']) {
+        let (marker, text) = trimmed.split_at(marker_end);
+        if !text.trim().is_empty() && is_marker(marker.as_bytes()) {
+            return Some(marker);
+        }
+    }
+    description_marker(line).map(|(_, marker)| marker)
+}
+
+/// Returns the term and marker of the description list item on `line`, the term ends at the
+/// first marker followed by whitespace or the end of the line
+pub(crate) fn description_marker(line: &str) -> Option<(&str, &str)> {
+    if line.starts_with("//") {
+        return None;
+    }
+    split_term(line.trim_start_matches(|c| is_inline_whitespace(&c)))
+}
+
+/// Term and marker of a description list item without its indentation, which may start with
+/// `//` unlike an unindented item
+fn split_term(line: &str) -> Option<(&str, &str)> {
+    line.char_indices().skip(1).find_map(|(i, _)| {
+        let marker = ["::::", ":::", "::", ";;"].into_iter().find(|marker| {
+            line[i..]
+                .strip_prefix(marker)
+                .is_some_and(|after| after.is_empty() || after.starts_with([' ', '\t']))
+        })?;
+        Some((&line[..i], &line[i..i + marker.len()]))
+    })
+}
+
+/// Markers of items belonging to the same list, explicit numerals of ordered lists are
+/// normalised so that `1.` and `2.` are siblings
+pub(crate) fn marker_family(marker: &str) -> String {
+    match marker.as_bytes() {
+        [c, b'.'] if c.is_ascii_lowercase() => "a.".to_string(),
+        [c, b'.'] if c.is_ascii_uppercase() => "A.".to_string(),
+        [digits @ .., b'.'] if !digits.is_empty() && digits.iter().all(u8::is_ascii_digit) => {
+            "1.".to_string()
+        }
+        [numeral @ .., b')'] if roman_case(numeral) == Some(true) => "i)".to_string(),
+        [numeral @ .., b')'] if roman_case(numeral) == Some(false) => "I)".to_string(),
+        _ => marker.to_string(),
+    }
+}
+
+/// Whether `numeral` is a lowercase roman numeral, or `None` if it isn't one
+fn roman_case(numeral: &[u8]) -> Option<bool> {
+    if numeral.is_empty() {
+        None
+    } else if numeral.iter().all(|c| b"ivx".contains(c)) {
+        Some(true)
+    } else if numeral.iter().all(|c| b"IVX".contains(c)) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+fn roman_value(numeral: &str) -> u32 {
+    let digit = |c: char| match c.to_ascii_lowercase() {
+        'i' => 1,
+        'v' => 5,
+        _ => 10,
+    };
+    let mut digits = numeral.chars().map(digit).peekable();
+    let mut value = 0;
+    while let Some(d) = digits.next() {
+        if digits.peek().is_some_and(|next| *next > d) {
+            value -= d as i64;
+        } else {
+            value += d as i64;
+        }
+    }
+    value.max(0) as u32
+}
+
+/// Numbering style and explicit number of an ordered list item, implicit numerals are
+/// styled by their depth
+pub(crate) fn marker_numbering(marker: &str) -> (NumberingStyle, Option<u32>) {
+    match marker.as_bytes() {
+        [c, b'.'] if c.is_ascii_lowercase() => {
+            (NumberingStyle::LowerAlpha, Some((c - b'a' + 1).into()))
+        }
+        [c, b'.'] if c.is_ascii_uppercase() => {
+            (NumberingStyle::UpperAlpha, Some((c - b'A' + 1).into()))
+        }
+        [numeral @ .., b')'] => {
+            let style = match roman_case(numeral) {
+                Some(false) => NumberingStyle::UpperRoman,
+                _ => NumberingStyle::LowerRoman,
+            };
+            (style, Some(roman_value(&marker[..marker.len() - 1])))
+        }
+        [.., b'.'] if !marker.starts_with('.') => (
+            NumberingStyle::Arabic,
+            marker[..marker.len() - 1].parse().ok(),
+        ),
+        _ => (NumberingStyle::for_depth(marker.len()), None),
+    }
+}
+
+/// Names of the block macros, other `name::target[]` lines are text like in AsciiDoc
+const BLOCK_MACROS: [&str; 4] = ["audio", "image", "toc", "video"];
+
+pub(crate) fn is_block_macro(name: &str) -> bool {
+    BLOCK_MACROS.contains(&name)
+}
+
+/// Whether `id` can be used as the id of an anchor, i.e. it is an XML name
+pub(crate) fn is_valid_id(id: &str) -> bool {
+    let mut chars = id.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_alphanumeric() || "_:-.".contains(c))
+}
+
+pub(crate) fn list_kind(marker: &str) -> ListKind {
+    if marker.starts_with('*') || marker == "-" {
+        ListKind::Unordered
+    } else if marker.ends_with([':', ';']) {
+        ListKind::Description
+    } else {
+        ListKind::Ordered
+    }
+}
+
+fn peek_line<T: Iterator<Item = char>>(iter: &mut CheckpointIterator<T>) -> Option<String> {
+    iter.lookahead(|s| {
+        s.peek()?;
+        Some(s.take_while_ref(|c| !is_line_end(c)).to_string())
+    })
+}
+
+/// Level of the section heading starting at the current position, skipping over the block
+/// title and attribute lines that would be attached to it
+pub(crate) fn peek_heading_level<T: Iterator<Item = char>>(
+    iter: &mut CheckpointIterator<T>,
+) -> Option<usize> {
+    iter.lookahead(|s| {
+        while s
+            .opt_memo(Rule::BlockTitle, CheckpointIterator::parse_block_title)
+            .or_else(|| s.opt_memo(Rule::BlockAnchor, CheckpointIterator::parse_block_anchor))
+            .or_else(|| s.opt_memo(Rule::Attributes, CheckpointIterator::parse_attributes))
+            .is_some()
+        {}
+        s.opt_memo(
+            Rule::SectionHeading,
+            CheckpointIterator::parse_section_heading,
+        )
+        .map(|h| h.token_text(SyntaxKind::HeadingMarker).len())
+    })
+}
+
+/// Family of the list item after any blank lines at the current position
+fn peek_list_item<T: Iterator<Item = char>>(iter: &mut CheckpointIterator<T>) -> Option<String> {
+    iter.lookahead(|s| {
+        while s
+            .opt_memo(Rule::BlankLine, CheckpointIterator::parse_blank_line)
+            .is_some()
+        {}
+        peek_line(s).and_then(|line| list_marker(&line).map(marker_family))
+    })
+}
+
+fn parse_blank_lines<T: Iterator<Item = char>>(
+    iter: &mut CheckpointIterator<T>,
+    children: &mut Vec<SyntaxElement>,
+) {
+    while let Some(blank) = iter.opt_memo(Rule::BlankLine, CheckpointIterator::parse_blank_line) {
+        children.push(blank.into());
+    }
+}
+
+/// Parses the line at the current position verbatim
+fn parse_raw_line<T: Iterator<Item = char>>(iter: &mut CheckpointIterator<T>) -> SyntaxNode {
+    let text: String = iter.take_while_ref(|c| !is_line_end(c)).to_string();
+    let mut children: Vec<SyntaxElement> = Vec::new();
+    if !text.is_empty() {
+        children.push(SyntaxToken::new(SyntaxKind::Text, text).into());
+    }
+    children.extend(
+        iter.opt_parse(CheckpointIterator::parse_newline)
+            .map(SyntaxElement::from),
+    );
+    SyntaxNode::new(SyntaxKind::Line, children)
+}
+
+/// Parses a delimiter line, the delimiter itself must be checked by the caller
+fn parse_delimiter_line<T: Iterator<Item = char>>(
+    iter: &mut CheckpointIterator<T>,
+) -> Vec<SyntaxElement> {
+    let delimiter: String = iter.take_while_ref(|c| !is_line_end(c)).to_string();
+    let mut children = text_tokens(SyntaxKind::Delimiter, delimiter);
+    children.extend(
+        iter.opt_parse(CheckpointIterator::parse_newline)
+            .map(SyntaxElement::from),
+    );
+    children
+}
+
+/// Parses the next element of a section, document or compound block, the element is always
+/// one of trivia, an attribute entry or a block
+pub(crate) fn parse_element<T: Iterator<Item = char>>(
+    iter: &mut CheckpointIterator<T>,
+    sections: bool,
+) -> Result<SyntaxNode, ParseError> {
+    match iter
+        .opt_memo(Rule::BlankLine, CheckpointIterator::parse_blank_line)
+        .or_else(|| iter.opt_memo(Rule::LineComment, CheckpointIterator::parse_line_comment))
+        .or_else(|| {
+            iter.opt_memo(
+                Rule::AttributeEntry,
+                CheckpointIterator::parse_attribute_entry,
+            )
+        })
+        .or_else(|| {
+            iter.opt_parse(|s| parse_block_with(s, sections))
+                .map(Rc::new)
+        }) {
+        Some(element) => Ok(Rc::unwrap_or_clone(element)),
+        None => iter.parse_undelimited_block(),
+    }
+}
+
+/// Parses the block title, anchor and attribute lines preceding a block
+fn parse_block_metadata<T: Iterator<Item = char>>(
+    iter: &mut CheckpointIterator<T>,
+) -> Vec<SyntaxElement> {
+    let mut metadata: Vec<SyntaxElement> = Vec::new();
+    while let Some(line) = iter
+        .opt_memo(Rule::BlockTitle, CheckpointIterator::parse_block_title)
+        .or_else(|| iter.opt_memo(Rule::BlockAnchor, CheckpointIterator::parse_block_anchor))
+        .or_else(|| iter.opt_memo(Rule::Attributes, CheckpointIterator::parse_attributes))
+    {
+        metadata.push(line.into());
+    }
+    metadata
+}
+
+/// Parses the metadata and heading of a section, leaving its content to the caller
+pub(crate) fn parse_section_start<T: Iterator<Item = char>>(
+    iter: &mut CheckpointIterator<T>,
+) -> Result<Vec<SyntaxElement>, ParseError> {
+    let mut children = parse_block_metadata(iter);
+    children.push(
+        iter.memo_parse(
+            Rule::SectionHeading,
+            CheckpointIterator::parse_section_heading,
+        )?
+        .into(),
+    );
+    Ok(children)
+}
+
+fn parse_block_with<T: Iterator<Item = char>>(
+    iter: &mut CheckpointIterator<T>,
+    sections: bool,
+) -> Result<SyntaxNode, ParseError> {
+    let metadata = parse_block_metadata(iter);
+    let block = if sections {
+        iter.opt_memo(Rule::SectionBlock, CheckpointIterator::parse_section_block)
+    } else {
+        None
+    };
+    let mut block = match block
+        .or_else(|| {
+            iter.opt_memo(
+                Rule::DelimitedBlock,
+                CheckpointIterator::parse_delimited_block,
+            )
+        })
+        .or_else(|| iter.opt_memo(Rule::BreakBlock, CheckpointIterator::parse_break_block))
+        .or_else(|| iter.opt_memo(Rule::MacroBlock, CheckpointIterator::parse_macro_block))
+        .or_else(|| iter.opt_memo(Rule::ListBlock, CheckpointIterator::parse_list_block))
+    {
+        // Only the top level is copied when the node is shared with the memo table
+        Some(block) => Rc::unwrap_or_clone(block),
+        None => iter.parse_undelimited_block()?,
+    };
+    block.children.splice(0..0, metadata);
+    Ok(block)
+}
+
+/// Splits the `[x]`, `[*]` or `[ ]` checkbox of a checklist item off the start of its text
+fn split_checkbox(text: &str) -> Option<(&str, &str)> {
+    let rest = ["[ ]", "[x]", "[*]"]
+        .into_iter()
+        .find_map(|checkbox| text.strip_prefix(checkbox))?;
+    let text_start = rest.trim_start_matches([' ', '\t']);
+    (text_start.len() < rest.len() && !text_start.is_empty()).then(|| text.split_at(3))
+}
+
+fn is_list_continuation(line: &str) -> bool {
+    line.trim_end_matches([' ', '\t']) == "+"
+}
+
+/// Parses the lines directly following a list item, which are part of its text or of the
+/// paragraph attached to it
+fn parse_item_lines<T: Iterator<Item = char>>(
+    iter: &mut CheckpointIterator<T>,
+    children: &mut Vec<SyntaxElement>,
+) {
+    while let Some(line) = peek_line(iter) {
+        if list_marker(&line).is_some() || is_list_continuation(&line) {
+            break;
+        }
+        match iter
+            .opt_memo(Rule::LineComment, CheckpointIterator::parse_line_comment)
+            .or_else(|| {
+                iter.opt_memo(
+                    Rule::UndelimitedBlockContent,
+                    CheckpointIterator::parse_undelimited_block_content,
+                )
+            }) {
+            Some(line) => children.push(line.into()),
+            None => break,
+        }
+    }
+}
+
+/// Parses a `+` line and the delimited block or paragraph it attaches to the list item
+/// before it
+fn parse_attached_block<T: Iterator<Item = char>>(
+    iter: &mut CheckpointIterator<T>,
+) -> Result<Vec<SyntaxElement>, ParseError> {
+    if !peek_line(iter).is_some_and(|line| is_list_continuation(&line)) {
+        return Err(iter.error("Expected '+' to attach a block".to_string()));
+    }
+    let continuation: String = iter.take_while_ref(|c| !is_line_end(c)).to_string();
+    let mut children = text_tokens(SyntaxKind::ListContinuation, continuation);
+    children.extend(
+        iter.opt_parse(CheckpointIterator::parse_newline)
+            .map(SyntaxElement::from),
+    );
+    let metadata = parse_block_metadata(iter);
+    let mut block = match iter.opt_memo(
+        Rule::DelimitedBlock,
+        CheckpointIterator::parse_delimited_block,
+    ) {
+        Some(block) => Rc::unwrap_or_clone(block),
+        None => {
+            let first = peek_line(iter)
+                .filter(|line| list_marker(line).is_none() && !is_list_continuation(line))
+                .and_then(|_| {
+                    iter.opt_memo(
+                        Rule::UndelimitedBlockContent,
+                        CheckpointIterator::parse_undelimited_block_content,
+                    )
+                })
+                .ok_or_else(|| iter.error("Expected a block to attach".to_string()))?;
+            let mut lines = vec![first.into()];
+            parse_item_lines(iter, &mut lines);
+            SyntaxNode::new(SyntaxKind::Paragraph, lines)
+        }
+    };
+    block.children.splice(0..0, metadata);
+    children.push(block.into());
+    Ok(children)
+}
+
+fn parse_list<T: Iterator<Item = char>>(
+    iter: &mut CheckpointIterator<T>,
+    ancestors: &[String],
+) -> Result<SyntaxNode, ParseError> {
+    let family = peek_line(iter)
+        .and_then(|line| list_marker(&line).map(marker_family))
+        .ok_or_else(|| iter.error("Expected a list item".to_string()))?;
+    if ancestors.contains(&family) {
+        return Err(iter.error(format!("List item '{family}' belongs to an enclosing list")));
+    }
+    let mut markers = ancestors.to_vec();
+    markers.push(family.clone());
+
+    let mut children: Vec<SyntaxElement> = Vec::new();
+    loop {
+        children.push(iter.parse_list_content(&markers)?.into());
+        if peek_list_item(iter).as_ref() != Some(&family) {
+            break;
+        }
+        parse_blank_lines(iter, &mut children);
+    }
+    Ok(SyntaxNode::new(SyntaxKind::List, children))
+}
+
 impl<T> DocContentParser for CheckpointIterator<T>
 where
     T: Iterator<Item = char>,
 {
-    fn parse_document_content(&mut self) -> Result<DocumentContent, super::ParseError> {
-        let mut blocks = Vec::new();
-        while let Ok(block) = self.parse_block() {
-            blocks.push(block);
+    fn parse_document_content(&mut self) -> Result<Vec<SyntaxElement>, super::ParseError> {
+        let mut content = Vec::new();
+        while self.peek().is_some() {
+            content.push(parse_element(self, true)?.into());
         }
-        Ok(DocumentContent { blocks })
+        Ok(content)
     }
 }
 
@@ -29,40 +480,146 @@ impl<T> DocBlockParser for CheckpointIterator<T>
 where
     T: Iterator<Item = char>,
 {
-    fn parse_block(&mut self) -> Result<Block, super::ParseError> {
-        self.parse_section_block()
-            .or_else(|e| {
-                log::error!("{e:?}");
-                self.parse_list_block()
-            })
-            .or_else(|e| {
-                log::error!("{e:?}");
-                self.parse_delimited_block()
-            })
-            .or_else(|e| {
-                log::error!("{e:?}");
-                self.parse_undelimited_block()
-            })
+    fn parse_block(&mut self) -> Result<SyntaxNode, super::ParseError> {
+        parse_block_with(self, true)
     }
 
-    fn parse_section_block(&mut self) -> Result<Block, super::ParseError> {
-        Ok(Block {
-            heading: self.parse_section_heading()?,
-            attributes: self.opt_parse(Self::parse_attributes).unwrap_or(Vec::new()),
-            content: BlockContent::Section(self.parse_section_content()?),
-        })
+    fn parse_section_block(&mut self) -> Result<SyntaxNode, super::ParseError> {
+        let heading = self.memo_parse(Rule::SectionHeading, Self::parse_section_heading)?;
+        let level = heading.token_text(SyntaxKind::HeadingMarker).len();
+        let mut children = vec![heading.into()];
+        children.extend(self.parse_section_content(level)?);
+        Ok(SyntaxNode::new(SyntaxKind::Section, children))
     }
 
-    fn parse_list_block(&mut self) -> Result<Block, super::ParseError> {
-        todo!("List parsing is not implemented")
+    fn parse_list_block(&mut self) -> Result<SyntaxNode, super::ParseError> {
+        parse_list(self, &[])
     }
 
-    fn parse_delimited_block(&mut self) -> Result<Block, super::ParseError> {
-        todo!("Delimited block parsing is not implemented")
+    fn parse_delimited_block(&mut self) -> Result<SyntaxNode, super::ParseError> {
+        let delimiter = peek_line(self)
+            .filter(|line| delimiter_kind(line).is_some())
+            .ok_or_else(|| self.error("Expected a block delimiter".to_string()))?;
+        let mut children = parse_delimiter_line(self);
+        children.extend(self.parse_delimited_block_content(delimiter.trim_end())?);
+        Ok(SyntaxNode::new(SyntaxKind::DelimitedBlock, children))
     }
 
-    fn parse_undelimited_block(&mut self) -> Result<Block, super::ParseError> {
-        todo!("Undelimited block parsing is not implemented")
+    fn parse_break_block(&mut self) -> Result<SyntaxNode, super::ParseError> {
+        peek_line(self)
+            .filter(|line| break_kind(line).is_some())
+            .ok_or_else(|| self.error("Expected a break".to_string()))?;
+        let marker: String = self.take_while_ref(|c| !is_line_end(c)).to_string();
+        let mut children = text_tokens(SyntaxKind::BreakMarker, marker);
+        children.extend(self.opt_parse(Self::parse_newline).map(SyntaxElement::from));
+        Ok(SyntaxNode::new(SyntaxKind::Break, children))
+    }
+
+    fn parse_macro_block(&mut self) -> Result<SyntaxNode, super::ParseError> {
+        let name: String = self
+            .take_while_ref(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+            .to_string();
+        if !is_block_macro(&name) {
+            return Err(self.error(format!("'{name}' is not a block macro")));
+        }
+        let colons: String = self.take_while_ref(|c| *c == ':').to_string();
+        if colons != "::" {
+            return Err(self.error("Expected '::' after the name of a block macro".to_string()));
+        }
+        let target: String = self
+            .take_while_ref(|c| *c != '[' && !c.is_whitespace())
+            .to_string();
+        let mut children = vec![
+            SyntaxToken::new(SyntaxKind::MacroName, name).into(),
+            SyntaxToken::new(SyntaxKind::Colon, colons).into(),
+        ];
+        if !target.is_empty() {
+            children.push(SyntaxToken::new(SyntaxKind::MacroTarget, target).into());
+        }
+        children.push(self.parse_attributes()?.into());
+        Ok(SyntaxNode::new(SyntaxKind::BlockMacro, children))
+    }
+
+    fn parse_undelimited_block(&mut self) -> Result<SyntaxNode, super::ParseError> {
+        let mut children = vec![self.parse_undelimited_block_content()?.into()];
+        while let Some(line) = self
+            .opt_memo(Rule::LineComment, Self::parse_line_comment)
+            .or_else(|| {
+                self.opt_memo(
+                    Rule::UndelimitedBlockContent,
+                    Self::parse_undelimited_block_content,
+                )
+            })
+        {
+            children.push(line.into());
+        }
+        Ok(SyntaxNode::new(SyntaxKind::Paragraph, children))
+    }
+}
+
+impl<T> DocBlockTitleParser for CheckpointIterator<T>
+where
+    T: Iterator<Item = char>,
+{
+    fn parse_block_title(&mut self) -> Result<SyntaxNode, super::ParseError> {
+        if let Some('.') = self.next() {
+        } else {
+            return Err(self.error("Expected '.' for the start of a block title".to_string()));
+        }
+        match self.peek() {
+            Some(c) if !c.is_whitespace() && c != '.' => {}
+            _ => return Err(self.error("Expected the text of the block title".to_string())),
+        }
+        let text: String = self.take_while_ref(|c| !is_line_end(c)).to_string();
+        let mut children = vec![SyntaxToken::new(SyntaxKind::BlockTitleMarker, ".").into()];
+        children.extend(text_tokens(SyntaxKind::Text, text));
+        children.extend(self.opt_parse(Self::parse_newline).map(SyntaxElement::from));
+        Ok(SyntaxNode::new(SyntaxKind::BlockTitle, children))
+    }
+}
+
+impl<T> DocBlockAnchorParser for CheckpointIterator<T>
+where
+    T: Iterator<Item = char>,
+{
+    fn parse_block_anchor(&mut self) -> Result<SyntaxNode, super::ParseError> {
+        let open: String = self.take_while_ref(|c| *c == '[').to_string();
+        if open != "[[" {
+            return Err(self.error("Expected '[[' for the start of an anchor".to_string()));
+        }
+        let id: String = self
+            .take_while_ref(|c| *c != ',' && *c != ']' && !c.is_whitespace())
+            .to_string();
+        if !is_valid_id(&id) {
+            return Err(self.error(format!("'{id}' is not a valid anchor id")));
+        }
+        let mut children = vec![
+            SyntaxToken::new(SyntaxKind::AnchorOpen, open).into(),
+            SyntaxToken::new(SyntaxKind::AnchorId, id).into(),
+        ];
+        if let Some(',') = self.peek() {
+            self.next();
+            children.push(SyntaxToken::new(SyntaxKind::Comma, ",").into());
+            let reftext: String = self
+                .take_while_ref(|c| *c != ']' && !is_line_end(c))
+                .to_string();
+            children.extend(text_tokens(SyntaxKind::Text, reftext));
+        }
+        let close: String = self.take_while_ref(|c| *c == ']').to_string();
+        if close != "]]" {
+            return Err(self.error("Expected ']]' for the end of an anchor".to_string()));
+        }
+        children.push(SyntaxToken::new(SyntaxKind::AnchorClose, close).into());
+        let whitespace: String = self.take_while_ref(is_inline_whitespace).to_string();
+        if !whitespace.is_empty() {
+            children.push(SyntaxToken::new(SyntaxKind::Whitespace, whitespace).into());
+        }
+        match self.opt_parse(Self::parse_newline) {
+            Some(newline) => children.push(newline.into()),
+            None if self.peek().is_none() => {}
+            None => return Err(self.error("Expected the anchor to end the line".to_string())),
+        }
+        Ok(SyntaxNode::new(SyntaxKind::BlockAnchor, children))
     }
 }
 
@@ -70,26 +627,52 @@ impl<T> DocAttributesParser for CheckpointIterator<T>
 where
     T: Iterator<Item = char>,
 {
-    fn parse_attributes(&mut self) -> Result<Vec<Attribute>, super::ParseError> {
+    fn parse_attributes(&mut self) -> Result<SyntaxNode, super::ParseError> {
         if let Some('[') = self.next() {
         } else {
             return Err(
                 self.error("Expected '[' for the start of a list of attributes".to_string())
             );
         }
-        let mut attributes = Vec::new();
-        while let Ok(attribute) = self.parse_attribute() {
-            attributes.push(attribute);
-            if let Some(',') = self.next() {
-            } else {
-                return Err(self.error("Expected ',' after a attribute".to_string()));
+        if let Some('[') = self.peek() {
+            return Err(self.error("Expected an attribute, found an anchor".to_string()));
+        }
+        let mut children = vec![SyntaxToken::new(SyntaxKind::LBracket, "[").into()];
+        loop {
+            if let Some(attribute) = self.opt_parse(Self::parse_attribute) {
+                children.push(attribute.into());
+            }
+            match self.next() {
+                Some(',') => {
+                    children.push(SyntaxToken::new(SyntaxKind::Comma, ",").into());
+                    let whitespace: String = self.take_while_ref(is_inline_whitespace).to_string();
+                    if !whitespace.is_empty() {
+                        children.push(SyntaxToken::new(SyntaxKind::Whitespace, whitespace).into());
+                    }
+                }
+                Some(']') => {
+                    children.push(SyntaxToken::new(SyntaxKind::RBracket, "]").into());
+                    break;
+                }
+                _ => {
+                    return Err(
+                        self.error("Expected ']' for the end of a list of attributes".to_string())
+                    );
+                }
             }
         }
-        if let Some(']') = self.next() {
-        } else {
-            return Err(self.error("Expected ']' for the end of a list of attributes".to_string()));
+        let whitespace: String = self.take_while_ref(is_inline_whitespace).to_string();
+        if !whitespace.is_empty() {
+            children.push(SyntaxToken::new(SyntaxKind::Whitespace, whitespace).into());
+        }
+        match self.opt_parse(Self::parse_newline) {
+            Some(newline) => children.push(newline.into()),
+            None if self.peek().is_none() => {}
+            None => {
+                return Err(self.error("Expected the attribute list to end the line".to_string()));
+            }
         }
-        Ok(attributes)
+        Ok(SyntaxNode::new(SyntaxKind::AttributeList, children))
     }
 }
 
@@ -97,24 +680,91 @@ impl<T> DocAttributeParser for CheckpointIterator<T>
 where
     T: Iterator<Item = char>,
 {
-    fn parse_attribute(&mut self) -> Result<Attribute, super::ParseError> {
+    fn parse_attribute(&mut self) -> Result<SyntaxNode, super::ParseError> {
+        let parse_value = |s: &mut Self| -> Result<String, ParseError> {
+            if let Some('"') = s.peek() {
+                s.next();
+                let value: String = s
+                    .take_while_ref(|c| *c != '"' && !is_line_end(c))
+                    .to_string();
+                if let Some('"') = s.next() {
+                    Ok(format!("\"{value}\""))
+                } else {
+                    Err(s.error("Matching '\"' not found for '\"'".to_string()))
+                }
+            } else {
+                Ok(
+                    s.take_while_ref(|c| *c != ']' && *c != ',' && !is_line_end(c))
+                        .to_string(),
+                )
+            }
+        };
+        if let Some('"') = self.peek() {
+            let value = parse_value(self)?;
+            return Ok(SyntaxNode::new(
+                SyntaxKind::Attribute,
+                vec![SyntaxToken::new(SyntaxKind::AttributeValue, value).into()],
+            ));
+        }
         let key: String = self
-            .take_while_ref(|c| *c != '=' && *c != ']' && *c != ',' && !c.is_ascii_control())
-            .collect();
+            .take_while_ref(|c| *c != '=' && *c != ']' && *c != ',' && !is_line_end(c))
+            .to_string();
         if key.is_empty() {
             return Err(self.error("Empty key for attribute".to_string()));
         }
-        let value: Option<String> = self.opt_parse(|s| {
-            if let Some('=') = s.next() {
-                Ok(
-                    s.take_while_ref(|c| *c != ']' && *c != ',' && !c.is_ascii_control())
-                        .collect(),
-                )
-            } else {
-                Err(s.error("Expect '=' for specifying value of keys in attributes".to_string()))
-            }
-        });
-        Ok(Attribute { key, value })
+        let mut children = vec![SyntaxToken::new(SyntaxKind::AttributeName, key).into()];
+        if let Some('=') = self.peek() {
+            self.next();
+            children.push(SyntaxToken::new(SyntaxKind::Eq, "=").into());
+            children.push(SyntaxToken::new(SyntaxKind::AttributeValue, parse_value(self)?).into());
+        }
+        Ok(SyntaxNode::new(SyntaxKind::Attribute, children))
+    }
+}
+
+impl<T> DocAttributeEntryParser for CheckpointIterator<T>
+where
+    T: Iterator<Item = char>,
+{
+    fn parse_attribute_entry(&mut self) -> Result<SyntaxNode, super::ParseError> {
+        if let Some(':') = self.next() {
+        } else {
+            return Err(self.error("Expected ':' for the start of an attribute entry".to_string()));
+        }
+        let mut children = vec![SyntaxToken::new(SyntaxKind::Colon, ":").into()];
+        if let Some('!') = self.peek() {
+            self.next();
+            children.push(SyntaxToken::new(SyntaxKind::Bang, "!").into());
+        }
+        let name: String = self
+            .take_while_ref(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+            .to_string();
+        if name.is_empty() {
+            return Err(self.error("Expected the name of the attribute".to_string()));
+        }
+        children.push(SyntaxToken::new(SyntaxKind::AttributeName, name).into());
+        if let Some('!') = self.peek() {
+            self.next();
+            children.push(SyntaxToken::new(SyntaxKind::Bang, "!").into());
+        }
+        if let Some(':') = self.next() {
+        } else {
+            return Err(self.error("Expected ':' for the end of the attribute name".to_string()));
+        }
+        children.push(SyntaxToken::new(SyntaxKind::Colon, ":").into());
+        let whitespace: String = self.take_while_ref(is_inline_whitespace).to_string();
+        let value: String = self.take_while_ref(|c| !is_line_end(c)).to_string();
+        if whitespace.is_empty() && !value.is_empty() {
+            return Err(self.error("Expected ' ' after the attribute name".to_string()));
+        }
+        if !whitespace.is_empty() {
+            children.push(SyntaxToken::new(SyntaxKind::Whitespace, whitespace).into());
+        }
+        if !value.is_empty() {
+            children.extend(text_tokens(SyntaxKind::AttributeValue, value));
+        }
+        children.extend(self.opt_parse(Self::parse_newline).map(SyntaxElement::from));
+        Ok(SyntaxNode::new(SyntaxKind::AttributeEntry, children))
     }
 }
 
@@ -122,14 +772,156 @@ impl<T> DocSectionContentParser for CheckpointIterator<T>
 where
     T: Iterator<Item = char>,
 {
-    fn parse_section_content(&mut self) -> Result<Vec<SectionContent>, super::ParseError> {
+    fn parse_section_content(
+        &mut self,
+        level: usize,
+    ) -> Result<Vec<SyntaxElement>, super::ParseError> {
         let mut content = Vec::new();
-        while let Ok(content_block) = self.parse_block().map(SectionContent::Block).or_else(|e| {
-            log::error!("{e:?}");
-            self.parse_line().map(SectionContent::Text)
-        }) {
-            content.push(content_block)
+        while self.peek().is_some() {
+            if peek_heading_level(self).is_some_and(|l| l <= level) {
+                break;
+            }
+            content.push(parse_element(self, true)?.into());
         }
         Ok(content)
     }
 }
+
+impl<T> DocDelimitedBlockContentParser for CheckpointIterator<T>
+where
+    T: Iterator<Item = char>,
+{
+    fn parse_delimited_block_content(
+        &mut self,
+        delimiter: &str,
+    ) -> Result<Vec<SyntaxElement>, super::ParseError> {
+        let kind = delimiter_kind(delimiter)
+            .ok_or_else(|| self.error(format!("'{delimiter}' is not a block delimiter")))?;
+        let mut content = Vec::new();
+        loop {
+            let Some(line) = peek_line(self) else {
+                log::warn!("Unterminated delimited block, expected '{delimiter}'");
+                break;
+            };
+            if line.trim_end() == delimiter {
+                content.extend(parse_delimiter_line(self));
+                break;
+            }
+            if kind.is_verbatim() {
+                content.push(parse_raw_line(self).into());
+            } else {
+                content.push(parse_element(self, false)?.into());
+            }
+        }
+        Ok(content)
+    }
+}
+
+impl<T> DocUndelimitedBlockContentParser for CheckpointIterator<T>
+where
+    T: Iterator<Item = char>,
+{
+    fn parse_undelimited_block_content(&mut self) -> Result<SyntaxNode, super::ParseError> {
+        match peek_line(self) {
+            Some(line) if delimiter_kind(&line).is_some() => {
+                Err(self.error("Expected a line of text, found a block delimiter".to_string()))
+            }
+            Some(_) => self.parse_text_line(SyntaxKind::Line),
+            None => Err(self.error("Expected a line of text".to_string())),
+        }
+    }
+}
+
+impl<T> DocListContentParser for CheckpointIterator<T>
+where
+    T: Iterator<Item = char>,
+{
+    fn parse_list_content(&mut self, markers: &[String]) -> Result<SyntaxNode, super::ParseError> {
+        let mut children: Vec<SyntaxElement> = Vec::new();
+        let whitespace: String = self.take_while_ref(is_inline_whitespace).to_string();
+        if !whitespace.is_empty() {
+            children.push(SyntaxToken::new(SyntaxKind::Whitespace, whitespace).into());
+        }
+        let description = markers
+            .last()
+            .is_some_and(|m| list_kind(m) == ListKind::Description);
+        if description {
+            let term = peek_line(self)
+                .and_then(|line| split_term(&line).map(|(term, _)| term.to_string()))
+                .ok_or_else(|| self.error("Expected the term of a description".to_string()))?;
+            let term: String = self.by_ref().take(term.chars().count()).collect();
+            children.extend(text_tokens(SyntaxKind::Term, term));
+        }
+        let marker: String = if description {
+            self.take_while_ref(|c| *c == ':' || *c == ';').to_string()
+        } else {
+            self.take_while_ref(|c| !c.is_whitespace()).to_string()
+        };
+        if markers.last() != Some(&marker_family(&marker)) {
+            return Err(self.error(format!("Expected a list item, found '{marker}'")));
+        }
+        let checklist = list_kind(&marker) == ListKind::Unordered;
+        children.push(SyntaxToken::new(SyntaxKind::ListMarker, marker).into());
+        let whitespace: String = self.take_while_ref(is_inline_whitespace).to_string();
+        let mut text: String = self.take_while_ref(|c| !is_line_end(c)).to_string();
+        // The description may start on the next line
+        if !description && (whitespace.is_empty() || text.trim().is_empty()) {
+            return Err(self.error("Expected the text of the list item".to_string()));
+        }
+        if !whitespace.is_empty() {
+            children.push(SyntaxToken::new(SyntaxKind::Whitespace, whitespace).into());
+        }
+        if checklist && let Some((checkbox, rest)) = split_checkbox(&text) {
+            let (whitespace, rest) =
+                rest.split_at(rest.len() - rest.trim_start_matches([' ', '\t']).len());
+            children.push(SyntaxToken::new(SyntaxKind::Checkbox, checkbox).into());
+            children.push(SyntaxToken::new(SyntaxKind::Whitespace, whitespace).into());
+            text = rest.to_string();
+        }
+        if !text.is_empty() {
+            children.extend(text_tokens(SyntaxKind::Text, text));
+        }
+        children.extend(self.opt_parse(Self::parse_newline).map(SyntaxElement::from));
+
+        parse_item_lines(self, &mut children);
+        while let Some(attached) = self.opt_parse(parse_attached_block) {
+            children.extend(attached);
+        }
+
+        while peek_list_item(self).is_some_and(|family| !markers.contains(&family)) {
+            parse_blank_lines(self, &mut children);
+            children.push(parse_list(self, markers)?.into());
+        }
+        Ok(SyntaxNode::new(SyntaxKind::ListItem, children))
+    }
+}
+
+impl<T> DocTriviaParser for CheckpointIterator<T>
+where
+    T: Iterator<Item = char>,
+{
+    fn parse_blank_line(&mut self) -> Result<SyntaxNode, super::ParseError> {
+        let whitespace: String = self.take_while_ref(is_inline_whitespace).to_string();
+        let mut children: Vec<SyntaxElement> = Vec::new();
+        if !whitespace.is_empty() {
+            children.push(SyntaxToken::new(SyntaxKind::Whitespace, whitespace).into());
+        }
+        match self.opt_parse(Self::parse_newline) {
+            Some(newline) => children.push(newline.into()),
+            None if self.peek().is_none() && !children.is_empty() => {}
+            None => return Err(self.error("Expected a blank line".to_string())),
+        }
+        Ok(SyntaxNode::new(SyntaxKind::BlankLine, children))
+    }
+
+    fn parse_line_comment(&mut self) -> Result<SyntaxNode, super::ParseError> {
+        let line = peek_line(self)
+            .filter(|line| line.starts_with("//") && delimiter_kind(line).is_none())
+            .ok_or_else(|| self.error("Expected '//' for the start of a comment".to_string()))?;
+        let comment: String = self.take_while_ref(|c| !is_line_end(c)).to_string();
+        debug_assert_eq!(comment, line);
+        let mut children = vec![SyntaxToken::new(SyntaxKind::Comment, comment).into()];
+        children.extend(self.opt_parse(Self::parse_newline).map(SyntaxElement::from));
+        Ok(SyntaxNode::new(SyntaxKind::LineComment, children))
+    }
+}
@@ -0,0 +1,249 @@
+use std::borrow::Cow;
+
+use crate::{
+    ast::{
+        Block, BlockContent, DelimitedBlockContent, DescriptionListContent, Document,
+        DocumentContent, DocumentHeader, ListContent, ListKind, NumberingStyle, OrderedListContent,
+        SectionContent, SectionHeading, UndelimitedBlockContent, UnorderedListContent,
+    },
+    cst::TextRange,
+    sections,
+};
+
+use super::{
+    ParseError,
+    events::{Event, Tag},
+};
+
+/// Builds the abstract syntax tree of a document from its [`Event`]s, each with the range of
+/// the source text it was parsed from, see [`super::events::Events::with_ranges`]
+pub fn build_document<'a, I>(events: I) -> Result<Document<'a>, ParseError>
+where
+    I: Iterator<Item = Result<(Event<'a>, TextRange), ParseError>>,
+{
+    let mut events = Cursor {
+        events,
+        range: TextRange::default(),
+    };
+    let mut header = None;
+    let mut blocks = Vec::new();
+    while let Some(event) = events.next_event() {
+        match event? {
+            Event::Start(Tag::Header) => header = Some(build_header(&mut events)?),
+            Event::Start(tag) => blocks.push(build_block(tag, &mut events)?),
+            event => return Err(events.unexpected(event)),
+        }
+    }
+    let mut document = Document {
+        header,
+        content: DocumentContent { blocks },
+    };
+    sections::assign(&mut document);
+    Ok(document)
+}
+
+fn build_header<'a, I>(events: &mut Cursor<I>) -> Result<DocumentHeader<'a>, ParseError>
+where
+    I: Iterator<Item = Result<(Event<'a>, TextRange), ParseError>>,
+{
+    let mut header = DocumentHeader {
+        title: None,
+        authors: Vec::new(),
+        revision: None,
+        attributes: Vec::new(),
+    };
+    loop {
+        match events.expect_event()? {
+            Event::Start(Tag::Heading { level }) => {
+                header.title = Some(SectionHeading {
+                    level,
+                    text: build_text(&Tag::Heading { level }, events)?,
+                    id: None,
+                    number: None,
+                })
+            }
+            Event::Attribute(attribute) => header.attributes.push(attribute),
+            Event::Author(author) => header.authors.push(author),
+            Event::Revision(revision) => header.revision = Some(revision),
+            Event::End(Tag::Header) => return Ok(header),
+            event => return Err(events.unexpected(event)),
+        }
+    }
+}
+
+fn build_block<'a, I>(tag: Tag, events: &mut Cursor<I>) -> Result<Block<'a>, ParseError>
+where
+    I: Iterator<Item = Result<(Event<'a>, TextRange), ParseError>>,
+{
+    let mut block = Block {
+        heading: None,
+        title: None,
+        attributes: Vec::new(),
+        content: BlockContent::Undelimited(Vec::new()),
+    };
+    let mut text = Cow::Borrowed("");
+    let mut blocks = Vec::new();
+    let mut items = Vec::new();
+    let mut block_macro = None;
+    loop {
+        match events.expect_event()? {
+            Event::Start(Tag::Heading { level }) => {
+                block.heading = Some(SectionHeading {
+                    level,
+                    text: build_text(&Tag::Heading { level }, events)?,
+                    id: None,
+                    number: None,
+                })
+            }
+            Event::Start(Tag::BlockTitle) => {
+                let title = build_text(&Tag::BlockTitle, events)?;
+                block.title.get_or_insert(title);
+            }
+            Event::Start(Tag::ListItem) => items.push(build_list_item(&tag, events)?),
+            Event::Start(child) => blocks.push(build_block(child, events)?),
+            Event::Attribute(attribute) => block.attributes.push(attribute),
+            Event::Macro(m) => block_macro = Some(m),
+            Event::Text(t) => push_text(&mut text, t),
+            Event::SoftBreak => text.to_mut().push('\n'),
+            Event::End(end) if end == tag => break,
+            event => return Err(events.unexpected(event)),
+        }
+    }
+    block.content = match tag {
+        Tag::Section { .. } => {
+            BlockContent::Section(blocks.into_iter().map(SectionContent::Block).collect())
+        }
+        Tag::Paragraph => BlockContent::Undelimited(vec![UndelimitedBlockContent::Text(text)]),
+        Tag::DelimitedBlock(kind) if kind.is_verbatim() => {
+            BlockContent::Delimited(kind, vec![DelimitedBlockContent::Text(text)])
+        }
+        Tag::DelimitedBlock(kind) => BlockContent::Delimited(
+            kind,
+            blocks
+                .into_iter()
+                .map(DelimitedBlockContent::Block)
+                .collect(),
+        ),
+        Tag::List(_) => BlockContent::List(items),
+        Tag::Break(kind) => BlockContent::Break(kind),
+        Tag::BlockMacro => match block_macro {
+            Some(block_macro) => BlockContent::Macro(block_macro),
+            None => return Err(events.unexpected(Event::End(tag))),
+        },
+        tag => return Err(events.unexpected(Event::Start(tag))),
+    };
+    Ok(block)
+}
+
+fn build_list_item<'a, I>(list: &Tag, events: &mut Cursor<I>) -> Result<ListContent<'a>, ParseError>
+where
+    I: Iterator<Item = Result<(Event<'a>, TextRange), ParseError>>,
+{
+    let mut term = Cow::Borrowed("");
+    let mut checked = None;
+    let mut numbering = (NumberingStyle::Arabic, None);
+    let mut text = Cow::Borrowed("");
+    let mut blocks = Vec::new();
+    let mut sublist = Vec::new();
+    loop {
+        match events.expect_event()? {
+            Event::Start(Tag::Term) => term = build_text(&Tag::Term, events)?,
+            Event::Checkbox(state) => checked = Some(state),
+            Event::Numbering(style, number) => numbering = (style, number),
+            Event::Text(t) => push_text(&mut text, t),
+            Event::SoftBreak => text.to_mut().push('\n'),
+            Event::Start(tag @ Tag::List(_)) => {
+                if let BlockContent::List(items) = build_block(tag, events)?.content {
+                    sublist.extend(items);
+                }
+            }
+            Event::Start(tag) => blocks.push(build_block(tag, events)?),
+            Event::End(Tag::ListItem) => break,
+            event => return Err(events.unexpected(event)),
+        }
+    }
+    Ok(match list {
+        Tag::List(ListKind::Ordered) => ListContent::OrderedList(OrderedListContent {
+            style: numbering.0,
+            number: numbering.1,
+            text,
+            blocks,
+            sublist,
+        }),
+        Tag::List(ListKind::Description) => ListContent::DescriptionList(DescriptionListContent {
+            term,
+            text,
+            blocks,
+            sublist,
+        }),
+        _ => ListContent::UnorderedList(UnorderedListContent {
+            checked,
+            text,
+            blocks,
+            sublist,
+        }),
+    })
+}
+
+/// Text up to the end of `tag`
+fn build_text<'a, I>(tag: &Tag, events: &mut Cursor<I>) -> Result<Cow<'a, str>, ParseError>
+where
+    I: Iterator<Item = Result<(Event<'a>, TextRange), ParseError>>,
+{
+    let mut text = Cow::Borrowed("");
+    loop {
+        match events.expect_event()? {
+            Event::Text(t) => push_text(&mut text, t),
+            Event::SoftBreak => text.to_mut().push('\n'),
+            Event::End(end) if end == *tag => return Ok(text),
+            event => return Err(events.unexpected(event)),
+        }
+    }
+}
+
+/// Appends `t` to `text`, borrowing it when it is the only text
+fn push_text<'a>(text: &mut Cow<'a, str>, t: Cow<'a, str>) {
+    if text.is_empty() {
+        *text = t;
+    } else {
+        text.to_mut().push_str(&t);
+    }
+}
+
+/// Events being built, with the range of the element the last one was parsed from
+struct Cursor<I> {
+    events: I,
+    range: TextRange,
+}
+
+impl<'a, I> Cursor<I>
+where
+    I: Iterator<Item = Result<(Event<'a>, TextRange), ParseError>>,
+{
+    fn next_event(&mut self) -> Option<Result<Event<'a>, ParseError>> {
+        let (event, range) = match self.events.next()? {
+            Ok(next) => next,
+            Err(e) => return Some(Err(e)),
+        };
+        self.range = range;
+        Some(Ok(event))
+    }
+
+    fn expect_event(&mut self) -> Result<Event<'a>, ParseError> {
+        self.next_event().unwrap_or_else(|| {
+            Err(ParseError {
+                start: self.range.end,
+                end: self.range.end,
+                message: "Unexpected end of the events".to_string(),
+            })
+        })
+    }
+
+    fn unexpected(&self, event: Event<'_>) -> ParseError {
+        ParseError {
+            start: self.range.start,
+            end: self.range.end,
+            message: format!("Unexpected event {event:?}"),
+        }
+    }
+}
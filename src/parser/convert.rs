@@ -0,0 +1,108 @@
+use std::{borrow::Cow, iter::Peekable, ops::Range};
+
+use crate::ast::{
+    Block, DescriptionListContent, ListContent, ListKind, OrderedListContent, UnorderedListContent,
+};
+
+use super::body::{list_kind, marker_family, marker_numbering};
+
+/// List item read on its own, before [`nest_list`] nests it by its marker
+pub(crate) struct ListItem<'a> {
+    pub marker: &'a str,
+    /// Term of a description list item
+    pub term: Option<Cow<'a, str>>,
+    pub checked: Option<bool>,
+    pub text: Cow<'a, str>,
+    pub blocks: Vec<Block<'a>>,
+}
+
+/// Text of the lines at the byte `ranges` of `input` joined by newlines, borrowed from `input`
+/// when only a newline separates each line from the next
+pub(crate) fn join_lines<'a>(
+    input: &'a str,
+    ranges: impl IntoIterator<Item = Range<usize>>,
+) -> Cow<'a, str> {
+    let mut text = Cow::Borrowed("");
+    let mut end = 0;
+    for (i, range) in ranges.into_iter().enumerate() {
+        match &mut text {
+            _ if i == 0 => text = Cow::Borrowed(&input[range.clone()]),
+            Cow::Borrowed(joined) if input[end..].starts_with('\n') && range.start == end + 1 => {
+                let start = end - joined.len();
+                text = Cow::Borrowed(&input[start..range.end]);
+            }
+            text => {
+                let text = text.to_mut();
+                text.push('\n');
+                text.push_str(&input[range.clone()]);
+            }
+        }
+        end = range.end;
+    }
+    text
+}
+
+/// Items of the list starting at the next item, items with a different marker than the
+/// enclosing lists start a nested list
+pub(crate) fn nest_list<'a, I>(
+    items: &mut Peekable<I>,
+    ancestors: &[String],
+) -> Vec<ListContent<'a>>
+where
+    I: Iterator<Item = ListItem<'a>>,
+{
+    let Some(family) = items.peek().map(|item| marker_family(item.marker)) else {
+        return Vec::new();
+    };
+    let mut markers = ancestors.to_vec();
+    markers.push(family.clone());
+    let mut list = Vec::new();
+    while let Some(item) = items.next_if(|item| marker_family(item.marker) == family) {
+        let mut sublist = Vec::new();
+        while items
+            .peek()
+            .is_some_and(|item| !markers.contains(&marker_family(item.marker)))
+        {
+            sublist.extend(nest_list(items, &markers));
+        }
+        let ListItem {
+            term,
+            checked,
+            text,
+            blocks,
+            ..
+        } = item;
+        list.push(match list_kind(item.marker) {
+            ListKind::Unordered => ListContent::UnorderedList(UnorderedListContent {
+                checked,
+                text,
+                blocks,
+                sublist,
+            }),
+            ListKind::Ordered => {
+                let (style, number) = marker_numbering(item.marker);
+                ListContent::OrderedList(OrderedListContent {
+                    style,
+                    number,
+                    text,
+                    blocks,
+                    sublist,
+                })
+            }
+            ListKind::Description => ListContent::DescriptionList(DescriptionListContent {
+                term: term.unwrap_or_default(),
+                text,
+                blocks,
+                sublist,
+            }),
+        });
+    }
+    list
+}
+
+/// Name and value of an attribute entry line, without a value when the entry unsets it
+pub(crate) fn split_attribute_entry(line: &str) -> Option<(&str, Option<&str>)> {
+    let (name, value) = line.strip_prefix(':')?.split_once(':')?;
+    let unset = name.starts_with('!') || name.ends_with('!');
+    Some((name.trim_matches('!'), (!unset).then(|| value.trim())))
+}
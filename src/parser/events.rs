@@ -0,0 +1,399 @@
+use std::{borrow::Cow, collections::VecDeque};
+
+use crate::{
+    ast::{
+        Attribute, Author, BlockMacro, BreakKind, DelimitedBlockKind, ListKind, NumberingStyle,
+        Revision,
+    },
+    checkpoint_iterator::CheckpointIterator,
+    cst::{SyntaxElement, SyntaxKind, SyntaxNode, TextRange},
+};
+
+use super::{
+    ParseError, Rule,
+    body::{
+        break_kind, delimiter_kind, list_kind, marker_numbering, parse_element,
+        parse_section_start, peek_heading_level,
+    },
+    header::{attribute_authors, attribute_revision},
+    traits::{
+        body::DocTriviaParser,
+        header::{AuthorsParser, DocHeaderParser, RevisionLineParser},
+    },
+};
+
+/// A block containing other events, opened by [`Event::Start`] and closed by [`Event::End`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Tag {
+    /// The document header, holding the title, [`Event::Attribute`] entries, [`Event::Author`]s
+    /// and [`Event::Revision`]
+    Header,
+    /// Title of the document or of a section
+    Heading {
+        level: usize,
+    },
+    Section {
+        level: usize,
+    },
+    /// `.Title` of the enclosing block
+    BlockTitle,
+    Paragraph,
+    DelimitedBlock(DelimitedBlockKind),
+    List(ListKind),
+    ListItem,
+    /// Term of the enclosing description list item
+    Term,
+    /// A break, holding only the attributes of the block
+    Break(BreakKind),
+    /// A block macro, holding the attributes of the block and its [`Event::Macro`]
+    BlockMacro,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event<'a> {
+    Start(Tag),
+    End(Tag),
+    Text(Cow<'a, str>),
+    /// Line break inside a paragraph, list item or verbatim block
+    SoftBreak,
+    /// Checkbox of a checklist item, with whether it is checked
+    Checkbox(bool),
+    /// Numbering of an ordered list item, with the number of its explicit numeral
+    Numbering(NumberingStyle, Option<u32>),
+    /// Attribute of the enclosing block, or attribute entry of the enclosing header
+    Attribute(Attribute<'a>),
+    Author(Author<'a>),
+    Revision(Revision<'a>),
+    /// Name, target and attributes of the enclosing block macro
+    Macro(BlockMacro<'a>),
+}
+
+/// Pull parser yielding the events of a document as it is read
+///
+/// Sections are opened and closed around the blocks they contain, so only the top level block
+/// being parsed is kept in memory. That block is parsed whole before its first event is
+/// yielded, including every nested block of a delimited block or list. The iterator ends after
+/// the first error.
+pub struct Events<T: Iterator<Item = char>> {
+    iter: CheckpointIterator<T>,
+    started: bool,
+    finished: bool,
+    /// Levels of the sections that haven't been closed yet
+    sections: Vec<usize>,
+    queue: VecDeque<Event<'static>>,
+    /// Source text of the element the queued events were parsed from
+    range: TextRange,
+}
+
+impl<T: Iterator<Item = char>> Events<T> {
+    pub fn new(iter: CheckpointIterator<T>) -> Self {
+        Self {
+            iter,
+            started: false,
+            finished: false,
+            sections: Vec::new(),
+            queue: VecDeque::new(),
+            range: TextRange::default(),
+        }
+    }
+
+    /// Yields each event with the range of the source text of the element it was parsed from,
+    /// which is empty for the end of the sections closed by the end of the document
+    pub fn with_ranges(
+        &mut self,
+    ) -> impl Iterator<Item = Result<(Event<'static>, TextRange), ParseError>> + '_ {
+        std::iter::from_fn(|| {
+            let event = self.next()?;
+            Some(event.map(|event| (event, self.range)))
+        })
+    }
+
+    /// The iterator the events were parsed from, at the end of the last parsed element
+    pub fn into_inner(self) -> CheckpointIterator<T> {
+        self.iter
+    }
+
+    /// Parses the next element of the document, queueing its events
+    fn parse_next(&mut self) -> Result<(), ParseError> {
+        if !self.started {
+            self.started = true;
+            while self
+                .iter
+                .opt_memo(Rule::BlankLine, CheckpointIterator::parse_blank_line)
+                .or_else(|| {
+                    self.iter
+                        .opt_memo(Rule::LineComment, CheckpointIterator::parse_line_comment)
+                })
+                .is_some()
+            {}
+            if let Some(header) = self
+                .iter
+                .opt_parse(CheckpointIterator::parse_document_header)
+            {
+                header_events(&header, &mut self.queue);
+            }
+            return Ok(());
+        }
+        if self.iter.peek().is_none() {
+            self.close_sections(0);
+            self.finished = true;
+            return Ok(());
+        }
+        if let Some(level) = peek_heading_level(&mut self.iter) {
+            self.close_sections(level);
+            let section =
+                SyntaxNode::new(SyntaxKind::Section, parse_section_start(&mut self.iter)?);
+            self.queue.push_back(Event::Start(Tag::Section { level }));
+            metadata_events(&section, &mut self.queue);
+            if let Some(heading) = section.child_node(SyntaxKind::Heading) {
+                heading_events(heading, &mut self.queue);
+            }
+            self.sections.push(level);
+            return Ok(());
+        }
+        block_events(&parse_element(&mut self.iter, false)?, &mut self.queue);
+        Ok(())
+    }
+
+    /// Closes the open sections of a level greater or equal to `level`
+    fn close_sections(&mut self, level: usize) {
+        while let Some(open) = self.sections.pop_if(|open| *open >= level) {
+            self.queue
+                .push_back(Event::End(Tag::Section { level: open }));
+        }
+    }
+}
+
+impl<T: Iterator<Item = char>> Iterator for Events<T> {
+    type Item = Result<Event<'static>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.queue.pop_front() {
+                return Some(Ok(event));
+            }
+            if self.finished {
+                return None;
+            }
+            let start = self.iter.current_position();
+            if let Err(e) = self.parse_next() {
+                self.finished = true;
+                return Some(Err(e));
+            }
+            self.range = TextRange::new(start, self.iter.current_position());
+        }
+    }
+}
+
+fn header_events(node: &SyntaxNode, queue: &mut VecDeque<Event<'static>>) {
+    queue.push_back(Event::Start(Tag::Header));
+    if let Some(heading) = node.child_node(SyntaxKind::Heading) {
+        heading_events(heading, queue);
+    }
+    let owned_entries: Vec<_> = node
+        .child_nodes()
+        .filter(|n| n.kind == SyntaxKind::AttributeEntry)
+        .map(|n| {
+            let unset = n.child_token(SyntaxKind::Bang).is_some();
+            let value = n.token_text(SyntaxKind::AttributeValue).trim().to_string();
+            (
+                n.token_text(SyntaxKind::AttributeName),
+                (!unset).then_some(value),
+            )
+        })
+        .collect();
+    queue.extend(owned_entries.iter().map(|(name, value)| {
+        Event::Attribute(Attribute {
+            key: name.clone().into(),
+            value: value.clone().map(Into::into),
+        })
+    }));
+    let entries: Vec<_> = owned_entries
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_deref()))
+        .collect();
+    let authors = match node.child_node(SyntaxKind::AuthorLine) {
+        Some(line) => CheckpointIterator::new(line.token_text(SyntaxKind::Text).chars())
+            .parse_authors()
+            .unwrap_or_default(),
+        None => attribute_authors(&entries),
+    };
+    queue.extend(authors.into_iter().map(Event::Author));
+    let revision = match node.child_node(SyntaxKind::RevisionLine) {
+        Some(line) => CheckpointIterator::new(line.token_text(SyntaxKind::Text).chars())
+            .parse_revision_line()
+            .ok(),
+        None => attribute_revision(&entries),
+    };
+    queue.extend(revision.map(Event::Revision));
+    queue.push_back(Event::End(Tag::Header));
+}
+
+fn heading_events(node: &SyntaxNode, queue: &mut VecDeque<Event<'static>>) {
+    let tag = Tag::Heading {
+        level: node.token_text(SyntaxKind::HeadingMarker).len(),
+    };
+    queue.push_back(Event::Start(tag.clone()));
+    queue.push_back(Event::Text(node.token_text(SyntaxKind::Text).into()));
+    queue.push_back(Event::End(tag));
+}
+
+/// Events of a block other than a section, trivia and attribute entries have none
+fn block_events(node: &SyntaxNode, queue: &mut VecDeque<Event<'static>>) {
+    let tag = match node.kind {
+        SyntaxKind::Paragraph => Tag::Paragraph,
+        SyntaxKind::DelimitedBlock => match node
+            .child_token(SyntaxKind::Delimiter)
+            .and_then(|d| delimiter_kind(&d.text))
+        {
+            Some(DelimitedBlockKind::Comment) | None => return,
+            Some(kind) => Tag::DelimitedBlock(kind),
+        },
+        SyntaxKind::List => match node.child_node(SyntaxKind::ListItem) {
+            Some(item) => Tag::List(list_kind(&item.token_text(SyntaxKind::ListMarker))),
+            None => return,
+        },
+        SyntaxKind::Break => match break_kind(&node.token_text(SyntaxKind::BreakMarker)) {
+            Some(kind) => Tag::Break(kind),
+            None => return,
+        },
+        SyntaxKind::BlockMacro => Tag::BlockMacro,
+        _ => return,
+    };
+    queue.push_back(Event::Start(tag.clone()));
+    metadata_events(node, queue);
+    match &tag {
+        Tag::Paragraph => lines_events(node, queue),
+        Tag::DelimitedBlock(kind) if kind.is_verbatim() => lines_events(node, queue),
+        Tag::List(kind) => {
+            for item in node
+                .child_nodes()
+                .filter(|n| n.kind == SyntaxKind::ListItem)
+            {
+                queue.push_back(Event::Start(Tag::ListItem));
+                if *kind == ListKind::Description {
+                    queue.push_back(Event::Start(Tag::Term));
+                    queue.push_back(Event::Text(item.token_text(SyntaxKind::Term).into()));
+                    queue.push_back(Event::End(Tag::Term));
+                }
+                if *kind == ListKind::Ordered {
+                    let (style, number) =
+                        marker_numbering(&item.token_text(SyntaxKind::ListMarker));
+                    queue.push_back(Event::Numbering(style, number));
+                }
+                if let Some(checkbox) = item.child_token(SyntaxKind::Checkbox) {
+                    queue.push_back(Event::Checkbox(checkbox.text != "[ ]"));
+                }
+                // A description may start on the line after its term
+                let text = item.token_text(SyntaxKind::Text);
+                let mut started = !text.is_empty();
+                if started {
+                    queue.push_back(Event::Text(text.into()));
+                }
+                for line in item.child_nodes().filter(|n| n.kind == SyntaxKind::Line) {
+                    if started {
+                        queue.push_back(Event::SoftBreak);
+                    }
+                    text_event(line, queue);
+                    started = true;
+                }
+                item.child_nodes().for_each(|n| block_events(n, queue));
+                queue.push_back(Event::End(Tag::ListItem));
+            }
+        }
+        Tag::Break(_) => {}
+        Tag::BlockMacro => queue.push_back(Event::Macro(block_macro(node))),
+        _ => node.child_nodes().for_each(|n| block_events(n, queue)),
+    }
+    queue.push_back(Event::End(tag));
+}
+
+/// Block title and attributes of the block, from the lines before its first token
+fn metadata_events(node: &SyntaxNode, queue: &mut VecDeque<Event<'static>>) {
+    for child in node.children.iter().map_while(|c| match c {
+        SyntaxElement::Node(n) => Some(n),
+        SyntaxElement::Token(_) => None,
+    }) {
+        match child.kind {
+            SyntaxKind::BlockTitle => {
+                queue.push_back(Event::Start(Tag::BlockTitle));
+                queue.push_back(Event::Text(child.token_text(SyntaxKind::Text).into()));
+                queue.push_back(Event::End(Tag::BlockTitle));
+            }
+            SyntaxKind::AttributeList => queue.extend(
+                child
+                    .child_nodes()
+                    .filter(|n| n.kind == SyntaxKind::Attribute)
+                    .map(|n| Event::Attribute(attribute(n))),
+            ),
+            // `[[id]]` is a shorthand for `[id=id]`
+            SyntaxKind::BlockAnchor => queue.push_back(Event::Attribute(Attribute {
+                key: "id".into(),
+                value: Some(child.token_text(SyntaxKind::AnchorId).into()),
+            })),
+            _ => {}
+        }
+    }
+}
+
+/// The [`SyntaxKind::Line`] children of `node` separated by soft breaks
+fn lines_events(node: &SyntaxNode, queue: &mut VecDeque<Event<'static>>) {
+    for (i, line) in node
+        .child_nodes()
+        .filter(|n| n.kind == SyntaxKind::Line)
+        .enumerate()
+    {
+        if i > 0 {
+            queue.push_back(Event::SoftBreak);
+        }
+        text_event(line, queue);
+    }
+}
+
+fn text_event(line: &SyntaxNode, queue: &mut VecDeque<Event<'static>>) {
+    let text = line.token_text(SyntaxKind::Text);
+    if !text.is_empty() {
+        queue.push_back(Event::Text(text.into()));
+    }
+}
+
+pub(crate) fn block_macro(node: &SyntaxNode) -> BlockMacro<'static> {
+    let attributes = node
+        .child_nodes()
+        .filter(|n| n.kind == SyntaxKind::AttributeList)
+        .last()
+        .into_iter()
+        .flat_map(|list| list.child_nodes())
+        .filter(|n| n.kind == SyntaxKind::Attribute)
+        .map(attribute)
+        .collect();
+    BlockMacro {
+        name: node.token_text(SyntaxKind::MacroName).into(),
+        target: node.token_text(SyntaxKind::MacroTarget).into(),
+        attributes,
+    }
+}
+
+pub(crate) fn attribute(node: &SyntaxNode) -> Attribute<'static> {
+    let value = node
+        .child_token(SyntaxKind::AttributeValue)
+        .map(|v| unquote(&v.text).to_string().into());
+    match node.child_token(SyntaxKind::AttributeName) {
+        Some(key) => Attribute {
+            key: key.text.clone().into(),
+            value,
+        },
+        // Positional attributes are keyed by their value, like the ones without quotes
+        None => Attribute {
+            key: value.unwrap_or_default(),
+            value: None,
+        },
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
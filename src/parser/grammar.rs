@@ -0,0 +1,344 @@
+use std::{borrow::Cow, iter::Peekable};
+
+use pest::{
+    Parser,
+    error::{Error, InputLocation},
+    iterators::{Pair, Pairs},
+};
+use pest_derive::Parser;
+
+use crate::{
+    ast::{
+        Attribute, Author, Block, BlockContent, BlockMacro, DelimitedBlockContent,
+        DelimitedBlockKind, Document, DocumentContent, DocumentHeader, SectionContent,
+        SectionHeading, UndelimitedBlockContent,
+    },
+    sections,
+};
+
+use super::{
+    ParseError,
+    body::{break_kind, delimiter_kind},
+    convert::{ListItem, join_lines, nest_list, split_attribute_entry},
+    header::{self, attribute_authors, attribute_revision},
+};
+
+/// Parser generated from `grammar.pest`, an alternative to the hand written parser producing
+/// the same [`Document`]
+#[derive(Parser)]
+#[grammar = "grammar.pest"]
+pub struct GrammarParser;
+
+impl GrammarParser {
+    pub fn parse_document(text: &str) -> Result<Document<'_>, ParseError> {
+        let document = Self::parse(Rule::document, text)
+            .map_err(parse_error)?
+            .next()
+            .expect("The document rule to produce a pair");
+        let mut header = None;
+        let mut blocks = Vec::new();
+        for pair in document.into_inner() {
+            match pair.as_rule() {
+                Rule::document_header => header = Some(document_header(pair)),
+                Rule::document_content => {
+                    blocks = section_content(&mut pair.into_inner().peekable(), None)
+                }
+                _ => {}
+            }
+        }
+        let mut document = Document {
+            header,
+            content: DocumentContent { blocks },
+        };
+        sections::assign(&mut document);
+        Ok(document)
+    }
+}
+
+fn parse_error(error: Error<Rule>) -> ParseError {
+    let (start, end) = match error.location {
+        InputLocation::Pos(pos) => (pos, pos),
+        InputLocation::Span(span) => span,
+    };
+    ParseError {
+        start,
+        end,
+        message: error.variant.message().to_string(),
+    }
+}
+
+fn line_text(pair: Pair<'_, Rule>) -> &str {
+    pair.into_inner()
+        .find(|p| p.as_rule() == Rule::line)
+        .map(|line| line.as_str().trim_end())
+        .unwrap_or_default()
+}
+
+fn document_header(pair: Pair<'_, Rule>) -> DocumentHeader<'_> {
+    let mut header = DocumentHeader {
+        title: None,
+        authors: Vec::new(),
+        revision: None,
+        attributes: Vec::new(),
+    };
+    let (mut author_line, mut revision_line) = (false, false);
+    let mut entries = Vec::new();
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::title => {
+                header.title = Some(SectionHeading {
+                    level: 1,
+                    text: line_text(pair).into(),
+                    id: None,
+                    number: None,
+                })
+            }
+            Rule::author_line => {
+                author_line = true;
+                header.authors = authors(pair);
+            }
+            Rule::revision_line => {
+                revision_line = true;
+                header.revision = Some(header::revision(line_text(pair)));
+            }
+            Rule::attribute_entry => entries.extend(split_attribute_entry(pair.as_str())),
+            _ => {}
+        }
+    }
+    header.attributes = entries
+        .iter()
+        .map(|&(key, value)| Attribute {
+            key: key.into(),
+            value: value.map(Into::into),
+        })
+        .collect();
+    if !author_line {
+        header.authors = attribute_authors(&entries);
+    }
+    if !revision_line {
+        header.revision = attribute_revision(&entries);
+    }
+    header
+}
+
+fn authors(pair: Pair<'_, Rule>) -> Vec<Author<'_>> {
+    let Some(authors) = pair.into_inner().find(|p| p.as_rule() == Rule::authors) else {
+        return Vec::new();
+    };
+    // Authors after one with an invalid email are ignored, like by the hand written parser
+    authors
+        .into_inner()
+        .map_while(|author| {
+            let mut pairs = author.into_inner();
+            let name = pairs.next()?.as_str();
+            header::author(name, pairs.next().map(|email| email.as_str()))
+        })
+        .collect()
+}
+
+/// Blocks up to the next section heading of at most `level`, nesting the sections below it
+fn section_content<'i>(
+    pairs: &mut Peekable<Pairs<'i, Rule>>,
+    level: Option<usize>,
+) -> Vec<Block<'i>> {
+    let mut blocks = Vec::new();
+    while let Some(pair) = pairs.peek() {
+        match pair.as_rule() {
+            Rule::section => {
+                let heading = pair
+                    .clone()
+                    .into_inner()
+                    .find(|p| p.as_rule() == Rule::heading)
+                    .expect("A section to have a heading");
+                let section_level = heading
+                    .clone()
+                    .into_inner()
+                    .find(|p| p.as_rule() == Rule::heading_marker)
+                    .map(|m| m.as_str().len())
+                    .unwrap_or_default();
+                if level.is_some_and(|level| section_level <= level) {
+                    break;
+                }
+                let mut block = metadata(pairs.next().expect("The peeked pair"));
+                block.heading = Some(SectionHeading {
+                    level: section_level,
+                    text: line_text(heading).into(),
+                    id: None,
+                    number: None,
+                });
+                block.content = BlockContent::Section(
+                    section_content(pairs, Some(section_level))
+                        .into_iter()
+                        .map(SectionContent::Block)
+                        .collect(),
+                );
+                blocks.push(block);
+            }
+            Rule::block => blocks.extend(block(pairs.next().expect("The peeked pair"))),
+            _ => {
+                pairs.next();
+            }
+        }
+    }
+    blocks
+}
+
+/// Block with the title and attributes from the metadata lines of `pair`, its content is
+/// left to the caller
+fn metadata(pair: Pair<'_, Rule>) -> Block<'_> {
+    let mut block = Block {
+        heading: None,
+        title: None,
+        attributes: Vec::new(),
+        content: BlockContent::Undelimited(Vec::new()),
+    };
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::block_title => {
+                let title = line_text(pair);
+                block.title.get_or_insert(title.into());
+            }
+            // `[[id]]` is a shorthand for `[id=id]`
+            Rule::block_anchor => block
+                .attributes
+                .extend(pair.into_inner().map(|id| Attribute {
+                    key: "id".into(),
+                    value: Some(id.as_str().into()),
+                })),
+            Rule::block_attribute_list => block.attributes.extend(pair.into_inner().map(attribute)),
+            _ => {}
+        }
+    }
+    block
+}
+
+fn attribute(pair: Pair<'_, Rule>) -> Attribute<'_> {
+    let mut key = None;
+    let mut value = None;
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::attribute_key => key = Some(pair.as_str().into()),
+            Rule::attribute_value => {
+                let v = pair.as_str();
+                value = Some(
+                    v.strip_prefix('"')
+                        .and_then(|v| v.strip_suffix('"'))
+                        .unwrap_or(v)
+                        .into(),
+                );
+            }
+            _ => {}
+        }
+    }
+    match key {
+        Some(key) => Attribute { key, value },
+        // Positional attributes are keyed by their value, like the ones without quotes
+        None => Attribute {
+            key: value.unwrap_or_default(),
+            value: None,
+        },
+    }
+}
+
+fn block(pair: Pair<'_, Rule>) -> Option<Block<'_>> {
+    let content = pair.clone().into_inner().find(|p| {
+        matches!(
+            p.as_rule(),
+            Rule::delimited_block
+                | Rule::break_block
+                | Rule::macro_block
+                | Rule::list_block
+                | Rule::paragraph
+                | Rule::item_paragraph
+        )
+    })?;
+    let mut block = metadata(pair);
+    block.content = match content.as_rule() {
+        Rule::paragraph | Rule::item_paragraph => {
+            BlockContent::Undelimited(vec![UndelimitedBlockContent::Text(lines(
+                content.into_inner(),
+                str::trim_end,
+            ))])
+        }
+        Rule::list_block => {
+            let mut items = content.into_inner().map(list_item).peekable();
+            BlockContent::List(nest_list(&mut items, &[]))
+        }
+        Rule::break_block => BlockContent::Break(break_kind(content.as_str())?),
+        Rule::macro_block => {
+            let mut block_macro = BlockMacro {
+                name: Cow::Borrowed(""),
+                target: Cow::Borrowed(""),
+                attributes: Vec::new(),
+            };
+            for pair in content.into_inner() {
+                match pair.as_rule() {
+                    Rule::macro_name => block_macro.name = pair.as_str().into(),
+                    Rule::macro_target => block_macro.target = pair.as_str().into(),
+                    _ => block_macro
+                        .attributes
+                        .extend(pair.into_inner().map(attribute)),
+                }
+            }
+            BlockContent::Macro(block_macro)
+        }
+        _ => {
+            let mut pairs = content.into_inner();
+            let kind = pairs.next().and_then(|d| delimiter_kind(d.as_str()))?;
+            let content = match kind {
+                DelimitedBlockKind::Comment => return None,
+                kind if kind.is_verbatim() => {
+                    vec![DelimitedBlockContent::Text(lines(pairs, |line| {
+                        line.trim_end_matches(['\n', '\r'])
+                    }))]
+                }
+                _ => pairs
+                    .filter(|p| p.as_rule() == Rule::block)
+                    .filter_map(self::block)
+                    .map(DelimitedBlockContent::Block)
+                    .collect(),
+            };
+            BlockContent::Delimited(kind, content)
+        }
+    };
+    Some(block)
+}
+
+/// A list item with the lines following it and the blocks attached to it
+fn list_item(pair: Pair<'_, Rule>) -> ListItem<'_> {
+    let input = pair.get_input();
+    let mut item = ListItem {
+        marker: "",
+        term: None,
+        checked: None,
+        text: Cow::Borrowed(""),
+        blocks: Vec::new(),
+    };
+    let mut lines = Vec::new();
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::term => item.term = Some(pair.as_str().trim_end().into()),
+            Rule::list_marker | Rule::description_marker => item.marker = pair.as_str(),
+            Rule::checkbox => item.checked = Some(pair.as_str() != "[ ]"),
+            Rule::attached_block => item.blocks.extend(block(pair)),
+            _ => {
+                let start = pair.as_span().start();
+                lines.push(start..start + pair.as_str().trim_end().len());
+            }
+        }
+    }
+    item.text = join_lines(input, lines);
+    item
+}
+
+/// Text of the line pairs trimmed by `trim`, see [`join_lines`]
+fn lines<'i>(pairs: Pairs<'i, Rule>, trim: fn(&str) -> &str) -> Cow<'i, str> {
+    let input = pairs.get_input();
+    join_lines(
+        input,
+        pairs.map(|line| {
+            let start = line.as_span().start();
+            start..start + trim(line.as_str()).len()
+        }),
+    )
+}
@@ -1,29 +1,73 @@
-use http::Uri;
+use std::borrow::Cow;
 
 use crate::{
-    ast::{Author, DocumentHeader, Revision, SectionHeading},
+    ast::{Author, Email, Revision},
     checkpoint_iterator::CheckpointIterator,
-    parser::ParseError,
+    cst::{SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken},
+    parser::{ParseError, Rule},
 };
 
-use super::traits::header::{
-    AuthorParser, AuthorsParser, DateParser, DecimalParser, DocHeaderParser, DocSectionHeading,
-    LineParser, RevisionLineParser, UriParser, VersionParser,
+use super::{
+    text_tokens,
+    traits::{
+        body::{DocAttributeEntryParser, DocTriviaParser},
+        header::{
+            AuthorParser, AuthorsParser, DocHeaderParser, DocSectionHeading, EmailParser,
+            LineParser, NewlineParser, RevisionLineParser, TextLineParser,
+        },
+    },
 };
 
 impl<T> DocHeaderParser for CheckpointIterator<T>
 where
     T: Iterator<Item = char>,
 {
-    fn parse_document_header(&mut self) -> Result<DocumentHeader, super::ParseError> {
-        let title = self.opt_parse(Self::parse_section_heading);
-        let authors = self.opt_parse(Self::parse_authors).unwrap_or(Vec::new());
-        let revision = self.opt_parse(Self::parse_revision_line);
-        Ok(DocumentHeader {
-            title,
-            authors,
-            revision,
-        })
+    fn parse_document_header(&mut self) -> Result<SyntaxNode, super::ParseError> {
+        let mut children: Vec<SyntaxElement> = Vec::new();
+        while let Some(entry) = self
+            .opt_memo(Rule::AttributeEntry, Self::parse_attribute_entry)
+            .or_else(|| self.opt_memo(Rule::LineComment, Self::parse_line_comment))
+        {
+            children.push(entry.into());
+        }
+        let title = self.opt_parse(|s| {
+            let heading = s.memo_parse(Rule::SectionHeading, Self::parse_section_heading)?;
+            if heading.token_text(SyntaxKind::HeadingMarker).len() == 1 {
+                Ok(heading)
+            } else {
+                Err(s.error("Expected a level 0 heading as the document title".to_string()))
+            }
+        });
+        if let Some(title) = title {
+            children.push(title.into());
+            // The author and revision lines are positional, they are only recognised directly
+            // below the title and before any attribute entry
+            let header_line = |s: &mut Self, kind| {
+                if s.lookahead(|s| s.parse_attribute_entry().is_ok())
+                    || s.lookahead(|s| s.parse_line_comment().is_ok())
+                {
+                    return Err(s.error("Expected an author or revision line".to_string()));
+                }
+                s.parse_text_line(kind)
+            };
+            if let Some(authors) = self.opt_parse(|s| header_line(s, SyntaxKind::AuthorLine)) {
+                children.push(authors.into());
+                if let Some(revision) = self.opt_parse(|s| header_line(s, SyntaxKind::RevisionLine))
+                {
+                    children.push(revision.into());
+                }
+            }
+        }
+        while let Some(entry) = self
+            .opt_memo(Rule::AttributeEntry, Self::parse_attribute_entry)
+            .or_else(|| self.opt_memo(Rule::LineComment, Self::parse_line_comment))
+        {
+            children.push(entry.into());
+        }
+        if children.is_empty() {
+            return Err(self.error("Expected a document title or attribute entries".to_string()));
+        }
+        Ok(SyntaxNode::new(SyntaxKind::Header, children))
     }
 }
 
@@ -31,22 +75,32 @@ impl<T> DocSectionHeading for CheckpointIterator<T>
 where
     T: Iterator<Item = char>,
 {
-    fn parse_section_heading(&mut self) -> Result<SectionHeading, super::ParseError> {
-        // Skip all preceding whitespace from the start, count for consuming the iterator
-        // Without count, iterator won't advance
-        let whitespace_count = self.take_while_ref(|c| c.is_whitespace()).count();
-        log::info!("Skipping {whitespace_count} Whitespaces before section heading");
-        let level = self.take_while_ref(|c| *c == '=').count();
-        log::info!("Section Level {level}");
-        if level == 0 {
+    fn parse_section_heading(&mut self) -> Result<SyntaxNode, super::ParseError> {
+        let marker: String = self.take_while_ref(|c| *c == '=').to_string();
+        log::info!("Section Level {}", marker.len());
+        if marker.is_empty() {
             return Err(self.error("Expected '='".to_string()));
         }
-        let space_count = self.take_while_ref(|c| *c == ' ').count();
-        if space_count == 0 {
+        if marker.len() > 6 {
+            return Err(self.error("Section headings can be at most 6 levels deep".to_string()));
+        }
+        let whitespace: String = self.take_while_ref(|c| *c == ' ' || *c == '\t').to_string();
+        if whitespace.is_empty() {
             return Err(self.error("Expected ' '".to_string()));
         }
-        let text = self.parse_line()?;
-        Ok(SectionHeading { level, text })
+        let text: String = self
+            .take_while_ref(|c| *c != '\n' && *c != '\r')
+            .to_string();
+        if text.trim().is_empty() {
+            return Err(self.error("Expected the text of the section heading".to_string()));
+        }
+        let mut children = vec![
+            SyntaxToken::new(SyntaxKind::HeadingMarker, marker).into(),
+            SyntaxToken::new(SyntaxKind::Whitespace, whitespace).into(),
+        ];
+        children.extend(text_tokens(SyntaxKind::Text, text));
+        children.extend(self.opt_parse(Self::parse_newline).map(SyntaxElement::from));
+        Ok(SyntaxNode::new(SyntaxKind::Heading, children))
     }
 }
 
@@ -54,86 +108,86 @@ impl<T> RevisionLineParser for CheckpointIterator<T>
 where
     T: Iterator<Item = char>,
 {
-    fn parse_revision_line(&mut self) -> Result<Revision, super::ParseError> {
-        let version = match self.parse_version() {
-            Err(_) => {
-                println!("Error while parsing version");
-                vec![]
-            }
-            Ok(v) => v,
-        };
-
-        let date = self.opt_parse(|state| {
-            let _ = state
-                .take_while_ref(|c| c.is_whitespace() || *c == ',')
-                .collect::<String>();
-            state.parse_date()
-        });
-        let _ = self
-            .take_while_ref(|c| c.is_whitespace() || *c == ':')
-            .count();
-        let remark = self.parse_line().expect("Parsing line shouldn't ever fail");
-        Ok(Revision {
-            version,
-            date,
-            remark,
-        })
+    fn parse_revision_line(&mut self) -> Result<Revision<'static>, super::ParseError> {
+        let line = self.parse_line()?;
+        let revision = revision(&line);
+        if let Some(date) = &revision.date
+            && revision.parsed_date.is_none()
+            && is_iso_date_like(date)
+        {
+            log::warn!("Invalid revision date '{date}'");
+        }
+        Ok(owned_revision(revision))
     }
 }
 
-impl<T> DateParser for CheckpointIterator<T>
-where
-    T: Iterator<Item = char>,
-{
-    fn parse_date(&mut self) -> Result<chrono::NaiveDate, super::ParseError> {
-        let year = self
-            .take_while_ref(|c| c.is_ascii_digit())
-            .collect::<String>()
-            .parse()
-            .map_err(|e| self.error(format!("Error while parsing year: {e}")))?;
-        self.take_while_ref(|v| *v == '-').count();
-        let month = self
-            .take_while_ref(|c| c.is_numeric())
-            .collect::<String>()
-            .parse()
-            .map_err(|e| self.error(format!("Error while parsing month: {e}")))?;
-        self.take_while_ref(|v| *v == '-').count();
-        let day = self
-            .take_while_ref(|c| c.is_numeric())
-            .collect::<String>()
-            .parse()
-            .map_err(|e| self.error(format!("Error while parsing day: {e}")))?;
-        match chrono::NaiveDate::from_ymd_opt(year, month, day) {
-            Some(v) => Ok(v),
-            None => Err(self.error(String::from("Invalid date provided"))),
-        }
-    }
-}
-
-impl<T> VersionParser for CheckpointIterator<T>
-where
-    T: Iterator<Item = char>,
-{
-    fn parse_version(&mut self) -> Result<Vec<isize>, super::ParseError> {
-        let mut version = Vec::new();
-        while let Ok(v) = self.parse_decimal() {
-            version.push(v);
-            self.take_while_ref(|v| *v == '.').next();
-        }
-        Ok(version)
+/// Revision of a revision line like `v1.0, 2020-01-31: Remark`
+///
+/// The number is the text before the first comma, without the letters before its first digit,
+/// and the date is the text after it up to the colon before the remark. Without a comma, the
+/// text is a date unless it starts with `v`.
+pub(crate) fn revision(line: &str) -> Revision<'_> {
+    let line = line.trim();
+    let (info, remark) = match line.split_once(':') {
+        Some((info, remark)) => (info, Some(remark.trim()).filter(|r| !r.is_empty())),
+        None => (line, None),
+    };
+    fn non_empty(text: &str) -> Option<&str> {
+        Some(text.trim()).filter(|t| !t.is_empty())
+    }
+    let (number, date) = match info.split_once(',') {
+        Some((number, date)) => (
+            non_empty(number.trim_start_matches(|c: char| !c.is_ascii_digit() && c != '{')),
+            non_empty(date),
+        ),
+        None => match info.trim().strip_prefix('v') {
+            Some(number) => (non_empty(number), None),
+            None => (None, non_empty(info)),
+        },
+    };
+    Revision {
+        number: number.map(Cow::Borrowed),
+        date: date.map(Cow::Borrowed),
+        parsed_date: date.and_then(Revision::parse_date),
+        remark: remark.map(Cow::Borrowed),
     }
 }
 
-impl<T> DecimalParser for CheckpointIterator<T>
-where
-    T: Iterator<Item = char>,
-{
-    fn parse_decimal(&mut self) -> Result<isize, super::ParseError> {
-        self.take_while_ref(|e| e.is_numeric() || *e == '_')
-            .filter(|e| e.is_numeric())
-            .collect::<String>()
-            .parse()
-            .map_err(|e| self.error(format!("Error while parsing int {e}")))
+/// Whether `date` looks like an ISO 8601 date, which is worth a warning when it is invalid
+pub(crate) fn is_iso_date_like(date: &str) -> bool {
+    let parts: Vec<&str> = date.split('-').collect();
+    parts.len() == 3
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Revision defined by the `revnumber`, `revdate` and `revremark` attribute entries of a
+/// header, for headers without a revision line. The last entry of each attribute wins.
+pub(crate) fn attribute_revision(entries: &[(&str, Option<&str>)]) -> Option<Revision<'static>> {
+    let value = |name: &str| -> Option<Cow<'static, str>> {
+        let &(_, value) = entries.iter().rfind(|(entry, _)| *entry == name)?;
+        Some(value?.trim())
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string().into())
+    };
+    let revision = Revision {
+        number: value("revnumber"),
+        date: value("revdate"),
+        parsed_date: value("revdate").and_then(|date| Revision::parse_date(&date)),
+        remark: value("revremark"),
+    };
+    (revision.number.is_some() || revision.date.is_some() || revision.remark.is_some())
+        .then_some(revision)
+}
+
+fn owned_revision(revision: Revision<'_>) -> Revision<'static> {
+    let owned = |text: Cow<'_, str>| -> Cow<'static, str> { text.into_owned().into() };
+    Revision {
+        number: revision.number.map(owned),
+        date: revision.date.map(owned),
+        parsed_date: revision.parsed_date,
+        remark: revision.remark.map(owned),
     }
 }
 
@@ -141,11 +195,11 @@ impl<T> AuthorsParser for CheckpointIterator<T>
 where
     T: Iterator<Item = char>,
 {
-    fn parse_authors(&mut self) -> Result<Vec<Author>, super::ParseError> {
+    fn parse_authors(&mut self) -> Result<Vec<Author<'static>>, super::ParseError> {
         let mut authors = Vec::new();
         let mut current_line = CheckpointIterator::new(self.take_while(|c| !c.is_ascii_control()));
         while let Ok(author) = current_line.parse_author() {
-            authors.push(author.clone());
+            authors.push(author);
         }
         Ok(authors)
     }
@@ -155,86 +209,147 @@ impl<T> AuthorParser for CheckpointIterator<T>
 where
     T: Iterator<Item = char>,
 {
-    fn parse_author(&mut self) -> Result<Author, super::ParseError> {
-        self.take_while_ref(|i| i.is_whitespace() || *i == ';')
-            .count();
-        let name: Vec<String> = self
+    fn parse_author(&mut self) -> Result<Author<'static>, super::ParseError> {
+        self.take_while_ref(|i| i.is_whitespace() || *i == ';');
+        let name: String = self
             .take_while_ref(|i| !(i.is_ascii_control() || *i == ';' || *i == '<'))
-            .collect::<String>()
-            .split_terminator(' ')
-            .map(|v| v.trim().to_string())
-            .collect();
-
-        if name.len() < 1 {
+            .to_string();
+        if name.trim().is_empty() {
             return Err(self.error("Expected to find author name".to_string()));
         }
-        if name.len() < 2 {
-            return Err(self.error("Expected to find author's last name".to_string()));
-        }
-
-        let first_name = name[0].clone();
-
-        let (middle_name, last_name) = if name.len() == 2 {
-            (None, name[1].clone())
-        } else {
-            (
-                Some(name[1].clone()),
-                name.last()
-                    .expect("Expected the name to have more than 2 words")
-                    .to_string(),
-            )
-        };
-
         let email = if let Some('<') = self.next() {
-            let email = Some(self.parse_url()?);
+            let email = self.parse_email()?;
             if let Some('>') = self.next() {
             } else {
                 return Err(self.error("Matching > not found for <".to_string()));
             }
-            email
+            Some(email)
         } else {
             None
         };
         Ok(Author {
-            first_name,
-            middle_name,
-            last_name,
             email,
+            ..owned_author(named_author(&name))
         })
     }
 }
 
-impl<T> UriParser for CheckpointIterator<T>
+impl<T> EmailParser for CheckpointIterator<T>
 where
     T: Iterator<Item = char>,
 {
-    fn parse_url(&mut self) -> Result<Uri, super::ParseError> {
-        let start_pos = self.push();
-        match self
-            .take_while_ref(|i| i.is_alphanumeric() || "-._~:/?#[]@!$&'()*+,;%=".contains(*i))
-            .collect::<String>()
-            .parse()
-        {
-            Err(e) => {
-                let end_pos = self
-                    .pop()
-                    .expect("Push executed before pop on checkpoint iterator");
-                log::warn!("URI Parsing failed with error: {e}");
-                return Err(ParseError {
-                    start: start_pos,
-                    end: end_pos,
-                    message: format!("URI Parsing Failed with error: {e}"),
-                });
-            }
-            Ok(v) => {
-                self.drop()
-                    .expect("Drop executed before pop on checkpoint iterator");
-                Ok(v)
+    fn parse_email(&mut self) -> Result<Email<'static>, super::ParseError> {
+        let start = self.current_position();
+        let address = self
+            .take_while_ref(|c| *c != '>' && !c.is_control())
+            .to_string();
+        match Email::parse(&address) {
+            Some(email) => Ok(owned_email(email)),
+            None => {
+                log::warn!("Invalid email address '{address}'");
+                Err(ParseError {
+                    start,
+                    end: self.current_position(),
+                    message: format!("Invalid email address '{address}'"),
+                })
             }
         }
     }
 }
 
+/// Author named by `name` with the `email` address, `None` when the address is invalid
+#[cfg(any(feature = "pest", feature = "nom"))]
+pub(crate) fn author<'a>(name: &'a str, email: Option<&'a str>) -> Option<Author<'a>> {
+    let email = match email {
+        Some(email) => Some(Email::parse(email.trim())?),
+        None => None,
+    };
+    Some(Author {
+        email,
+        ..named_author(name)
+    })
+}
+
+/// Author named by `name`, without an email address
+///
+/// A name of up to three words is split into the first, middle and last names, underscores
+/// join the words of a part like in `Mary_Ann Smith`. Other names are kept whole as the
+/// first name.
+fn named_author<'a>(name: &'a str) -> Author<'a> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let is_part = |word: &&str| {
+        let mut chars = word.chars();
+        chars.next().is_some_and(is_word) && chars.all(|c| is_word(c) || "-'.".contains(c))
+    };
+    let part = |word: &'a str| -> Cow<'a, str> {
+        if word.contains('_') {
+            word.replace('_', " ").into()
+        } else {
+            word.into()
+        }
+    };
+    let words: Vec<&str> = name.split_whitespace().collect();
+    let (first_name, middle_name, last_name) = match words[..] {
+        [first] if is_part(&first) => (part(first), None, None),
+        [first, last] if words.iter().all(is_part) => (part(first), None, Some(part(last))),
+        [first, middle, last] if words.iter().all(is_part) => {
+            (part(first), Some(part(middle)), Some(part(last)))
+        }
+        // Names are borrowed unless their words are separated by more than a space
+        _ if name.trim().split(' ').count() == words.len() => (name.trim().into(), None, None),
+        _ => (words.join(" ").into(), None, None),
+    };
+    Author {
+        first_name,
+        middle_name,
+        last_name,
+        email: None,
+    }
+}
+
+/// Authors defined by the `author`, `email` and `authors` attribute entries of a header, for
+/// headers without an author line. The last entry of each attribute wins.
+pub(crate) fn attribute_authors(entries: &[(&str, Option<&str>)]) -> Vec<Author<'static>> {
+    let (mut author, mut email, mut authors) = (None, None, None);
+    for &(name, value) in entries {
+        let value = value.map(str::trim).filter(|v| !v.is_empty());
+        match name {
+            "author" => author = value,
+            "email" => email = value,
+            "authors" => authors = value,
+            _ => {}
+        }
+    }
+    let mut result = CheckpointIterator::new(authors.or(author).unwrap_or_default().chars())
+        .parse_authors()
+        .unwrap_or_default();
+    if authors.is_none() {
+        result.truncate(1);
+    }
+    if let Some(first) = result.first_mut()
+        && first.email.is_none()
+    {
+        first.email = email.and_then(Email::parse).map(owned_email);
+    }
+    result
+}
+
+fn owned_author(author: Author<'_>) -> Author<'static> {
+    Author {
+        first_name: author.first_name.into_owned().into(),
+        middle_name: author.middle_name.map(|name| name.into_owned().into()),
+        last_name: author.last_name.map(|name| name.into_owned().into()),
+        email: author.email.map(owned_email),
+    }
+}
+
+fn owned_email(email: Email<'_>) -> Email<'static> {
+    Email {
+        local_part: email.local_part.into_owned().into(),
+        domain: email.domain.into_owned().into(),
+    }
+}
+
 impl<T> LineParser for CheckpointIterator<T>
 where
     T: Iterator<Item = char>,
@@ -245,3 +360,45 @@ where
             .collect::<String>())
     }
 }
+
+impl<T> NewlineParser for CheckpointIterator<T>
+where
+    T: Iterator<Item = char>,
+{
+    fn parse_newline(&mut self) -> Result<SyntaxToken, super::ParseError> {
+        match self.next() {
+            Some('\n') => Ok(SyntaxToken::new(SyntaxKind::Newline, "\n")),
+            Some('\r') => {
+                if let Some('\n') = self.peek() {
+                    self.next();
+                    Ok(SyntaxToken::new(SyntaxKind::Newline, "\r\n"))
+                } else {
+                    Ok(SyntaxToken::new(SyntaxKind::Newline, "\r"))
+                }
+            }
+            _ => Err(self.error("Expected a newline".to_string())),
+        }
+    }
+}
+
+impl<T> TextLineParser for CheckpointIterator<T>
+where
+    T: Iterator<Item = char>,
+{
+    fn parse_text_line(&mut self, kind: SyntaxKind) -> Result<SyntaxNode, super::ParseError> {
+        let whitespace: String = self.take_while_ref(|c| *c == ' ' || *c == '\t').to_string();
+        let text: String = self
+            .take_while_ref(|c| *c != '\n' && *c != '\r')
+            .to_string();
+        if text.trim().is_empty() {
+            return Err(self.error("Expected a non blank line".to_string()));
+        }
+        let mut children: Vec<SyntaxElement> = Vec::new();
+        if !whitespace.is_empty() {
+            children.push(SyntaxToken::new(SyntaxKind::Whitespace, whitespace).into());
+        }
+        children.extend(text_tokens(SyntaxKind::Text, text));
+        children.extend(self.opt_parse(Self::parse_newline).map(SyntaxElement::from));
+        Ok(SyntaxNode::new(kind, children))
+    }
+}
@@ -1,160 +1,729 @@
-use crate::ast::{
-    Attribute, Author, Block, BlockContent, DelimitedBlockContent, Document, DocumentContent,
-    DocumentHeader, ListContent, Revision, SectionContent, UndelimitedBlockContent,
-};
-use http::Uri;
+use std::borrow::Cow;
+
 use nom::{
-    IResult, Parser,
+    IResult, Offset, Parser,
     branch::alt,
-    bytes::{is_a, is_not, tag, take},
-    character::complete::{alphanumeric1, newline, space0, space1},
-    combinator::{map, map_res, not, opt, recognize},
-    multi::{many0, many1, separated_list0, separated_list1},
-    sequence::{delimited, preceded, separated_pair, terminated},
+    bytes::complete::{tag, take_while, take_while_m_n, take_while1},
+    character::complete::{anychar, char, satisfy},
+    combinator::{cond, eof, not, opt, peek, recognize, verify},
+    multi::{fold_many0, many0, many0_count, many1, many1_count},
+    sequence::{delimited, preceded, terminated},
 };
 
-use super::AsciiDocParser;
+use crate::{
+    ast::{
+        Attribute, Author, Block, BlockContent, BlockMacro, BreakKind, DelimitedBlockContent,
+        DelimitedBlockKind, Document, DocumentContent, DocumentHeader, ListContent, ListKind,
+        Revision, SectionContent, SectionHeading, UndelimitedBlockContent,
+    },
+    sections,
+};
+
+use super::{
+    ParseError,
+    body::{break_kind, delimiter_kind, description_marker, is_block_macro, list_kind},
+    convert::{ListItem, join_lines, nest_list, split_attribute_entry},
+    header::{self, attribute_authors, attribute_revision},
+    traits::AsciiDocParser,
+};
 
+type Res<'a, T> = IResult<&'a str, T>;
+
+/// Parser of a document held in memory, producing the same [`Document`] as the hand written
+/// parser with its text borrowed from the input
+///
+/// Text is only copied when it has to differ from the input, like a paragraph with trailing
+/// whitespace on its lines.
 pub struct InMemoryParser {}
 
 impl<'a> AsciiDocParser<'a> for InMemoryParser {
     type Input = &'a str;
-    fn try_to_ast(&self, input: &'a str) -> IResult<Self::Input, Document<'a>> {
-        parse_document(input)
+
+    fn try_to_ast(&self, input: &'a str) -> Result<Document<'a>, ParseError> {
+        match parse_document(input) {
+            Ok((_, document)) => Ok(document),
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                let position = input.offset(e.input);
+                Err(ParseError {
+                    start: position,
+                    end: position,
+                    message: format!("Unexpected input, expected {:?}", e.code),
+                })
+            }
+            Err(nom::Err::Incomplete(_)) => Err(ParseError {
+                start: 0,
+                end: 0,
+                message: "Incomplete input".to_string(),
+            }),
+        }
     }
 }
 
-fn parse_document<'a>(input: &'a str) -> IResult<&'a str, Document<'a>> {
+fn parse_document(input: &str) -> Res<'_, Document<'_>> {
+    let (input, _) = many0_count(alt((parse_blank_line, parse_comment_line))).parse(input)?;
     let (input, header) = opt(parse_document_header).parse(input)?;
-    let (input, content) = parse_document_content(input)?;
-    Ok((input, Document { header, content }))
+    let (input, blocks) = parse_section_content(input, None)?;
+    let (input, _) = eof(input)?;
+    let mut document = Document {
+        header,
+        content: DocumentContent { blocks },
+    };
+    sections::assign(&mut document);
+    Ok((input, document))
+}
+
+fn is_ws(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+fn is_line_end(c: char) -> bool {
+    c == '\n' || c == '\r'
+}
+
+fn parse_ws(input: &str) -> Res<'_, &str> {
+    take_while(is_ws)(input)
+}
+
+fn parse_newline(input: &str) -> Res<'_, &str> {
+    alt((tag("\r\n"), tag("\n"), tag("\r"))).parse(input)
+}
+
+/// End of a line, the last line may not have a newline
+fn parse_eol(input: &str) -> Res<'_, &str> {
+    alt((parse_newline, eof)).parse(input)
 }
 
+/// Rest of the line, without the newline
+fn parse_rest(input: &str) -> Res<'_, &str> {
+    take_while(|c| !is_line_end(c))(input)
+}
 
-fn parse_document_content<'a>(input: &'a str) -> IResult<&'a str, DocumentContent<'a>> {
-    let (input, blocks) = separated_list0(space0, parse_block).parse(input)?;
-    Ok((input, DocumentContent { blocks }))
+fn parse_line(input: &str) -> Res<'_, &str> {
+    take_while1(|c| !is_line_end(c))(input)
 }
 
-fn parse_block_header<'a>(input: &'a str) -> IResult<&'a str, (&'a str, Vec<Attribute<'a>>)> {
-    let (input, title) = parse_block_title(input)?;
+fn parse_blank_line(input: &str) -> Res<'_, &str> {
+    recognize(alt(((parse_ws, parse_newline), (take_while1(is_ws), eof)))).parse(input)
+}
 
-    let (input, attributes) = parse_attribute_list(input)?;
+fn parse_comment_line(input: &str) -> Res<'_, &str> {
+    recognize((not(parse_delimiter_line), tag("//"), parse_rest, parse_eol)).parse(input)
+}
 
-    Ok((input, (title, attributes)))
+/// A non blank line that doesn't open or close a delimited block, without the whitespace
+/// around it
+fn parse_text_line(input: &str) -> Res<'_, &str> {
+    delimited(
+        (not(parse_blank_line), not(parse_delimiter_line), parse_ws),
+        parse_line,
+        parse_eol,
+    )
+    .map(str::trim_end)
+    .parse(input)
+}
+
+fn parse_heading(input: &str) -> Res<'_, SectionHeading<'_>> {
+    (
+        terminated(take_while_m_n(1, 6, |c| c == '='), not(char('='))),
+        take_while1(is_ws),
+        not(parse_blank_line),
+        parse_line,
+        parse_eol,
+    )
+        .map(|(marker, _, _, text, _)| SectionHeading {
+            level: marker.len(),
+            text: text.trim_end().into(),
+            id: None,
+            number: None,
+        })
+        .parse(input)
 }
 
-fn parse_block<'a>(input: &'a str) -> IResult<&'a str, Block<'a>> {
-    let (input, (title, attributes)) = parse_block_header(input)?;
-    let (input, content) = alt((
-        map(parse_list_content, BlockContent::List),
-        map(parse_section_content, BlockContent::Section),
-        map(parse_delimited_block_content, BlockContent::Delimited),
-        map(parse_undelimited_block_content, BlockContent::Undelimited),
+fn parse_title(input: &str) -> Res<'_, SectionHeading<'_>> {
+    preceded(
+        (
+            char('='),
+            not(char('=')),
+            take_while1(is_ws),
+            not(parse_blank_line),
+        ),
+        terminated(parse_line, parse_eol),
+    )
+    .map(|text| SectionHeading {
+        level: 1,
+        text: text.trim_end().into(),
+        id: None,
+        number: None,
+    })
+    .parse(input)
+}
+
+fn parse_attribute_entry(input: &str) -> Res<'_, &str> {
+    recognize((
+        char(':'),
+        opt(char('!')),
+        take_while1(|c: char| c.is_alphanumeric() || c == '-' || c == '_'),
+        opt(char('!')),
+        char(':'),
+        opt((take_while1(is_ws), parse_rest)),
+        parse_eol,
     ))
-    .parse(input)?;
-    Ok((
-        input,
-        Block {
-            title,
-            attributes,
-            content,
+    .parse(input)
+}
+
+/// Attribute entries and comments of the header, keeping the lines of the entries
+fn parse_header_entries(input: &str) -> Res<'_, Vec<&str>> {
+    fold_many0(
+        alt((
+            parse_attribute_entry.map(Some),
+            parse_comment_line.map(|_| None),
+        )),
+        Vec::new,
+        |mut entries, entry| {
+            entries.extend(entry);
+            entries
         },
+    )
+    .parse(input)
+}
+
+fn parse_document_header(input: &str) -> Res<'_, DocumentHeader<'_>> {
+    let (input, _) = peek(alt((parse_attribute_entry, recognize(parse_title)))).parse(input)?;
+    let (input, mut entries) = parse_header_entries(input)?;
+    let (input, lines) = opt((
+        parse_title,
+        opt((parse_author_line, opt(parse_revision_line))),
     ))
+    .parse(input)?;
+    let (input, after) = parse_header_entries(input)?;
+    entries.extend(after);
+    let mut header = DocumentHeader {
+        title: None,
+        authors: Vec::new(),
+        revision: None,
+        attributes: Vec::new(),
+    };
+    let mut author_line = false;
+    if let Some((title, lines)) = lines {
+        header.title = Some(title);
+        if let Some((authors, revision)) = lines {
+            author_line = true;
+            header.authors = authors;
+            header.revision = revision;
+        }
+    }
+    let entries: Vec<_> = entries
+        .into_iter()
+        .filter_map(split_attribute_entry)
+        .collect();
+    header.attributes = entries
+        .iter()
+        .map(|&(key, value)| Attribute {
+            key: key.into(),
+            value: value.map(Into::into),
+        })
+        .collect();
+    if !author_line {
+        header.authors = attribute_authors(&entries);
+    }
+    if header.revision.is_none() {
+        header.revision = attribute_revision(&entries);
+    }
+    Ok((input, header))
+}
+
+/// Start of a header line after the title, which can't be an attribute entry or a comment
+fn parse_header_line_start(input: &str) -> Res<'_, &str> {
+    preceded(
+        (
+            not(parse_attribute_entry),
+            not(parse_comment_line),
+            parse_ws,
+        ),
+        recognize(not(parse_eol)),
+    )
+    .parse(input)
+}
+
+fn parse_author_line(input: &str) -> Res<'_, Vec<Author<'_>>> {
+    let (input, _) = parse_header_line_start(input)?;
+    let (input, authors) = opt(many1(preceded(
+        take_while(|c| is_ws(c) || c == ';'),
+        parse_author,
+    )))
+    .parse(input)?;
+    let (input, _) = terminated(parse_rest, parse_eol).parse(input)?;
+    // Authors after one with an invalid email are ignored, like by the hand written parser
+    let authors = authors
+        .unwrap_or_default()
+        .into_iter()
+        .map_while(|(name, email)| header::author(name, email))
+        .collect();
+    Ok((input, authors))
+}
+
+/// Name of an author and their email
+fn parse_author(input: &str) -> Res<'_, (&str, Option<&str>)> {
+    let names = recognize(many1_count(preceded(
+        not(alt((
+            tag(";"),
+            tag("<"),
+            recognize(satisfy(char::is_control)),
+            recognize((parse_ws, parse_eol)),
+        ))),
+        anychar,
+    )));
+    let email = take_while(|c: char| c != '>' && !c.is_control());
+    (
+        names,
+        alt((
+            delimited(char('<'), email, char('>')).map(Some),
+            not(char('<')).map(|_| None),
+        )),
+    )
+        .parse(input)
 }
 
-fn parse_block_title<'a>(input: &'a str) -> IResult<&'a str, &'a str> {
-    delimited((tag("."), not(space1)), is_not("\r\n"), newline).parse(input)
+fn parse_revision_line(input: &str) -> Res<'_, Revision<'_>> {
+    let (input, _) = parse_header_line_start(input)?;
+    let (input, line) = terminated(parse_rest, parse_eol).parse(input)?;
+    Ok((input, header::revision(line)))
 }
 
-fn parse_attribute_list<'a>(input: &'a str) -> IResult<&'a str, Vec<Attribute<'a>>> {
+/// Blocks up to the next section heading of at most `level`, nesting the sections below it
+fn parse_section_content(input: &str, level: Option<usize>) -> Res<'_, Vec<Block<'_>>> {
+    let mut input = input;
+    let mut blocks = Vec::new();
+    while !input.is_empty() {
+        if let Ok((rest, _)) =
+            alt((parse_blank_line, parse_comment_line, parse_attribute_entry)).parse(input)
+        {
+            input = rest;
+        } else if let Ok((rest, (mut block, heading))) =
+            (parse_block_metadata, parse_heading).parse(input)
+        {
+            if level.is_some_and(|level| heading.level <= level) {
+                break;
+            }
+            let (rest, content) = parse_section_content(rest, Some(heading.level))?;
+            block.heading = Some(heading);
+            block.content =
+                BlockContent::Section(content.into_iter().map(SectionContent::Block).collect());
+            blocks.push(block);
+            input = rest;
+        } else if let Ok((rest, block)) = parse_block(input) {
+            blocks.extend(block);
+            input = rest;
+        } else {
+            break;
+        }
+    }
+    Ok((input, blocks))
+}
+
+enum Metadata<'a> {
+    Title(&'a str),
+    Anchor(&'a str),
+    Attributes(Vec<Attribute<'a>>),
+}
+
+/// Block with the title and attributes from the metadata lines, its content is left to the
+/// caller
+fn parse_block_metadata(input: &str) -> Res<'_, Block<'_>> {
+    let empty = || Block {
+        heading: None,
+        title: None,
+        attributes: Vec::new(),
+        content: BlockContent::Undelimited(Vec::new()),
+    };
+    fold_many0(
+        alt((
+            parse_block_title.map(Metadata::Title),
+            parse_block_anchor.map(Metadata::Anchor),
+            parse_attribute_list.map(Metadata::Attributes),
+        )),
+        empty,
+        |mut block, metadata| {
+            match metadata {
+                Metadata::Title(title) => {
+                    block.title.get_or_insert(title.into());
+                }
+                // `[[id]]` is a shorthand for `[id=id]`
+                Metadata::Anchor(id) => block.attributes.push(Attribute {
+                    key: "id".into(),
+                    value: Some(id.into()),
+                }),
+                Metadata::Attributes(attributes) => block.attributes.extend(attributes),
+            }
+            block
+        },
+    )
+    .parse(input)
+}
+
+fn parse_block_title(input: &str) -> Res<'_, &str> {
     delimited(
-        tag("["),
-        separated_list1(tag(", "), parse_attribute),
-        tag("]"),
+        (char('.'), not(satisfy(|c| c.is_whitespace() || c == '.'))),
+        parse_line,
+        parse_eol,
     )
+    .map(str::trim_end)
     .parse(input)
 }
 
-fn parse_attribute<'a>(input: &'a str) -> IResult<&'a str, Attribute<'a>> {
-    let (input, (key, value)) = separated_pair(
-        alt((is_not("="), delimited(tag("\""), is_not("\""), tag("\"")))),
-        tag("="),
-        alt((is_not(","), delimited(tag("\""), is_not("\""), tag("\"")))),
+fn parse_block_anchor(input: &str) -> Res<'_, &str> {
+    delimited(
+        (tag("[["), not(char('['))),
+        recognize((
+            satisfy(|c| c.is_alphabetic() || c == '_' || c == ':'),
+            take_while(|c: char| c.is_alphanumeric() || "_:-.".contains(c)),
+        )),
+        (
+            opt((char(','), take_while(|c| c != ']' && !is_line_end(c)))),
+            tag("]]"),
+            not(char(']')),
+            parse_ws,
+            parse_eol,
+        ),
     )
-    .parse(input)?;
-    Ok((input, Attribute { key, value }))
+    .parse(input)
 }
 
-fn parse_list_content<'a>(input: &'a str) -> IResult<&'a str, Vec<ListContent<'a>>> {
-    todo!()
+fn parse_attribute_list(input: &str) -> Res<'_, Vec<Attribute<'_>>> {
+    delimited(
+        (char('['), not(char('['))),
+        (
+            opt(parse_attribute),
+            many0(preceded((char(','), parse_ws), opt(parse_attribute))),
+        ),
+        (char(']'), parse_ws, parse_eol),
+    )
+    .map(|(first, rest)| {
+        first
+            .into_iter()
+            .chain(rest.into_iter().flatten())
+            .collect()
+    })
+    .parse(input)
 }
 
-fn parse_ordered_list<'a>(input: &'a str) -> IResult<&'a str, Vec<ListContent<'a>>> {
-    todo!()
+fn parse_attribute_value(input: &str) -> Res<'_, &str> {
+    alt((
+        delimited(
+            char('"'),
+            take_while(|c| c != '"' && !is_line_end(c)),
+            char('"'),
+        ),
+        preceded(
+            not(char('"')),
+            take_while(|c| c != ']' && c != ',' && !is_line_end(c)),
+        ),
+    ))
+    .parse(input)
 }
 
-fn parse_section_content<'a>(input: &'a str) -> IResult<&'a str, Vec<SectionContent<'a>>> {
-    todo!()
+fn parse_attribute(input: &str) -> Res<'_, Attribute<'_>> {
+    alt((
+        // Positional attributes are keyed by their value, like the ones without quotes
+        preceded(peek(char('"')), parse_attribute_value).map(|value| Attribute {
+            key: value.into(),
+            value: None,
+        }),
+        (
+            take_while1(|c| c != '=' && c != ']' && c != ',' && !is_line_end(c)),
+            opt(preceded(char('='), parse_attribute_value)),
+        )
+            .map(|(key, value)| Attribute {
+                key: key.into(),
+                value: value.map(Cow::Borrowed),
+            }),
+    ))
+    .parse(input)
 }
 
-fn parse_delimited_block_content<'a>(
-    input: &'a str,
-) -> IResult<&'a str, Vec<DelimitedBlockContent<'a>>> {
-    many1(alt((
-        map(parse_delimited_subblocks, |block| {
-            DelimitedBlockContent::Block(block)
+/// Lines joined by newlines, borrowed from `input` when they follow each other in it
+fn join<'a>(input: &'a str, lines: &[&'a str]) -> Cow<'a, str> {
+    join_lines(
+        input,
+        lines.iter().map(|line| {
+            let start = input.offset(line);
+            start..start + line.len()
         }),
-        map(parse_line, |line| DelimitedBlockContent::Text(line)),
-    )))
+    )
+}
+
+/// A block other than a section, comment blocks are parsed but have no block
+fn parse_block(input: &str) -> Res<'_, Option<Block<'_>>> {
+    let paragraph = |input| {
+        parse_undelimited_block_content
+            .map(|content| Some(BlockContent::Undelimited(content)))
+            .parse(input)
+    };
+    alt((
+        (
+            parse_block_metadata,
+            alt((
+                parse_delimited_block_content,
+                parse_break_block.map(|kind| Some(BlockContent::Break(kind))),
+                parse_block_macro.map(|m| Some(BlockContent::Macro(m))),
+                parse_list_content.map(|list| Some(BlockContent::List(list))),
+                paragraph,
+            )),
+        )
+            .map(|(block, content)| content.map(|content| Block { content, ..block })),
+        // Metadata lines without a block below them are a paragraph
+        paragraph.map(|content| {
+            content.map(|content| Block {
+                heading: None,
+                title: None,
+                attributes: Vec::new(),
+                content,
+            })
+        }),
+    ))
     .parse(input)
 }
 
-fn parse_delimited_subblocks<'a>(input: &'a str) -> IResult<&'a str, Block<'a>> {
-    let (input, (title, attributes)) = parse_block_header(input)?;
-    let (input, content) = alt((
-        map(parse_list_content, BlockContent::List),
-        map(parse_section_content, BlockContent::Section),
-        map(parse_undelimited_block_content, BlockContent::Undelimited),
+fn parse_undelimited_block_content(input: &str) -> Res<'_, Vec<UndelimitedBlockContent<'_>>> {
+    let (rest, (first, lines)) = (
+        parse_text_line,
+        many0(alt((
+            parse_comment_line.map(|_| None),
+            parse_text_line.map(Some),
+        ))),
+    )
+        .parse(input)?;
+    let lines: Vec<&str> = [first]
+        .into_iter()
+        .chain(lines.into_iter().flatten())
+        .collect();
+    Ok((
+        rest,
+        vec![UndelimitedBlockContent::Text(join(input, &lines))],
+    ))
+}
+
+fn parse_delimiter(input: &str) -> Res<'_, &str> {
+    let repeated = |c: char| verify(take_while1(move |d| d == c), |d: &str| d.len() >= 4);
+    alt((
+        repeated('-'),
+        repeated('.'),
+        repeated('+'),
+        repeated('/'),
+        repeated('='),
+        repeated('*'),
+        repeated('_'),
+        tag("--"),
     ))
-    .parse(input)?;
+    .parse(input)
+}
+
+fn parse_delimiter_line(input: &str) -> Res<'_, &str> {
+    terminated(parse_delimiter, (parse_ws, parse_eol)).parse(input)
+}
+
+/// Delimited block up to the matching delimiter, or the end of the document when it is missing
+fn parse_delimited_block_content(input: &str) -> Res<'_, Option<BlockContent<'_>>> {
+    let (input, delimiter) = parse_delimiter_line(input)?;
+    let kind = delimiter_kind(delimiter).expect("The delimiter to have a kind");
+    let closing = |input| (tag(delimiter), parse_ws, parse_eol).parse(input);
+    let (rest, content) = if kind.is_verbatim() {
+        let (rest, lines) = many0(preceded(
+            not(closing),
+            alt((terminated(parse_rest, parse_newline), parse_line)),
+        ))
+        .parse(input)?;
+        (rest, vec![DelimitedBlockContent::Text(join(input, &lines))])
+    } else {
+        let (rest, blocks) = many0(preceded(
+            (not(closing), peek(anychar)),
+            alt((
+                alt((parse_blank_line, parse_comment_line, parse_attribute_entry)).map(|_| None),
+                parse_block,
+            )),
+        ))
+        .parse(input)?;
+        let blocks = blocks.into_iter().flatten();
+        (rest, blocks.map(DelimitedBlockContent::Block).collect())
+    };
+    let (rest, _) = alt((recognize(closing), eof)).parse(rest)?;
     Ok((
-        input,
-        Block {
-            title,
-            attributes,
-            content,
-        },
+        rest,
+        (kind != DelimitedBlockKind::Comment).then_some(BlockContent::Delimited(kind, content)),
     ))
 }
 
+/// `'''`, `---` and `***` are thematic breaks, `<<<` is a page break
+fn parse_break_block(input: &str) -> Res<'_, BreakKind> {
+    let repeated = |c: char| verify(take_while1(move |d| d == c), |d: &str| d.len() >= 3);
+    let marker = alt((repeated('\''), repeated('<'), tag("---"), tag("***")));
+    terminated(marker, (parse_ws, parse_eol))
+        .map(|marker| break_kind(marker).expect("The marker to have a kind"))
+        .parse(input)
+}
 
-fn parse_undelimited_block_content<'a>(
-    input: &'a str,
-) -> IResult<&'a str, Vec<UndelimitedBlockContent<'a>>> {
-    many1(alt((
-        map(parse_undelimited_subblocks, |block| {
-            UndelimitedBlockContent::Block(block)
-        }),
-        map(parse_line, |line| UndelimitedBlockContent::Text(line)),
-    )))
+/// `name::target[attributes]` line of one of the block macros, other names are text
+fn parse_block_macro(input: &str) -> Res<'_, BlockMacro<'_>> {
+    (
+        verify(
+            take_while1(|c: char| c.is_alphanumeric() || c == '_' || c == '-'),
+            is_block_macro,
+        ),
+        tag("::"),
+        not(char(':')),
+        take_while(|c: char| c != '[' && !c.is_whitespace()),
+        parse_attribute_list,
+    )
+        .map(|(name, _, _, target, attributes)| BlockMacro {
+            name: name.into(),
+            target: target.into(),
+            attributes,
+        })
+        .parse(input)
+}
+
+fn parse_list_marker(input: &str) -> Res<'_, &str> {
+    alt((
+        tag("-"),
+        take_while_m_n(1, 5, |c| c == '*'),
+        take_while_m_n(1, 5, |c| c == '.'),
+        recognize((satisfy(|c| c.is_ascii_alphabetic()), char('.'))),
+        recognize((take_while1(|c: char| c.is_ascii_digit()), char('.'))),
+        recognize((take_while1(|c| "ivx".contains(c)), char(')'))),
+        recognize((take_while1(|c| "IVX".contains(c)), char(')'))),
+    ))
     .parse(input)
 }
 
-fn parse_undelimited_subblocks<'a>(input: &'a str) -> IResult<&'a str, Block<'a>> {
-    let (input, (title, attributes)) = parse_block_header(input)?;
-    let (input, content) = alt((
-        map(parse_list_content, BlockContent::List),
-        map(parse_section_content, BlockContent::Section),
-        map(parse_delimited_block_content, BlockContent::Delimited),
+/// Line starting a list item
+fn parse_list_item_start(input: &str) -> Res<'_, &str> {
+    alt((
+        recognize((
+            parse_ws,
+            parse_list_marker,
+            take_while1(is_ws),
+            satisfy(|c| !is_line_end(c)),
+        )),
+        verify(parse_rest, |line: &str| description_marker(line).is_some()),
     ))
+    .parse(input)
+}
+
+/// Lines directly following a list item, which are part of its text
+fn parse_list_item_lines(input: &str) -> Res<'_, Vec<&str>> {
+    many0(preceded(
+        (not(parse_list_item_start), not(parse_list_continuation)),
+        alt((parse_comment_line.map(|_| None), parse_text_line.map(Some))),
+    ))
+    .map(|lines| lines.into_iter().flatten().collect())
+    .parse(input)
+}
+
+/// `+` line attaching the next block to a list item
+fn parse_list_continuation(input: &str) -> Res<'_, &str> {
+    recognize((char('+'), parse_ws, parse_eol)).parse(input)
+}
+
+/// Paragraph attached to a list item, ending at the next list item
+fn parse_item_paragraph(input: &str) -> Res<'_, Option<BlockContent<'_>>> {
+    let (rest, (first, lines)) = preceded(
+        (not(parse_list_item_start), not(parse_list_continuation)),
+        (parse_text_line, parse_list_item_lines),
+    )
     .parse(input)?;
-    Ok((
-        input,
-        Block {
-            title,
-            attributes,
-            content,
-        },
+    let lines: Vec<&str> = [first].into_iter().chain(lines).collect();
+    let content = vec![UndelimitedBlockContent::Text(join(input, &lines))];
+    Ok((rest, Some(BlockContent::Undelimited(content))))
+}
+
+/// Blocks attached to a list item by `+` lines, comment blocks are parsed but have no block
+fn parse_attached_blocks(input: &str) -> Res<'_, Vec<Block<'_>>> {
+    many0(preceded(
+        parse_list_continuation,
+        (
+            parse_block_metadata,
+            alt((parse_delimited_block_content, parse_item_paragraph)),
+        ),
     ))
+    .map(|blocks| {
+        blocks
+            .into_iter()
+            .filter_map(|(block, content)| content.map(|content| Block { content, ..block }))
+            .collect()
+    })
+    .parse(input)
+}
+
+fn parse_checkbox(input: &str) -> Res<'_, bool> {
+    terminated(
+        delimited(char('['), satisfy(|c| " x*".contains(c)), char(']')),
+        (take_while1(is_ws), peek(satisfy(|c| !is_line_end(c)))),
+    )
+    .map(|checked| checked != ' ')
+    .parse(input)
+}
+
+/// Marker and text of a list item, with the lines following it and the blocks attached to it
+fn parse_list_item(input: &str) -> Res<'_, ListItem<'_>> {
+    let (rest, (_, marker, _)) = (parse_ws, parse_list_marker, take_while1(is_ws)).parse(input)?;
+    let checklist = list_kind(marker) == ListKind::Unordered;
+    let (rest, (checked, first, _, lines, blocks)) = (
+        cond(checklist, opt(parse_checkbox)).map(Option::flatten),
+        parse_line,
+        parse_eol,
+        parse_list_item_lines,
+        parse_attached_blocks,
+    )
+        .parse(rest)?;
+    let lines: Vec<&str> = [first.trim_end()].into_iter().chain(lines).collect();
+    let item = ListItem {
+        marker,
+        term: None,
+        checked,
+        text: join(input, &lines),
+        blocks,
+    };
+    Ok((rest, item))
+}
+
+/// Term, marker and description of a description list item, the description may start on
+/// the next line
+fn parse_description_item(input: &str) -> Res<'_, ListItem<'_>> {
+    let (_, line) = parse_rest(input)?;
+    let Some((term, marker)) = description_marker(line) else {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Verify,
+        )));
+    };
+    let after_marker = &input[input.offset(marker) + marker.len()..];
+    let (rest, (first, _, lines, blocks)) = (
+        preceded(parse_ws, parse_rest),
+        parse_eol,
+        parse_list_item_lines,
+        parse_attached_blocks,
+    )
+        .parse(after_marker)?;
+    let first = first.trim_end();
+    let lines: Vec<&str> = (!first.is_empty())
+        .then_some(first)
+        .into_iter()
+        .chain(lines)
+        .collect();
+    let item = ListItem {
+        marker,
+        term: Some(term.trim_end().into()),
+        checked: None,
+        text: join(input, &lines),
+        blocks,
+    };
+    Ok((rest, item))
+}
+
+fn parse_list_content(input: &str) -> Res<'_, Vec<ListContent<'_>>> {
+    let item = |input| alt((parse_list_item, parse_description_item)).parse(input);
+    let (rest, (first, items)) =
+        (item, many0(preceded(many0_count(parse_blank_line), item))).parse(input)?;
+    let mut items = [first].into_iter().chain(items).peekable();
+    Ok((rest, nest_list(&mut items, &[])))
 }
@@ -0,0 +1,149 @@
+use std::rc::Rc;
+
+use crate::{
+    checkpoint_iterator::CheckpointIterator,
+    cst::{SyntaxElement, SyntaxKind, SyntaxNode, TextEdit, TextRange},
+};
+
+use super::{
+    ParseError,
+    body::{parse_element, peek_heading_level},
+    traits::DocSyntaxParser,
+};
+
+/// Updates `tree` after `edit` has been applied to its text, `text` being the text after the
+/// edit.
+///
+/// Only the blocks touched by the edit are parsed again, descending into the section that
+/// contains it, the rest of the tree is reused. Falls back to parsing the whole text when the
+/// edit changes the document header or the structure around it can't be resynchronised.
+pub fn reparse(tree: &mut SyntaxNode, edit: &TextEdit, text: &str) -> Result<(), ParseError> {
+    let first_content = match tree
+        .children
+        .iter()
+        .position(|c| c.kind() == SyntaxKind::Header)
+    {
+        Some(header) => header + 1,
+        None => tree
+            .children
+            .iter()
+            .position(|c| !is_trivia(c))
+            .unwrap_or(tree.children.len()),
+    };
+    if !reparse_children(tree, 0, first_content, None, edit, text) {
+        log::debug!("Falling back to a full reparse for {edit:?}");
+        *tree = CheckpointIterator::new(text.chars()).parse_syntax_tree()?;
+    }
+    Ok(())
+}
+
+fn is_trivia(element: &SyntaxElement) -> bool {
+    matches!(
+        element.kind(),
+        SyntaxKind::BlankLine | SyntaxKind::LineComment
+    )
+}
+
+/// Reparses the children of `node` starting at `base` in the old text, the children before
+/// `first_content` are never reparsed at this level. `level` is the level of the section
+/// being reparsed, if any.
+fn reparse_children(
+    node: &mut SyntaxNode,
+    base: usize,
+    first_content: usize,
+    level: Option<usize>,
+    edit: &TextEdit,
+    text: &str,
+) -> bool {
+    let delta = edit.replacement.len() as isize - edit.range.len() as isize;
+    let mut ranges = Vec::with_capacity(node.children.len());
+    let mut offset = base;
+    for child in node.children.iter() {
+        ranges.push(TextRange::new(offset, offset + child.text_len()));
+        offset += child.text_len();
+    }
+    let end = offset;
+
+    let mut affected = (first_content..node.children.len())
+        .filter(|i| ranges[*i].start <= edit.range.end && ranges[*i].end >= edit.range.start);
+    let Some(first) = affected.next() else {
+        return false;
+    };
+    let last = affected.next_back().unwrap_or(first);
+
+    // Edits within the content of a single section only affect that section
+    if first == last
+        && ranges[first].start < edit.range.start
+        && edit.range.end < ranges[first].end
+        && let SyntaxElement::Node(section) = &mut node.children[first]
+        && section.kind == SyntaxKind::Section
+        && let Some(heading) = section
+            .children
+            .iter()
+            .position(|c| c.kind() == SyntaxKind::Heading)
+    {
+        let heading_end = ranges[first].start
+            + section.children[..=heading]
+                .iter()
+                .map(SyntaxElement::text_len)
+                .sum::<usize>();
+        let section_level = section.children[heading]
+            .as_node()
+            .map(|h| h.token_text(SyntaxKind::HeadingMarker).len())
+            .unwrap_or_default();
+        if heading_end <= edit.range.start
+            && reparse_children(
+                Rc::make_mut(section),
+                ranges[first].start,
+                heading + 1,
+                Some(section_level),
+                edit,
+                text,
+            )
+        {
+            return true;
+        }
+    }
+
+    // The end of a block depends on the lines following it, e.g. a list continues after blank
+    // lines, so the block before the edit is parsed again as well
+    let mut start_index = first;
+    while start_index > first_content && is_trivia(&node.children[start_index - 1]) {
+        start_index -= 1;
+    }
+    if start_index > first_content {
+        start_index -= 1;
+    } else if level.is_none() {
+        // The first block after the header could be merged into it
+        return false;
+    }
+
+    let start = ranges[start_index].start;
+    let new_end = (end as isize + delta) as usize;
+    let mut iter = CheckpointIterator::new(text[start..].chars());
+    let mut pos = start;
+    let mut parsed: Vec<SyntaxElement> = Vec::new();
+    loop {
+        let old_pos = pos as isize - delta;
+        if !parsed.is_empty() && old_pos >= edit.range.end as isize {
+            let old_pos = old_pos as usize;
+            if let Ok(k) = ranges.binary_search_by_key(&old_pos, |r| r.end) {
+                node.children.splice(start_index..=k, parsed);
+                return true;
+            }
+        }
+        if pos > new_end || iter.peek().is_none() {
+            return false;
+        }
+        if let Some(level) = level
+            && peek_heading_level(&mut iter).is_some_and(|l| l <= level)
+        {
+            return false;
+        }
+        let Ok(element) = parse_element(&mut iter, true) else {
+            return false;
+        };
+        pos += element.text_len();
+        parsed.push(element.into());
+    }
+}
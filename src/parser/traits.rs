@@ -1,10 +1,20 @@
+pub mod body;
 pub mod header;
 use super::ParseError;
 
-use crate::ast::Document;
+use crate::{ast::Document, cst::SyntaxNode};
 
 pub trait DocParser {
-    fn parse_document(&mut self) -> Result<Document, ParseError>;
+    fn parse_document(&mut self) -> Result<Document<'static>, ParseError>;
 }
 
-pub mod body;
+pub trait DocSyntaxParser {
+    fn parse_syntax_tree(&mut self) -> Result<SyntaxNode, ParseError>;
+}
+
+/// Parser of a whole input held in memory, producing a [`Document`] borrowing from it
+pub trait AsciiDocParser<'a> {
+    type Input;
+
+    fn try_to_ast(&self, input: Self::Input) -> Result<Document<'a>, ParseError>;
+}
@@ -1,46 +1,63 @@
-use crate::ast::{
-    Attribute, Block, BlockContent, DelimitedBlockContent, DocumentContent, ListContent,
-    SectionContent, UndelimitedBlockContent,
-};
+use crate::cst::{SyntaxElement, SyntaxNode};
 
 use super::ParseError;
 
 pub trait DocContentParser {
-    fn parse_document_content(&mut self) -> Result<DocumentContent, ParseError>;
+    fn parse_document_content(&mut self) -> Result<Vec<SyntaxElement>, ParseError>;
 }
 
 pub trait DocBlockParser {
-    fn parse_block(&mut self) -> Result<Block, ParseError>;
-    fn parse_section_block(&mut self) -> Result<Block, ParseError>;
-    fn parse_list_block(&mut self) -> Result<Block, ParseError>;
-    fn parse_delimited_block(&mut self) -> Result<Block, ParseError>;
-    fn parse_undelimited_block(&mut self) -> Result<Block, ParseError>;
+    fn parse_block(&mut self) -> Result<SyntaxNode, ParseError>;
+    fn parse_section_block(&mut self) -> Result<SyntaxNode, ParseError>;
+    fn parse_list_block(&mut self) -> Result<SyntaxNode, ParseError>;
+    fn parse_delimited_block(&mut self) -> Result<SyntaxNode, ParseError>;
+    fn parse_break_block(&mut self) -> Result<SyntaxNode, ParseError>;
+    /// Parses a `name::target[attributes]` line of one of the block macros
+    fn parse_macro_block(&mut self) -> Result<SyntaxNode, ParseError>;
+    fn parse_undelimited_block(&mut self) -> Result<SyntaxNode, ParseError>;
+}
+
+pub trait DocBlockTitleParser {
+    fn parse_block_title(&mut self) -> Result<SyntaxNode, ParseError>;
+}
+
+pub trait DocBlockAnchorParser {
+    fn parse_block_anchor(&mut self) -> Result<SyntaxNode, ParseError>;
 }
 
 pub trait DocAttributeParser {
-    fn parse_attribute(&mut self) -> Result<Attribute, ParseError>;
+    fn parse_attribute(&mut self) -> Result<SyntaxNode, ParseError>;
 }
 
 pub trait DocAttributesParser {
-    fn parse_attributes(&mut self) -> Result<Vec<Attribute>, ParseError>;
+    fn parse_attributes(&mut self) -> Result<SyntaxNode, ParseError>;
 }
 
-pub trait DocBlockContentParser {
-    fn parse_block_content(&mut self) -> Result<BlockContent, ParseError>;
+pub trait DocAttributeEntryParser {
+    fn parse_attribute_entry(&mut self) -> Result<SyntaxNode, ParseError>;
 }
 
 pub trait DocListContentParser {
-    fn parse_list_content(&mut self) -> Result<ListContent, ParseError>;
+    /// Parses a single list item, `markers` holds the markers of the enclosing lists
+    fn parse_list_content(&mut self, markers: &[String]) -> Result<SyntaxNode, ParseError>;
 }
 
 pub trait DocSectionContentParser {
-    fn parse_section_content(&mut self) -> Result<Vec<SectionContent>, ParseError>;
+    fn parse_section_content(&mut self, level: usize) -> Result<Vec<SyntaxElement>, ParseError>;
 }
 
 pub trait DocDelimitedBlockContentParser {
-    fn parse_delimited_block_content(&mut self) -> Result<DelimitedBlockContent, ParseError>;
+    fn parse_delimited_block_content(
+        &mut self,
+        delimiter: &str,
+    ) -> Result<Vec<SyntaxElement>, ParseError>;
 }
 
 pub trait DocUndelimitedBlockContentParser {
-    fn parse_undelimited_block_content(&mut self) -> Result<UndelimitedBlockContent, ParseError>;
+    fn parse_undelimited_block_content(&mut self) -> Result<SyntaxNode, ParseError>;
+}
+
+pub trait DocTriviaParser {
+    fn parse_blank_line(&mut self) -> Result<SyntaxNode, ParseError>;
+    fn parse_line_comment(&mut self) -> Result<SyntaxNode, ParseError>;
 }
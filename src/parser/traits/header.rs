@@ -1,43 +1,43 @@
 use super::ParseError;
-use http::Uri;
 
-use crate::ast::{Author, DocumentHeader, Revision, SectionHeading};
+use crate::{
+    ast::{Author, Email, Revision},
+    cst::{SyntaxKind, SyntaxNode, SyntaxToken},
+};
 pub trait DocHeaderParser {
-    fn parse_document_header(&mut self) -> Result<DocumentHeader, ParseError>;
+    fn parse_document_header(&mut self) -> Result<SyntaxNode, ParseError>;
 }
 
 pub trait DocSectionHeading {
-    fn parse_section_heading(&mut self) -> Result<SectionHeading, ParseError>;
+    fn parse_section_heading(&mut self) -> Result<SyntaxNode, ParseError>;
 }
 
 pub trait RevisionLineParser {
-    fn parse_revision_line(&mut self) -> Result<Revision, ParseError>;
-}
-
-pub trait DateParser {
-    fn parse_date(&mut self) -> Result<chrono::NaiveDate, ParseError>;
-}
-
-pub trait VersionParser {
-    fn parse_version(&mut self) -> Result<Vec<isize>, ParseError>;
-}
-
-pub trait DecimalParser {
-    fn parse_decimal(&mut self) -> Result<isize, ParseError>;
+    fn parse_revision_line(&mut self) -> Result<Revision<'static>, ParseError>;
 }
 
 pub trait AuthorsParser {
-    fn parse_authors(&mut self) -> Result<Vec<Author>, ParseError>;
+    fn parse_authors(&mut self) -> Result<Vec<Author<'static>>, ParseError>;
 }
 
 pub trait AuthorParser {
-    fn parse_author(&mut self) -> Result<Author, ParseError>;
+    fn parse_author(&mut self) -> Result<Author<'static>, ParseError>;
 }
 
-pub trait UriParser {
-    fn parse_url(&mut self) -> Result<Uri, ParseError>;
+pub trait EmailParser {
+    /// Parses the address of an author line, up to the closing `>`
+    fn parse_email(&mut self) -> Result<Email<'static>, ParseError>;
 }
 
 pub trait LineParser {
     fn parse_line(&mut self) -> Result<String, ParseError>;
 }
+
+pub trait NewlineParser {
+    fn parse_newline(&mut self) -> Result<SyntaxToken, ParseError>;
+}
+
+pub trait TextLineParser {
+    /// Parses a non blank line into a node of the given kind
+    fn parse_text_line(&mut self, kind: SyntaxKind) -> Result<SyntaxNode, ParseError>;
+}
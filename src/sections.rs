@@ -0,0 +1,259 @@
+//! Ids and numbers of the sections, which depend on the whole document
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
+
+use crate::{
+    ast::{
+        Block, BlockContent, Document, DocumentHeader, SectionContent, SectionKind,
+        visit::{Visitor, walk_block},
+    },
+    subs::{Substitution, substitute},
+};
+
+/// Substitutions applied to a title before its id is generated from it, the markup they
+/// produce is then dropped
+const TITLE_SUBS: [Substitution; 4] = [
+    Substitution::SpecialCharacters,
+    Substitution::Quotes,
+    Substitution::Attributes,
+    Substitution::Replacements,
+];
+
+/// Sets the id of every section, generated from its title unless it has an explicit one or
+/// `sectids` is unset, and its number when `sectnums` is set
+pub fn assign(document: &mut Document<'_>) {
+    let header = document.header.as_ref();
+    let attribute = |name| header.and_then(|h| h.attribute(name));
+    let mut ids = header.is_none_or(|h| !is_unset(h, "sectids")).then(|| {
+        SectionIds::new(
+            header
+                .map(DocumentHeader::attribute_values)
+                .unwrap_or_default(),
+        )
+    });
+    if let Some(ids) = &mut ids {
+        let mut explicit = ExplicitIds(ids);
+        for block in &document.content.blocks {
+            explicit.visit_block(block);
+        }
+    }
+    let mut numbering = attribute("sectnums").map(|_| Numbering {
+        levels: attribute("sectnumlevels")
+            .and_then(|levels| levels.parse().ok())
+            .unwrap_or(3),
+        chapters: 0,
+        appendices: 0,
+    });
+    assign_blocks(
+        &mut document.content.blocks.iter_mut(),
+        ids.as_mut(),
+        numbering.as_mut(),
+        Parent::Root,
+    );
+}
+
+/// Generates unique section ids following the `idprefix` and `idseparator` attributes
+#[derive(Debug, Clone)]
+pub struct SectionIds {
+    prefix: String,
+    separator: String,
+    attributes: HashMap<String, String>,
+    taken: HashSet<String>,
+}
+
+impl SectionIds {
+    /// Generator using the values of the document `attributes`
+    pub fn new(attributes: HashMap<String, String>) -> Self {
+        Self {
+            prefix: attributes
+                .get("idprefix")
+                .map_or("_", String::as_str)
+                .into(),
+            separator: attributes
+                .get("idseparator")
+                .map_or("_", String::as_str)
+                .into(),
+            attributes,
+            taken: HashSet::new(),
+        }
+    }
+
+    /// Marks `id` as taken by an explicit anchor, so no generated id clashes with it
+    pub fn register(&mut self, id: &str) {
+        self.taken.insert(id.to_string());
+    }
+
+    /// Id for a section titled `title`, suffixed by `_2`, `_3`, ... when it is already taken
+    pub fn generate(&mut self, title: &str) -> String {
+        let base = self.base_id(title);
+        let separator = if self.separator.is_empty() {
+            "_"
+        } else {
+            &self.separator
+        };
+        let mut id = base.clone();
+        let mut n = 2;
+        while self.taken.contains(&id) {
+            id = format!("{base}{separator}{n}");
+            n += 1;
+        }
+        self.taken.insert(id.clone());
+        id
+    }
+
+    /// Lowercase title without markup, where runs of spaces, `.` and `-` become the separator
+    fn base_id(&self, title: &str) -> String {
+        let title = substitute(title.into(), &TITLE_SUBS, &self.attributes).to_lowercase();
+        let mut id = self.prefix.clone();
+        let mut in_run = false;
+        let mut chars = title.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let skipped = match c {
+                '<' => title[i..].find('>'),
+                '&' => entity_len(&title[i..]),
+                _ => None,
+            };
+            if let Some(len) = skipped {
+                while chars.next_if(|&(j, _)| j <= i + len).is_some() {}
+                continue;
+            }
+            if matches!(c, ' ' | '.' | '-') {
+                if !in_run {
+                    id.push_str(&self.separator);
+                }
+                in_run = true;
+            } else if c.is_alphanumeric() || c == '_' {
+                id.push(c);
+                in_run = false;
+            }
+        }
+        if !self.separator.is_empty() {
+            if let Some(trimmed) = id.strip_suffix(self.separator.as_str()) {
+                id.truncate(trimmed.len());
+            }
+            if self.prefix.is_empty()
+                && let Some(trimmed) = id.strip_prefix(self.separator.as_str())
+            {
+                id = trimmed.to_string();
+            }
+        }
+        id
+    }
+}
+
+/// Length up to the `;` of the character reference at the start of `text`
+fn entity_len(text: &str) -> Option<usize> {
+    let end = text.find(';')?;
+    let name = &text[1..end];
+    let valid = match name.strip_prefix('#') {
+        Some(code) => code.strip_prefix('x').map_or(
+            code.len() >= 2 && code.chars().all(|c| c.is_ascii_digit()),
+            |hex| hex.len() >= 2 && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        ),
+        None => name.len() >= 2 && name.chars().all(|c| c.is_ascii_alphanumeric()),
+    };
+    (valid && end <= 10).then_some(end)
+}
+
+fn is_unset(header: &DocumentHeader<'_>, name: &str) -> bool {
+    header
+        .attributes
+        .iter()
+        .rfind(|a| a.key == name)
+        .is_some_and(|a| a.value.is_none())
+}
+
+/// Registers the explicit ids of all the blocks before any id is generated
+struct ExplicitIds<'i>(&'i mut SectionIds);
+
+impl Visitor for ExplicitIds<'_> {
+    fn visit_block(&mut self, block: &Block<'_>) {
+        if let Some(id) = block.id() {
+            self.0.register(id);
+        }
+        walk_block(self, block);
+    }
+}
+
+struct Numbering {
+    /// Deepest numbered section level, `sectnumlevels`
+    levels: usize,
+    /// Chapters are numbered across the parts of a book
+    chapters: u32,
+    appendices: u32,
+}
+
+#[derive(Clone, Copy)]
+enum Parent<'n> {
+    /// The document or a part
+    Root,
+    Numbered(&'n str),
+    Unnumbered,
+}
+
+fn assign_blocks<'b, 'a: 'b>(
+    blocks: &mut dyn Iterator<Item = &'b mut Block<'a>>,
+    mut ids: Option<&mut SectionIds>,
+    mut numbering: Option<&mut Numbering>,
+    parent: Parent<'_>,
+) {
+    let mut count = 0;
+    for block in blocks {
+        let Some(kind) = block.section_kind() else {
+            continue;
+        };
+        let id = block.id().map(|id| Cow::Owned(id.to_string()));
+        let Some(heading) = &mut block.heading else {
+            continue;
+        };
+        heading.id = id.or_else(|| Some(Cow::Owned(ids.as_mut()?.generate(&heading.text))));
+        let level = heading.level.saturating_sub(1);
+        heading.number = match (numbering.as_deref_mut(), parent) {
+            (Some(numbering), _) if level > numbering.levels => None,
+            (Some(numbering), Parent::Root) if kind == SectionKind::Appendix => {
+                numbering.appendices += 1;
+                Some(appendix_letter(numbering.appendices))
+            }
+            (Some(numbering), Parent::Root) if kind == SectionKind::Section => {
+                numbering.chapters += 1;
+                Some(numbering.chapters.to_string())
+            }
+            (Some(_), Parent::Numbered(number)) if !kind.is_special() => {
+                count += 1;
+                Some(format!("{number}.{count}"))
+            }
+            _ => None,
+        };
+        let children = match (&heading.number, kind) {
+            (_, SectionKind::Part) => Parent::Root,
+            (Some(number), _) => Parent::Numbered(number),
+            (None, _) => Parent::Unnumbered,
+        };
+        if let BlockContent::Section(content) = &mut block.content {
+            assign_blocks(
+                &mut content.iter_mut().filter_map(|c| match c {
+                    SectionContent::Block(block) => Some(block),
+                    SectionContent::Text(_) => None,
+                }),
+                ids.as_deref_mut(),
+                numbering.as_deref_mut(),
+                children,
+            );
+        }
+    }
+}
+
+/// `A` to `Z`, then `AA`, `AB`, ... like spreadsheet columns
+fn appendix_letter(n: u32) -> String {
+    let mut n = n;
+    let mut letters = Vec::new();
+    while n > 0 {
+        n -= 1;
+        letters.push(char::from(b'A' + (n % 26) as u8));
+        n /= 26;
+    }
+    letters.iter().rev().collect()
+}
@@ -0,0 +1,500 @@
+pub(crate) mod macros;
+mod quotes;
+mod replacements;
+
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::{
+    ast::{
+        Block, BlockContent, DelimitedBlockKind, Inline,
+        visit_mut::{VisitorMut, walk_section_heading_mut},
+    },
+    footnotes::Footnotes,
+};
+
+/// Steps of the substitution pipeline, [`Substitution::NORMAL`] lists them in the order
+/// AsciiDoc applies them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Substitution {
+    /// Escapes `&`, `<` and `>`
+    SpecialCharacters,
+    /// Inline formatting like `*strong*`, `_emphasis_` and `` `monospace` ``
+    Quotes,
+    /// `{name}` references to attributes
+    Attributes,
+    /// Typographic replacements like `(C)`, `...` and `--`
+    Replacements,
+    /// Inline macros like `link:target[text]` and bare URLs
+    Macros,
+    /// Hard line breaks marked by a ` +` at the end of a line
+    PostReplacements,
+}
+
+impl Substitution {
+    pub const NORMAL: [Substitution; 6] = [
+        Substitution::SpecialCharacters,
+        Substitution::Quotes,
+        Substitution::Attributes,
+        Substitution::Replacements,
+        Substitution::Macros,
+        Substitution::PostReplacements,
+    ];
+
+    pub const VERBATIM: [Substitution; 1] = [Substitution::SpecialCharacters];
+
+    /// Substitutions called `name` in a `subs` attribute, groups like `normal` name several
+    pub fn from_name(name: &str) -> Option<&'static [Substitution]> {
+        Some(match name {
+            "specialcharacters" | "specialchars" | "c" => &[Self::SpecialCharacters],
+            "quotes" | "q" => &[Self::Quotes],
+            "attributes" | "a" => &[Self::Attributes],
+            "replacements" | "r" => &[Self::Replacements],
+            "macros" | "m" => &[Self::Macros],
+            "post_replacements" | "p" => &[Self::PostReplacements],
+            "normal" | "n" => &Self::NORMAL,
+            "verbatim" | "v" => &Self::VERBATIM,
+            "none" => &[],
+            _ => return None,
+        })
+    }
+
+    /// Applies the substitution to `text`, whose special characters are `escaped` by an
+    /// earlier substitution or not, returns `None` if it doesn't change anything
+    fn apply(
+        self,
+        text: &str,
+        attributes: &HashMap<String, String>,
+        escaped: bool,
+    ) -> Option<String> {
+        match self {
+            Self::SpecialCharacters => special_characters(text),
+            Self::Quotes => quotes::quotes(text),
+            Self::Attributes => attribute_references(text, attributes),
+            Self::Replacements => replacements::replacements(text, escaped),
+            Self::Macros => macros::macros(text, attributes),
+            Self::PostReplacements => post_replacements(text),
+        }
+    }
+}
+
+/// What a name in a `subs` attribute does with its substitutions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    /// `name` or `+name`
+    Append,
+    /// `name+`
+    Prepend,
+    /// `-name`
+    Remove,
+}
+
+/// Substitutions of a block with the `subs` attribute `value`, which either lists them or
+/// adds to and removes from the `defaults` with `+name`, `name+` and `-name`
+pub fn parse_subs(value: &str, defaults: &[Substitution]) -> Vec<Substitution> {
+    let names: Vec<&str> = value.split(',').map(str::trim).collect();
+    let incremental = names
+        .iter()
+        .any(|name| name.starts_with(['+', '-']) || name.ends_with('+'));
+    let mut subs = if incremental {
+        defaults.to_vec()
+    } else {
+        Vec::new()
+    };
+    for name in names.into_iter().filter(|name| !name.is_empty()) {
+        let (key, step) = if let Some(key) = name.strip_prefix('+') {
+            (key, Step::Append)
+        } else if let Some(key) = name.strip_prefix('-') {
+            (key, Step::Remove)
+        } else if let Some(key) = name.strip_suffix('+') {
+            (key, Step::Prepend)
+        } else {
+            (name, Step::Append)
+        };
+        let Some(named) = Substitution::from_name(key) else {
+            log::warn!("Unknown substitution '{key}'");
+            continue;
+        };
+        subs.retain(|sub| !named.contains(sub));
+        match step {
+            Step::Append => subs.extend(named),
+            Step::Prepend => {
+                subs.splice(0..0, named.iter().copied());
+            }
+            Step::Remove => {}
+        }
+    }
+    subs
+}
+
+/// Substitutions applied to the content of delimited blocks of `kind` by default
+pub fn default_substitutions(kind: DelimitedBlockKind) -> &'static [Substitution] {
+    match kind {
+        DelimitedBlockKind::Listing | DelimitedBlockKind::Literal => &Substitution::VERBATIM,
+        DelimitedBlockKind::Passthrough | DelimitedBlockKind::Comment => &[],
+        _ => &Substitution::NORMAL,
+    }
+}
+
+/// Substitutions applied to the content of `block`, from its `subs` attribute and the
+/// defaults for its kind and style
+pub fn block_substitutions(block: &Block<'_>) -> Vec<Substitution> {
+    let defaults = match (&block.content, block.style()) {
+        (BlockContent::Delimited(kind, _), _) => default_substitutions(*kind),
+        (_, Some("source" | "listing" | "literal")) => &Substitution::VERBATIM,
+        (_, Some("pass")) => &[],
+        _ => &Substitution::NORMAL,
+    };
+    match block
+        .attributes
+        .iter()
+        .find_map(|a| (a.key == "subs").then_some(a.value.as_deref()).flatten())
+    {
+        Some(value) => parse_subs(value, defaults),
+        None => defaults.to_vec(),
+    }
+}
+
+/// Splits `text` into the typographic replacements, character references and hard line
+/// breaks it contains and the text around them, so they can be output as symbols or as HTML
+/// references
+pub fn inlines(text: &str) -> Vec<Inline<'_>> {
+    replacements::parse(text, false)
+}
+
+/// Applies `subs` to `text` in order, the `+++` and `pass:[]` passthroughs are left as is
+/// when macros are substituted
+pub fn substitute<'a>(
+    text: Cow<'a, str>,
+    subs: &[Substitution],
+    attributes: &HashMap<String, String>,
+) -> Cow<'a, str> {
+    let mut passthroughs = Vec::new();
+    let mut text = text;
+    if subs.contains(&Substitution::Macros)
+        && let Some(extracted) = macros::extract_passthroughs(&text, &mut passthroughs, attributes)
+    {
+        text = Cow::Owned(extracted);
+    }
+    let mut escaped = false;
+    for sub in subs {
+        if let Some(substituted) = sub.apply(&text, attributes, escaped) {
+            text = Cow::Owned(substituted);
+        }
+        escaped |= *sub == Substitution::SpecialCharacters;
+    }
+    if !passthroughs.is_empty() {
+        text = Cow::Owned(macros::restore_passthroughs(&text, &passthroughs));
+    }
+    text
+}
+
+/// Text of `text` after the normal substitutions without the markup they add, with the
+/// character references as characters, for titles in outlines
+pub fn plain_text(text: &str, attributes: &HashMap<String, String>) -> String {
+    let html = substitute(text.into(), &Substitution::NORMAL, attributes);
+    let mut stripped = String::with_capacity(html.len());
+    let mut rest = html.as_ref();
+    while let Some(start) = rest.find('<') {
+        stripped.push_str(&rest[..start]);
+        rest = rest[start..]
+            .find('>')
+            .map_or("", |end| &rest[start + end + 1..]);
+    }
+    stripped.push_str(rest);
+    inlines(&stripped)
+        .into_iter()
+        .map(|inline| match inline {
+            Inline::Text(text) => text,
+            Inline::Replacement(replacement) => replacement.text().replace('\u{200b}', "").into(),
+            Inline::CharacterReference(reference) => match reference.char() {
+                // Only there to allow a line break after dashes and ellipses
+                Some('\u{200b}') => "".into(),
+                Some(c) => c.to_string().into(),
+                None => reference.entity().into(),
+            },
+            Inline::HardBreak => " ".into(),
+        })
+        .collect()
+}
+
+/// Applies the substitutions to the text of a document, with the ones given by
+/// [`block_substitutions`] for the content of each block
+///
+/// The footnotes are numbered across the document as their macros are replaced, and are
+/// kept in [`Substitutor::footnotes`] to be rendered at the end of it.
+pub struct Substitutor<'s> {
+    attributes: &'s HashMap<String, String>,
+    subs: Vec<Substitution>,
+    /// Whether every line of the block ends with a line break, set by the `hardbreaks` option
+    hardbreaks: bool,
+    footnotes: Footnotes,
+}
+
+impl<'s> Substitutor<'s> {
+    /// Substitutor replacing the references to `attributes` with their value
+    pub fn new(attributes: &'s HashMap<String, String>) -> Self {
+        Self {
+            attributes,
+            subs: Substitution::NORMAL.to_vec(),
+            hardbreaks: false,
+            footnotes: Footnotes::default(),
+        }
+    }
+
+    /// Footnotes of the text substituted so far
+    pub fn footnotes(&self) -> &Footnotes {
+        &self.footnotes
+    }
+}
+
+impl VisitorMut for Substitutor<'_> {
+    fn visit_block_mut(&mut self, block: &mut Block<'_>) {
+        // Titles get the normal substitutions, the `subs` of the block are for its content
+        let subs = std::mem::replace(&mut self.subs, Substitution::NORMAL.to_vec());
+        let hardbreaks = std::mem::take(&mut self.hardbreaks);
+        if let Some(heading) = &mut block.heading {
+            walk_section_heading_mut(self, heading);
+        }
+        if let Some(title) = &mut block.title {
+            self.visit_text_mut(title);
+        }
+        self.subs = block_substitutions(block);
+        self.hardbreaks =
+            block.has_option("hardbreaks") || self.attributes.contains_key("hardbreaks-option");
+        self.visit_block_content_mut(&mut block.content);
+        self.subs = subs;
+        self.hardbreaks = hardbreaks;
+    }
+
+    fn visit_text_mut(&mut self, text: &mut Cow<'_, str>) {
+        *text = substitute(std::mem::take(text), &self.subs, self.attributes);
+        if self.hardbreaks
+            && self.subs.contains(&Substitution::PostReplacements)
+            && let Some(broken) = hard_breaks(text)
+        {
+            *text = Cow::Owned(broken);
+        }
+        if self.subs.contains(&Substitution::Macros)
+            && let Some(replaced) = self.footnotes.replace_html(text)
+        {
+            *text = Cow::Owned(replaced);
+        }
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn special_characters(text: &str) -> Option<String> {
+    if !text.contains(['&', '<', '>']) {
+        return None;
+    }
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            c => result.push(c),
+        }
+    }
+    Some(result)
+}
+
+/// `text` with its special characters and double quotes escaped, for an HTML attribute value
+pub(crate) fn escape_html(text: &str) -> String {
+    escape_quotes(&special_characters(text).unwrap_or_else(|| text.to_string()))
+}
+
+/// Substituted `text` with its double quotes escaped, for an HTML attribute value
+pub(crate) fn escape_quotes(text: &str) -> String {
+    text.replace('"', "&quot;")
+}
+
+/// Value of the built in attributes, which are defined in every document
+fn intrinsic_attribute(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "empty" => "",
+        "sp" => " ",
+        "nbsp" => "&#160;",
+        "zwsp" => "&#8203;",
+        "wj" => "&#8288;",
+        "apos" => "&#39;",
+        "quot" => "&#34;",
+        "lsquo" => "&#8216;",
+        "rsquo" => "&#8217;",
+        "ldquo" => "&#8220;",
+        "rdquo" => "&#8221;",
+        "deg" => "&#176;",
+        "plus" => "&#43;",
+        "brvbar" => "&#166;",
+        "vbar" => "|",
+        "amp" => "&",
+        "lt" => "<",
+        "gt" => ">",
+        "startsb" => "[",
+        "endsb" => "]",
+        "caret" => "^",
+        "asterisk" => "*",
+        "tilde" => "~",
+        "backslash" => "\\",
+        "backtick" => "`",
+        "two-colons" => "::",
+        "two-semicolons" => ";;",
+        "cpp" => "C++",
+        _ => return None,
+    })
+}
+
+/// Replaces the `{name}` references with the value of the attribute, references to missing
+/// attributes are left as is and `\{name}` escapes a reference
+fn attribute_references(text: &str, attributes: &HashMap<String, String>) -> Option<String> {
+    let mut result = String::new();
+    let mut copied = 0;
+    let mut from = 0;
+    while let Some(found) = text[from..].find('{') {
+        let start = from + found;
+        from = start + 1;
+        let Some(len) = text[from..].find('}') else {
+            break;
+        };
+        let name = &text[from..from + len];
+        if name.is_empty() || !name.chars().all(|c| is_word(c) || c == '-') {
+            continue;
+        }
+        let end = from + len + 1;
+        if text[..start].ends_with('\\') {
+            result.push_str(&text[copied..start - 1]);
+            result.push_str(&text[start..end]);
+        } else if let Some(value) = attributes
+            .get(name)
+            .map(String::as_str)
+            .or_else(|| intrinsic_attribute(name))
+        {
+            result.push_str(&text[copied..start]);
+            result.push_str(value);
+        } else {
+            log::debug!("Skipping the reference to the missing attribute '{name}'");
+            continue;
+        }
+        copied = end;
+        from = end;
+    }
+    (copied > 0).then(|| result + &text[copied..])
+}
+
+/// Turns a ` +` at the end of a line into a line break
+fn post_replacements(text: &str) -> Option<String> {
+    if !text.lines().any(|line| line.ends_with(" +")) {
+        return None;
+    }
+    let lines: Vec<String> = text
+        .split('\n')
+        .map(|line| {
+            let (line, cr) = split_cr(line);
+            match line.strip_suffix(" +") {
+                Some(line) => format!("{line}<br>{cr}"),
+                None => format!("{line}{cr}"),
+            }
+        })
+        .collect();
+    Some(lines.join("\n"))
+}
+
+/// Ends every line but the last with a line break, unless it already has one
+fn hard_breaks(text: &str) -> Option<String> {
+    let (lines, last) = text.rsplit_once('\n')?;
+    let mut result = String::with_capacity(text.len());
+    for line in lines.split('\n') {
+        let (line, cr) = split_cr(line);
+        result.push_str(line);
+        if !line.ends_with("<br>") {
+            result.push_str("<br>");
+        }
+        result.push_str(cr);
+        result.push('\n');
+    }
+    Some(result + last)
+}
+
+/// `line` without the `\r` of a CRLF line ending, and that `\r`
+fn split_cr(line: &str) -> (&str, &str) {
+    match line.strip_suffix('\r') {
+        Some(line) => (line, "\r"),
+        None => (line, ""),
+    }
+}
+
+/// How the text enclosed by a pair of marks may be placed, see [`replace_enclosed`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pairing {
+    /// Anywhere, even inside a word
+    Unconstrained,
+    /// Around words, with no word character right outside the marks and no whitespace right
+    /// inside them
+    Constrained,
+    /// Around text without any whitespace
+    Unspaced,
+}
+
+/// Replaces each text enclosed by `open` and `close` with `f` of the text between them,
+/// `\` before `open` escapes the pair. Returns `None` if there is no pair
+fn replace_enclosed(
+    text: &str,
+    open: &str,
+    close: &str,
+    pairing: Pairing,
+    mut f: impl FnMut(&str) -> String,
+) -> Option<String> {
+    let mut result = String::new();
+    let mut copied = 0;
+    let mut from = 0;
+    while let Some(found) = text[from..].find(open) {
+        let start = from + found;
+        let inner = start + open.len();
+        from = start + open.chars().next().map_or(1, char::len_utf8);
+        let before = text[..start].chars().next_back();
+        if pairing == Pairing::Constrained
+            && before.is_some_and(|c| is_word(c) || ";:}".contains(c))
+        {
+            continue;
+        }
+        let Some(end) = find_close(text, inner, close, pairing) else {
+            continue;
+        };
+        if before == Some('\\') {
+            result.push_str(&text[copied..start - 1]);
+            result.push_str(&text[start..end + close.len()]);
+        } else {
+            result.push_str(&text[copied..start]);
+            result.push_str(&f(&text[inner..end]));
+        }
+        copied = end + close.len();
+        from = copied;
+    }
+    (copied > 0).then(|| result + &text[copied..])
+}
+
+/// Start of the `close` mark ending the text enclosed from `inner`
+fn find_close(text: &str, inner: usize, close: &str, pairing: Pairing) -> Option<usize> {
+    let first = text[inner..].chars().next()?;
+    if pairing != Pairing::Unconstrained && first.is_whitespace() {
+        return None;
+    }
+    let mut from = inner + first.len_utf8();
+    while let Some(found) = text[from..].find(close) {
+        let end = from + found;
+        let enclosed = &text[inner..end];
+        if pairing == Pairing::Unspaced && enclosed.contains(char::is_whitespace) {
+            return None;
+        }
+        let after = text[end + close.len()..].chars().next();
+        let constrained = !enclosed.ends_with(char::is_whitespace) && !after.is_some_and(is_word);
+        if pairing != Pairing::Constrained || constrained {
+            return Some(end);
+        }
+        from = end + close.len();
+    }
+    None
+}
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+
+use super::{Pairing, Substitution, escape_quotes, parse_subs, replace_enclosed, substitute};
+use crate::{
+    media::replace_inline_images,
+    xref::{scan_anchors, scan_xrefs},
+};
+
+/// Schemes of the URLs turned into links without a macro
+const SCHEMES: [&str; 5] = ["https://", "http://", "ftp://", "irc://", "mailto:"];
+
+/// Replaces the inline images, the inline macros and bare URLs with links, the cross
+/// references with links to the anchors and the inline anchors with the targets of those
+/// links
+pub(super) fn macros(text: &str, attributes: &HashMap<String, String>) -> Option<String> {
+    let mut result = replace_inline_images(text, attributes);
+    if let Some(linked) = links(result.as_deref().unwrap_or(text)) {
+        result = Some(linked);
+    }
+    references(result.as_deref().unwrap_or(text)).or(result)
+}
+
+fn links(text: &str) -> Option<String> {
+    let mut result = String::new();
+    let mut copied = 0;
+    let mut from = 0;
+    while let Some(start) = next_macro(text, from) {
+        from = start + 1;
+        let Some((len, link)) = link(&text[start..]) else {
+            continue;
+        };
+        if text[..start].ends_with('\\') {
+            result.push_str(&text[copied..start - 1]);
+            result.push_str(&text[start..start + len]);
+        } else {
+            result.push_str(&text[copied..start]);
+            result.push_str(&link);
+        }
+        copied = start + len;
+        from = copied;
+    }
+    (copied > 0).then(|| result + &text[copied..])
+}
+
+/// Replaces the cross references and inline anchors, the special characters are already
+/// replaced in the `<<id>>` form
+fn references(text: &str) -> Option<String> {
+    let mut found: Vec<_> = scan_xrefs(text, "&lt;&lt;", "&gt;&gt;")
+        .into_iter()
+        .map(|(range, xref)| {
+            let label = match (xref.text, xref.id) {
+                (Some(text), _) => text.replace("\\]", "]"),
+                (None, Some(id)) => format!("[{id}]"),
+                (None, None) => xref.href(),
+            };
+            let href = escape_quotes(&xref.href());
+            (range, format!("<a href=\"{href}\">{label}</a>"))
+        })
+        .chain(scan_anchors(text).into_iter().map(|(range, anchor)| {
+            let html = format!("<a id=\"{}\"></a>", anchor.id);
+            match (anchor.bibliography, anchor.reftext) {
+                (true, label) => (range, format!("{html}[{}]", label.unwrap_or(anchor.id))),
+                (false, _) => (range, html),
+            }
+        }))
+        .collect();
+    found.sort_by_key(|(range, _)| range.start);
+    let mut result = String::new();
+    let mut copied = 0;
+    for (range, html) in found {
+        if range.start < copied {
+            continue;
+        }
+        if text[..range.start].ends_with('\\') {
+            result.push_str(&text[copied..range.start - 1]);
+            result.push_str(&text[range.clone()]);
+        } else {
+            result.push_str(&text[copied..range.start]);
+            result.push_str(&html);
+        }
+        copied = range.end;
+    }
+    (copied > 0).then(|| result + &text[copied..])
+}
+
+/// Start of the next `link:` macro or URL from `from`, macros start after whitespace or the
+/// punctuation around them
+fn next_macro(text: &str, from: usize) -> Option<usize> {
+    text[from..]
+        .char_indices()
+        .map(|(i, _)| from + i)
+        .find(|start| {
+            ["link:"]
+                .iter()
+                .chain(&SCHEMES)
+                .any(|prefix| text[*start..].starts_with(prefix))
+                && text[..*start]
+                    .chars()
+                    .next_back()
+                    .is_none_or(|c| c.is_whitespace() || "<>()[];\\".contains(c))
+        })
+}
+
+/// Length and HTML of the link at the start of `text`
+fn link(text: &str) -> Option<(usize, String)> {
+    let (target, rest) = match text.strip_prefix("link:") {
+        Some(rest) => {
+            let target_len = rest.find(|c: char| c == '[' || c.is_whitespace())?;
+            (&rest[..target_len], &rest[target_len..])
+        }
+        None => {
+            let target_len = text
+                .find(|c: char| c == '[' || c.is_whitespace())
+                .unwrap_or(text.len());
+            (&text[..target_len], &text[target_len..])
+        }
+    };
+    let prefix_len = text.len() - target.len() - rest.len();
+    if let Some(attributes) = rest.strip_prefix('[') {
+        let attributes_len = attributes.find(']')?;
+        let label = &attributes[..attributes_len];
+        let label = if label.is_empty() {
+            target.strip_prefix("mailto:").unwrap_or(target)
+        } else {
+            label
+        };
+        let end = prefix_len + target.len() + attributes_len + 2;
+        let href = escape_quotes(target);
+        return Some((end, format!("<a href=\"{href}\">{label}</a>")));
+    }
+    if target.starts_with("mailto:") || text.starts_with("link:") {
+        return None;
+    }
+    // Punctuation ending a sentence isn't part of a bare URL
+    let target = target.trim_end_matches(['.', ',', ';', ':', '!', '?', ')']);
+    SCHEMES
+        .iter()
+        .any(|scheme| target.len() > scheme.len() && target.starts_with(scheme))
+        .then(|| {
+            let href = escape_quotes(target);
+            let link = format!("<a href=\"{href}\" class=\"bare\">{target}</a>");
+            (target.len(), link)
+        })
+}
+
+/// Start and end of the placeholder of a passthrough, around its index
+const PLACEHOLDER: (char, char) = ('\u{96}', '\u{97}');
+
+/// Replaces the passthroughs with placeholders, pushing their text with its own
+/// substitutions already applied to `passthroughs`
+pub(super) fn extract_passthroughs(
+    text: &str,
+    passthroughs: &mut Vec<String>,
+    attributes: &HashMap<String, String>,
+) -> Option<String> {
+    let mut placeholder = |passthrough: String| {
+        passthroughs.push(passthrough);
+        format!(
+            "{}{}{}",
+            PLACEHOLDER.0,
+            passthroughs.len() - 1,
+            PLACEHOLDER.1
+        )
+    };
+    let mut result = pass_macros(text, attributes, &mut placeholder);
+    // `+++` passes the text as is, `++` and `+` only escape the special characters
+    let passes: [(&str, Pairing, &[Substitution]); 3] = [
+        ("+++", Pairing::Unconstrained, &[]),
+        ("++", Pairing::Unconstrained, &Substitution::VERBATIM),
+        ("+", Pairing::Constrained, &Substitution::VERBATIM),
+    ];
+    for (mark, pairing, subs) in passes {
+        let current = result.as_deref().unwrap_or(text);
+        if let Some(extracted) = replace_enclosed(current, mark, mark, pairing, |inner| {
+            placeholder(substitute(inner.into(), subs, attributes).into_owned())
+        }) {
+            result = Some(extracted);
+        }
+    }
+    result
+}
+
+/// Replaces the `pass:subs[text]` macros using `placeholder`
+fn pass_macros(
+    text: &str,
+    attributes: &HashMap<String, String>,
+    placeholder: &mut impl FnMut(String) -> String,
+) -> Option<String> {
+    let mut result = String::new();
+    let mut copied = 0;
+    let mut from = 0;
+    while let Some(found) = text[from..].find("pass:") {
+        let start = from + found;
+        from = start + "pass:".len();
+        let Some(open) = text[from..].find('[').map(|i| from + i) else {
+            break;
+        };
+        let subs = &text[from..open];
+        if !subs
+            .chars()
+            .all(|c| c.is_ascii_alphabetic() || c == ',' || c == '_')
+        {
+            continue;
+        }
+        let Some(close) = closing_bracket(&text[open + 1..]).map(|i| open + 1 + i) else {
+            continue;
+        };
+        let content = text[open + 1..close].replace("\\]", "]");
+        if text[..start].ends_with('\\') {
+            result.push_str(&text[copied..start - 1]);
+            result.push_str(&text[start..=close]);
+        } else {
+            result.push_str(&text[copied..start]);
+            let subs = parse_subs(subs, &[]);
+            result.push_str(&placeholder(
+                substitute(content.into(), &subs, attributes).into_owned(),
+            ));
+        }
+        copied = close + 1;
+        from = copied;
+    }
+    (copied > 0).then(|| result + &text[copied..])
+}
+
+/// Index of the first `]` not escaped by `\`
+pub(crate) fn closing_bracket(text: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            ']' if !escaped => return Some(i),
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+/// Puts the text of the passthroughs back in place of their placeholders
+pub(super) fn restore_passthroughs(text: &str, passthroughs: &[String]) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(PLACEHOLDER.0) {
+        result.push_str(&rest[..start]);
+        rest = &rest[start + PLACEHOLDER.0.len_utf8()..];
+        let end = rest.find(PLACEHOLDER.1).unwrap_or(rest.len());
+        match rest[..end]
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| passthroughs.get(i))
+        {
+            Some(passthrough) => result.push_str(passthrough),
+            None => result.push_str(&rest[..end]),
+        }
+        rest = rest
+            .get(end + PLACEHOLDER.1.len_utf8()..)
+            .unwrap_or_default();
+    }
+    result + rest
+}
@@ -0,0 +1,35 @@
+use super::{Pairing, replace_enclosed};
+
+/// Marks of the inline formatting, in the order they are replaced, with the HTML enclosing
+/// the formatted text
+const QUOTES: [(&str, &str, Pairing, &str, &str); 12] = [
+    ("**", "**", Pairing::Unconstrained, "<strong>", "</strong>"),
+    ("*", "*", Pairing::Constrained, "<strong>", "</strong>"),
+    ("\"`", "`\"", Pairing::Constrained, "&#8220;", "&#8221;"),
+    ("'`", "`'", Pairing::Constrained, "&#8216;", "&#8217;"),
+    ("``", "``", Pairing::Unconstrained, "<code>", "</code>"),
+    ("`", "`", Pairing::Constrained, "<code>", "</code>"),
+    ("__", "__", Pairing::Unconstrained, "<em>", "</em>"),
+    ("_", "_", Pairing::Constrained, "<em>", "</em>"),
+    ("##", "##", Pairing::Unconstrained, "<mark>", "</mark>"),
+    ("#", "#", Pairing::Constrained, "<mark>", "</mark>"),
+    ("^", "^", Pairing::Unspaced, "<sup>", "</sup>"),
+    ("~", "~", Pairing::Unspaced, "<sub>", "</sub>"),
+];
+
+/// Replaces the formatting marks with HTML tags
+pub(super) fn quotes(text: &str) -> Option<String> {
+    let mut result = None;
+    for (open, close, pairing, start_tag, end_tag) in QUOTES {
+        let current = result.as_deref().unwrap_or(text);
+        if !current.contains(open) {
+            continue;
+        }
+        if let Some(replaced) = replace_enclosed(current, open, close, pairing, |inner| {
+            format!("{start_tag}{inner}{end_tag}")
+        }) {
+            result = Some(replaced);
+        }
+    }
+    result
+}
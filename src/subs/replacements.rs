@@ -0,0 +1,144 @@
+use std::borrow::Cow;
+
+use crate::ast::{CharacterReference, Inline, Replacement};
+
+use super::is_word;
+
+/// Replacements found wherever their characters are, as written in the source and once the
+/// special characters are escaped
+const REPLACEMENTS: [(&str, &str, Replacement); 8] = [
+    ("(C)", "(C)", Replacement::Copyright),
+    ("(R)", "(R)", Replacement::Registered),
+    ("(TM)", "(TM)", Replacement::Trademark),
+    ("...", "...", Replacement::Ellipsis),
+    ("->", "-&gt;", Replacement::RightArrow),
+    ("=>", "=&gt;", Replacement::RightDoubleArrow),
+    ("<-", "&lt;-", Replacement::LeftArrow),
+    ("<=", "&lt;=", Replacement::LeftDoubleArrow),
+];
+
+/// Replaces the typographic marks with the HTML character references of their symbols,
+/// keeping the character references of the source, see [`parse`] for `escaped`
+pub(super) fn replacements(text: &str, escaped: bool) -> Option<String> {
+    let inlines = parse(text, escaped);
+    if let [Inline::Text(_)] | [] = &inlines[..] {
+        return None;
+    }
+    let mut result = String::with_capacity(text.len());
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => result.push_str(&text),
+            Inline::Replacement(replacement) => result.push_str(replacement.entity()),
+            Inline::CharacterReference(reference) => result.push_str(&reference.entity()),
+            // Line breaks are left to the post replacements
+            Inline::HardBreak => result.push_str(" +"),
+        }
+    }
+    Some(result)
+}
+
+/// Splits `text` into the replacements, character references and hard line breaks it
+/// contains and the text around them. With `escaped`, the special characters of `text` are
+/// escaped, so only the references escaped along with them are from the source
+pub(super) fn parse(text: &str, escaped: bool) -> Vec<Inline<'_>> {
+    let mut inlines = Vec::new();
+    let mut copied = 0;
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        let Some((start, end, inline)) = inline_at(text, i, escaped) else {
+            i += c.len_utf8();
+            continue;
+        };
+        if text[..i].ends_with('\\') {
+            push_text(&mut inlines, &text[copied..i - 1]);
+            copied = i;
+        } else {
+            push_text(&mut inlines, &text[copied..start]);
+            inlines.push(inline);
+            copied = end;
+        }
+        i = end;
+    }
+    push_text(&mut inlines, &text[copied..]);
+    inlines
+}
+
+fn push_text<'a>(inlines: &mut Vec<Inline<'a>>, text: &'a str) {
+    if text.is_empty() {
+        return;
+    }
+    match inlines.last_mut() {
+        Some(Inline::Text(last)) => last.to_mut().push_str(text),
+        _ => inlines.push(Inline::Text(text.into())),
+    }
+}
+
+/// Range and node of the replacement or character reference at `i`
+fn inline_at(text: &str, i: usize, escaped: bool) -> Option<(usize, usize, Inline<'_>)> {
+    let rest = &text[i..];
+    let before = text[..i].chars().next_back();
+    if rest.starts_with(" +")
+        && (rest.len() == 2 || rest[2..].starts_with('\n') || rest[2..].starts_with("\r\n"))
+    {
+        return Some((i, i + 2, Inline::HardBreak));
+    }
+    if let Some(after_dash) = rest.strip_prefix("--") {
+        let after = after_dash.chars().next();
+        // An em dash set off by spaces takes their place
+        let spaced = |c: Option<char>| c.is_none_or(|c| c == ' ' || c == '\n');
+        if (spaced(before) || before == Some('\\')) && spaced(after) {
+            let start = if before == Some(' ') { i - 1 } else { i };
+            let end = if after == Some(' ') { i + 3 } else { i + 2 };
+            let dash = Replacement::EmDash { spaced: true };
+            return Some((start, end, Inline::Replacement(dash)));
+        }
+        if before.is_some_and(is_word) && after.is_some_and(is_word) {
+            let dash = Replacement::EmDash { spaced: false };
+            return Some((i, i + 2, Inline::Replacement(dash)));
+        }
+    }
+    if rest.starts_with('\'')
+        && before.is_some_and(char::is_alphanumeric)
+        && rest[1..].starts_with(char::is_alphabetic)
+    {
+        return Some((i, i + 1, Inline::Replacement(Replacement::Apostrophe)));
+    }
+    if let Some((len, replacement)) = REPLACEMENTS.iter().find_map(|(raw, esc, replacement)| {
+        let pattern = if escaped { esc } else { raw };
+        rest.starts_with(pattern)
+            .then_some((pattern.len(), *replacement))
+    }) {
+        return Some((i, i + len, Inline::Replacement(replacement)));
+    }
+    let ampersand = if escaped { "&amp;" } else { "&" };
+    let name = rest.strip_prefix(ampersand)?;
+    let len = name.find(';')?;
+    let reference = character_reference(&name[..len])?;
+    Some((i, i + ampersand.len() + len + 1, reference))
+}
+
+/// Reference to the character named by `name`, which is a name of letters ending with at
+/// most two digits, or a decimal or `x` prefixed hexadecimal code point
+fn character_reference(name: &str) -> Option<Inline<'_>> {
+    let reference = if let Some(hex) = name.strip_prefix("#x") {
+        if !(2..=5).contains(&hex.len()) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        CharacterReference::Hexadecimal(u32::from_str_radix(hex, 16).ok()?)
+    } else if let Some(decimal) = name.strip_prefix('#') {
+        if !(2..=6).contains(&decimal.len()) || !decimal.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        CharacterReference::Decimal(decimal.parse().ok()?)
+    } else {
+        let letters = name.trim_end_matches(|c: char| c.is_ascii_digit());
+        if letters.len() < 2
+            || name.len() - letters.len() > 2
+            || !letters.chars().all(|c| c.is_ascii_alphabetic())
+        {
+            return None;
+        }
+        CharacterReference::Named(Cow::Borrowed(name))
+    };
+    Some(Inline::CharacterReference(reference))
+}
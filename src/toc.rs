@@ -0,0 +1,180 @@
+//! Table of contents of a document, built from its sections
+
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    ast::{Block, BlockContent, Document, DocumentHeader, SectionContent, TocPlacement},
+    subs::{escape_html, plain_text},
+};
+
+/// Table of contents, set up by the `toc`, `toclevels` and `toc-title` attributes
+///
+/// With [`TocPlacement::Macro`] it goes where the `toc::[]` block macro is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toc {
+    pub title: String,
+    /// `None` when the document has no `toc` attribute, the outline can still be rendered
+    pub placement: Option<TocPlacement>,
+    /// Deepest section level listed, `toclevels`
+    pub levels: usize,
+    pub entries: Vec<TocEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocEntry {
+    /// Level of the section, 0 for a part and 1 for a chapter or a top level section
+    pub level: usize,
+    pub id: Option<String>,
+    pub number: Option<String>,
+    /// Title without its inline markup
+    pub title: String,
+    pub entries: Vec<TocEntry>,
+}
+
+impl Toc {
+    /// Table of contents of the sections of `document`, which need their ids and numbers from
+    /// [`crate::sections::assign`] like every parser gives them
+    pub fn new(document: &Document<'_>) -> Self {
+        let header = document.header.as_ref();
+        let attributes = header
+            .map(DocumentHeader::attribute_values)
+            .unwrap_or_default();
+        let levels = attributes
+            .get("toclevels")
+            .and_then(|levels| levels.parse().ok())
+            .unwrap_or(2);
+        Self {
+            title: attributes
+                .get("toc-title")
+                .map_or("Table of Contents", String::as_str)
+                .to_string(),
+            placement: header.and_then(DocumentHeader::toc),
+            levels,
+            entries: entries(&document.content.blocks, levels, &attributes),
+        }
+    }
+
+    /// Whether the table goes in place of `block`, the `toc::[]` block macro when the placement
+    /// is [`TocPlacement::Macro`]
+    pub fn is_placed_at(&self, block: &Block<'_>) -> bool {
+        self.placement == Some(TocPlacement::Macro)
+            && matches!(&block.content, BlockContent::Macro(m) if m.name == "toc")
+    }
+
+    /// `<div id="toc">` with nested lists of links to the sections
+    pub fn to_html(&self) -> String {
+        let class = match self.placement {
+            Some(TocPlacement::Left | TocPlacement::Right) => "toc2",
+            _ => "toc",
+        };
+        let mut html = format!(
+            "<div id=\"toc\" class=\"{class}\">\n<div id=\"toctitle\">{}</div>\n",
+            escape_html(&self.title)
+        );
+        html_list(&self.entries, &mut html);
+        html.push_str("</div>\n");
+        html
+    }
+
+    /// DocBook processors generate the table of contents themselves, it is only requested
+    pub fn to_docbook(&self) -> String {
+        "<?asciidoc-toc?>\n".to_string()
+    }
+
+    /// Nested list of links to the sections
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        for (depth, entry) in self.iter() {
+            let indent = "  ".repeat(depth);
+            let text = entry.text().replace(['[', ']'], "");
+            match &entry.id {
+                Some(id) => markdown.push_str(&format!("{indent}- [{text}](#{id})\n")),
+                None => markdown.push_str(&format!("{indent}- {text}\n")),
+            }
+        }
+        markdown
+    }
+
+    /// Every entry in order, with its depth in the table
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &TocEntry)> {
+        let mut stack: Vec<_> = self.entries.iter().rev().map(|e| (0, e)).collect();
+        std::iter::from_fn(move || {
+            let (depth, entry) = stack.pop()?;
+            stack.extend(entry.entries.iter().rev().map(|e| (depth + 1, e)));
+            Some((depth, entry))
+        })
+    }
+}
+
+impl TocEntry {
+    /// Title preceded by the number of the section
+    pub fn text(&self) -> String {
+        match &self.number {
+            Some(number) => format!("{number}. {}", self.title),
+            None => self.title.clone(),
+        }
+    }
+}
+
+/// Outline of the titles, indented by their depth
+impl fmt::Display for Toc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (depth, entry) in self.iter() {
+            writeln!(f, "{}{}", "  ".repeat(depth), entry.text())?;
+        }
+        Ok(())
+    }
+}
+
+fn entries<'b, 'a: 'b>(
+    blocks: impl IntoIterator<Item = &'b Block<'a>>,
+    levels: usize,
+    attributes: &HashMap<String, String>,
+) -> Vec<TocEntry> {
+    let mut entries = Vec::new();
+    for block in blocks {
+        let (Some(heading), BlockContent::Section(content)) = (&block.heading, &block.content)
+        else {
+            continue;
+        };
+        let level = heading.level.saturating_sub(1);
+        if level > levels {
+            continue;
+        }
+        let children: Vec<_> = content
+            .iter()
+            .filter_map(|c| match c {
+                SectionContent::Block(block) => Some(block),
+                SectionContent::Text(_) => None,
+            })
+            .collect();
+        entries.push(TocEntry {
+            level,
+            id: heading.id.as_deref().map(str::to_string),
+            number: heading.number.clone(),
+            title: plain_text(&heading.text, attributes),
+            entries: self::entries(children, levels, attributes),
+        });
+    }
+    entries
+}
+
+fn html_list(entries: &[TocEntry], html: &mut String) {
+    let Some(first) = entries.first() else {
+        return;
+    };
+    html.push_str(&format!("<ul class=\"sectlevel{}\">\n", first.level));
+    for entry in entries {
+        let text = escape_html(&entry.text());
+        match &entry.id {
+            Some(id) => html.push_str(&format!("<li><a href=\"#{}\">{text}</a>", escape_html(id))),
+            None => html.push_str(&format!("<li>{text}")),
+        }
+        if !entry.entries.is_empty() {
+            html.push('\n');
+            html_list(&entry.entries, html);
+        }
+        html.push_str("</li>\n");
+    }
+    html.push_str("</ul>\n");
+}
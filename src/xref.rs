@@ -0,0 +1,450 @@
+//! Cross references between the anchors of a document and of other documents
+
+use std::{borrow::Cow, collections::HashMap, ops::Range};
+
+use crate::{
+    ast::{
+        Block, BlockContent, Document,
+        visit::{Visitor, walk_block, walk_block_content},
+        visit_mut::{VisitorMut, walk_block_content_mut},
+    },
+    cst::{SyntaxElement, SyntaxKind, SyntaxNode, TextRange},
+    diagnostics::{attribute_entries, texts},
+    parser::body::is_valid_id,
+    sections::SectionIds,
+    subs::macros::closing_bracket,
+};
+
+/// `<<target,text>>` or `xref:target[text]` reference
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Xref<'t> {
+    /// Document the reference points to, `None` within the same document
+    pub path: Option<&'t str>,
+    /// `None` for a reference to a whole document
+    pub id: Option<&'t str>,
+    /// `None` when omitted, the text of the target is used instead
+    pub text: Option<&'t str>,
+}
+
+impl<'t> Xref<'t> {
+    /// Reference to `target`, an id, a document like `other.adoc` or an id in one like
+    /// `other.adoc#id`
+    pub fn new(target: &'t str, text: Option<&'t str>) -> Self {
+        let (path, id) = match target.split_once('#') {
+            Some((path, id)) => (Some(path), Some(id)),
+            None if target.ends_with(".adoc") => (Some(target), None),
+            None => (None, Some(target)),
+        };
+        Self {
+            path: path.filter(|p| !p.is_empty()),
+            id: id.filter(|id| !id.is_empty()),
+            text: text.filter(|t| !t.is_empty()),
+        }
+    }
+
+    /// Link to the target, other documents are linked by their converted `.html` file
+    pub fn href(&self) -> String {
+        let path = self
+            .path
+            .map(|path| format!("{}.html", path.strip_suffix(".adoc").unwrap_or(path)))
+            .unwrap_or_default();
+        match self.id {
+            Some(id) => format!("{path}#{id}"),
+            None => path,
+        }
+    }
+}
+
+/// `[[id,reftext]]` or `anchor:id[reftext]` anchor, or `[[[id,label]]]` entry of a
+/// bibliography
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineAnchor<'t> {
+    pub id: &'t str,
+    /// Text of the references to the anchor, the label of a bibliography entry
+    pub reftext: Option<&'t str>,
+    pub bibliography: bool,
+}
+
+/// Byte range and reference of the cross references in `text`, `\` escapes them
+pub fn xrefs(text: &str) -> Vec<(Range<usize>, Xref<'_>)> {
+    scan_xrefs(text, "<<", ">>")
+        .into_iter()
+        .filter(|(range, _)| !text[..range.start].ends_with('\\'))
+        .collect()
+}
+
+/// Cross references with the `<<` and `>>` of the shorthand form written as `open` and
+/// `close`, escaped ones included
+pub(crate) fn scan_xrefs<'t>(
+    text: &'t str,
+    open: &str,
+    close: &str,
+) -> Vec<(Range<usize>, Xref<'t>)> {
+    let mut xrefs = Vec::new();
+    let mut end = 0;
+    for (start, _) in text.char_indices() {
+        if start < end {
+            continue;
+        }
+        let found = if let Some(rest) = text[start..].strip_prefix(open) {
+            rest.find(close).and_then(|len| {
+                let (target, label) = match rest[..len].split_once(',') {
+                    Some((target, label)) => (target, Some(label.trim())),
+                    None => (&rest[..len], None),
+                };
+                is_target(target)
+                    .then(|| (open.len() + len + close.len(), Xref::new(target, label)))
+            })
+        } else if let Some(rest) = text[start..].strip_prefix("xref:")
+            && text[..start]
+                .chars()
+                .next_back()
+                .is_none_or(|c| c.is_whitespace() || "()[];\\".contains(c))
+        {
+            rest.find('[').and_then(|target_len| {
+                let target = &rest[..target_len];
+                let label_len = closing_bracket(&rest[target_len + 1..])?;
+                let label = &rest[target_len + 1..target_len + 1 + label_len];
+                is_target(target).then(|| {
+                    (
+                        5 + target_len + label_len + 2,
+                        Xref::new(target, Some(label)),
+                    )
+                })
+            })
+        } else {
+            None
+        };
+        if let Some((len, xref)) = found {
+            end = start + len;
+            xrefs.push((start..end, xref));
+        }
+    }
+    xrefs
+}
+
+fn is_target(target: &str) -> bool {
+    !target.is_empty() && !target.contains(char::is_whitespace)
+}
+
+/// Byte range and anchor of the inline anchors in `text`, `\` escapes them
+pub fn inline_anchors(text: &str) -> Vec<(Range<usize>, InlineAnchor<'_>)> {
+    scan_anchors(text)
+        .into_iter()
+        .filter(|(range, _)| !text[..range.start].ends_with('\\'))
+        .collect()
+}
+
+/// Inline anchors in `text`, escaped ones included
+pub(crate) fn scan_anchors(text: &str) -> Vec<(Range<usize>, InlineAnchor<'_>)> {
+    let mut anchors = Vec::new();
+    let mut end = 0;
+    for (start, _) in text.char_indices() {
+        if start < end {
+            continue;
+        }
+        let rest = &text[start..];
+        let found = if let Some(inner) = rest.strip_prefix("[[[") {
+            inner.find("]]]").map(|len| (len + 6, &inner[..len], true))
+        } else if let Some(inner) = rest.strip_prefix("[[") {
+            inner.find("]]").map(|len| (len + 4, &inner[..len], false))
+        } else if let Some(inner) = rest.strip_prefix("anchor:") {
+            inner.find('[').and_then(|id_len| {
+                let len = closing_bracket(&inner[id_len + 1..])?;
+                let inner = &inner[..id_len + 1 + len];
+                Some((7 + inner.len() + 1, inner, false))
+            })
+        } else {
+            None
+        };
+        let Some((len, inner, bibliography)) = found else {
+            continue;
+        };
+        let (id, reftext) = match inner.split_once([',', '[']) {
+            Some((id, reftext)) => (id, Some(reftext.trim()).filter(|r| !r.is_empty())),
+            None => (inner, None),
+        };
+        if is_valid_id(id) {
+            end = start + len;
+            anchors.push((
+                start..end,
+                InlineAnchor {
+                    id,
+                    reftext,
+                    bibliography,
+                },
+            ));
+        }
+    }
+    anchors
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnchorKind {
+    Section,
+    Block,
+    Inline,
+    Bibliography,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Anchor {
+    pub kind: AnchorKind,
+    /// Text of the references to the anchor without their own, from the `reftext`
+    /// attribute, the title of the section or block, or the label of a bibliography entry
+    pub reftext: Option<String>,
+}
+
+/// Anchors of a document by id, the first definition of an id is the one referenced
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Catalog {
+    /// Title of the document, the text of the references to the whole document
+    pub title: Option<String>,
+    pub anchors: HashMap<String, Anchor>,
+    /// Ids defined again after their first definition, in order
+    pub duplicates: Vec<String>,
+}
+
+impl Catalog {
+    /// Catalog of the sections, blocks and inline anchors of `document`, which needs the ids
+    /// of its sections from [`crate::sections::assign`] like every parser gives them
+    pub fn new(document: &Document<'_>) -> Self {
+        let mut catalog = Self {
+            title: document
+                .header
+                .as_ref()
+                .and_then(|h| h.title.as_ref())
+                .map(|title| title.text.to_string()),
+            ..Self::default()
+        };
+        for block in &document.content.blocks {
+            catalog.visit_block(block);
+        }
+        catalog
+    }
+
+    /// Text of a reference to `xref` without its own, `None` when the target has none or
+    /// is not in the catalog
+    pub fn reftext(&self, xref: &Xref<'_>) -> Option<&str> {
+        match xref.id {
+            Some(id) => self.anchors.get(id)?.reftext.as_deref(),
+            None => self.title.as_deref(),
+        }
+    }
+
+    fn insert(&mut self, id: &str, kind: AnchorKind, reftext: Option<String>) {
+        if self.anchors.contains_key(id) {
+            self.duplicates.push(id.to_string());
+        } else {
+            self.anchors
+                .insert(id.to_string(), Anchor { kind, reftext });
+        }
+    }
+}
+
+impl Visitor for Catalog {
+    fn visit_block(&mut self, block: &Block<'_>) {
+        let reftext = block
+            .attributes
+            .iter()
+            .rfind(|a| a.key == "reftext")
+            .and_then(|a| a.value.as_deref());
+        match &block.heading {
+            Some(heading) => {
+                if let Some(id) = &heading.id {
+                    let reftext = reftext.unwrap_or(&heading.text);
+                    self.insert(id, AnchorKind::Section, Some(reftext.to_string()));
+                }
+            }
+            None => {
+                if let Some(id) = block.id() {
+                    let reftext = reftext.or(block.title.as_deref());
+                    self.insert(id, AnchorKind::Block, reftext.map(str::to_string));
+                }
+            }
+        }
+        walk_block(self, block);
+    }
+
+    fn visit_block_content(&mut self, content: &BlockContent<'_>) {
+        if !is_verbatim(content) {
+            walk_block_content(self, content);
+        }
+    }
+
+    fn visit_text(&mut self, text: &str) {
+        for (_, anchor) in inline_anchors(text) {
+            if anchor.bibliography {
+                let label = anchor.reftext.unwrap_or(anchor.id);
+                self.insert(
+                    anchor.id,
+                    AnchorKind::Bibliography,
+                    Some(format!("[{label}]")),
+                );
+            } else {
+                let reftext = anchor.reftext.map(str::to_string);
+                self.insert(anchor.id, AnchorKind::Inline, reftext);
+            }
+        }
+    }
+}
+
+fn is_verbatim(content: &BlockContent<'_>) -> bool {
+    matches!(content, BlockContent::Delimited(kind, _) if kind.is_verbatim())
+}
+
+/// Fills in the text of the cross references that omit it, from the [`Catalog`] of their
+/// target document, before the substitutions turn them into links
+pub struct XrefResolver<'c> {
+    catalog: &'c Catalog,
+    documents: HashMap<&'c str, &'c Catalog>,
+}
+
+impl<'c> XrefResolver<'c> {
+    /// Resolver of the references within the document of `catalog`
+    pub fn new(catalog: &'c Catalog) -> Self {
+        Self {
+            catalog,
+            documents: HashMap::new(),
+        }
+    }
+
+    /// Also resolves the references to the document at `path`, as written in the references
+    pub fn with_document(mut self, path: &'c str, catalog: &'c Catalog) -> Self {
+        self.documents.insert(path, catalog);
+        self
+    }
+
+    fn reftext(&self, xref: &Xref<'_>) -> Option<&'c str> {
+        let catalog = match xref.path {
+            Some(path) => self.documents.get(path)?,
+            None => self.catalog,
+        };
+        catalog.reftext(xref)
+    }
+}
+
+impl VisitorMut for XrefResolver<'_> {
+    fn visit_block_content_mut(&mut self, content: &mut BlockContent<'_>) {
+        if !is_verbatim(content) {
+            walk_block_content_mut(self, content);
+        }
+    }
+
+    fn visit_text_mut(&mut self, text: &mut Cow<'_, str>) {
+        let mut resolved = text.to_string();
+        let mut changed = false;
+        for (range, xref) in xrefs(text).into_iter().rev() {
+            let Some(reftext) = xref.text.is_none().then(|| self.reftext(&xref)).flatten() else {
+                continue;
+            };
+            // The text goes in the `[]` of the macro form, after a comma before the `>>` otherwise
+            if text[range.clone()].ends_with(']') {
+                resolved.insert_str(range.end - 1, &reftext.replace(']', "\\]"));
+            } else {
+                resolved.insert_str(range.end - 2, &format!(",{reftext}"));
+            }
+            changed = true;
+        }
+        if changed {
+            *text = Cow::Owned(resolved);
+        }
+    }
+}
+
+/// Ids of the anchors of a parsed document, explicit or generated for the sections, with the
+/// range of their definition in the order of the document
+pub fn anchors(root: &SyntaxNode) -> Vec<(String, TextRange)> {
+    let mut anchors = Vec::new();
+    for (node, range) in root.descendants() {
+        anchors.extend(explicit_id(node).map(|id| (id, range)));
+    }
+    for (text, offset) in texts(root) {
+        for (range, anchor) in inline_anchors(text) {
+            let start = offset + range.start + text[range].find(anchor.id).unwrap_or_default();
+            anchors.push((
+                anchor.id.to_string(),
+                TextRange::new(start, start + anchor.id.len()),
+            ));
+        }
+    }
+    anchors.extend(section_ids(root, &anchors));
+    anchors.sort_by_key(|(_, range)| range.start);
+    anchors
+}
+
+/// Cross references of a parsed document with their range, the ones in verbatim blocks are
+/// only text
+pub fn references(root: &SyntaxNode) -> Vec<(Xref<'_>, TextRange)> {
+    texts(root)
+        .into_iter()
+        .flat_map(|(text, offset)| {
+            xrefs(text).into_iter().map(move |(range, xref)| {
+                (
+                    xref,
+                    TextRange::new(offset + range.start, offset + range.end),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Id defined by an `[[id]]` anchor, an `id` attribute or the `#id` shorthand of a style
+fn explicit_id(node: &SyntaxNode) -> Option<String> {
+    match node.kind {
+        SyntaxKind::BlockAnchor => Some(node.token_text(SyntaxKind::AnchorId)),
+        SyntaxKind::Attribute => {
+            let name = node.token_text(SyntaxKind::AttributeName);
+            let value = node.token_text(SyntaxKind::AttributeValue);
+            let id = if name == "id" {
+                Some(value.trim_matches('"').to_string())
+            } else {
+                // Shorthand syntax, e.g. `[#id]` or `[quote#id.role]`
+                name.split_once('#')
+                    .map(|(_, id)| id.split(['.', '%']).next().unwrap_or_default().to_string())
+            };
+            id.filter(|id| is_valid_id(id))
+        }
+        _ => None,
+    }
+}
+
+/// Ids generated for the sections without an explicit one, with the range of their heading
+fn section_ids(root: &SyntaxNode, explicit: &[(String, TextRange)]) -> Vec<(String, TextRange)> {
+    let entries = attribute_entries(root);
+    if entries.get("sectids").is_some_and(Option::is_none) {
+        return Vec::new();
+    }
+    let attributes = entries
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect();
+    let mut ids = SectionIds::new(attributes);
+    for (id, _) in explicit {
+        ids.register(id);
+    }
+    let mut generated = Vec::new();
+    for (node, range) in root.descendants() {
+        if node.kind != SyntaxKind::Section
+            || node.child_nodes().any(|n| {
+                explicit_id(n).is_some()
+                    || n.kind == SyntaxKind::AttributeList
+                        && n.child_nodes().any(|a| explicit_id(a).is_some())
+            })
+        {
+            continue;
+        }
+        let mut offset = range.start;
+        for child in node.children.iter() {
+            if let SyntaxElement::Node(heading) = child
+                && heading.kind == SyntaxKind::Heading
+            {
+                let id = ids.generate(&heading.token_text(SyntaxKind::Text));
+                generated.push((id, TextRange::new(offset, offset + heading.text_len())));
+                break;
+            }
+            offset += child.text_len();
+        }
+    }
+    generated
+}
@@ -0,0 +1,125 @@
+use rascii::{
+    ast::{Author, Document, Email},
+    checkpoint_iterator::CheckpointIterator,
+    parser::traits::DocParser,
+};
+
+fn parse(text: &str) -> Document<'static> {
+    CheckpointIterator::new(text.chars())
+        .parse_document()
+        .unwrap()
+}
+
+fn authors(text: &str) -> Vec<Author<'static>> {
+    parse(text).header.map(|h| h.authors).unwrap_or_default()
+}
+
+fn author<'a>(
+    first_name: &'a str,
+    middle_name: Option<&'a str>,
+    last_name: Option<&'a str>,
+    email: Option<&'a str>,
+) -> Author<'a> {
+    Author {
+        first_name: first_name.into(),
+        middle_name: middle_name.map(Into::into),
+        last_name: last_name.map(Into::into),
+        email: email.map(|email| Email::parse(email).unwrap()),
+    }
+}
+
+#[test]
+fn author_names() {
+    assert_eq!(
+        authors(
+            "= Title\nPrince; Jane  Doe <jane.doe+docs@example.org>; Jean-Luc O'Brien Picard;\
+             Mary_Sue van_der_Berg; J. R. R. Tolkien; Zoë Ångström\n"
+        ),
+        [
+            author("Prince", None, None, None),
+            author("Jane", None, Some("Doe"), Some("jane.doe+docs@example.org")),
+            author("Jean-Luc", Some("O'Brien"), Some("Picard"), None),
+            author("Mary Sue", None, Some("van der Berg"), None),
+            author("J. R. R. Tolkien", None, None, None),
+            author("Zoë", None, Some("Ångström"), None),
+        ]
+    );
+    // Authors after an invalid email are ignored
+    assert_eq!(
+        authors("= Title\nJane Doe; John Doe <not an email>; Jim Doe\n"),
+        [author("Jane", None, Some("Doe"), None)]
+    );
+    assert_eq!(
+        authors("= Title\n-- <a@b.c>\n"),
+        [author("--", None, None, Some("a@b.c"))]
+    );
+}
+
+#[test]
+fn emails() {
+    let email = Email::parse("a.b+c@mail.example.co.uk").unwrap();
+    assert_eq!(email.local_part, "a.b+c");
+    assert_eq!(email.domain, "mail.example.co.uk");
+    assert_eq!(email.to_string(), "a.b+c@mail.example.co.uk");
+    assert!(Email::parse("root@localhost").is_some());
+    for invalid in [
+        "@example.org",
+        "a..b@example.org",
+        "a.@example.org",
+        "a@",
+        "a@-x.org",
+        "a@x..org",
+        "a b@x.org",
+        "https://example.org",
+    ] {
+        assert_eq!(Email::parse(invalid), None, "{invalid}");
+    }
+}
+
+#[test]
+fn attribute_authors() {
+    assert_eq!(
+        authors("= Title\n:author: Jane Q Doe\n:email: jane@example.org\n"),
+        [author(
+            "Jane",
+            Some("Q"),
+            Some("Doe"),
+            Some("jane@example.org")
+        )]
+    );
+    assert_eq!(
+        authors(":authors: Jane Doe <jane@example.org>; John Doe\n:email: ignored@example.org\n"),
+        [
+            author("Jane", None, Some("Doe"), Some("jane@example.org")),
+            author("John", None, Some("Doe"), None),
+        ]
+    );
+    assert_eq!(
+        authors("= Title\n:author: Jane Doe\n:author!:\n:author: John Doe\n"),
+        [author("John", None, Some("Doe"), None)]
+    );
+    // The author line takes precedence over the attribute entries
+    assert_eq!(
+        authors("= Title\nJane Doe\n:author: John Doe\n"),
+        [author("Jane", None, Some("Doe"), None)]
+    );
+}
+
+#[test]
+fn author_attributes() {
+    let header = parse("= Title\nJane Q Doe <jane@example.org>; Prince\n")
+        .header
+        .unwrap();
+    let attributes = header.author_attributes();
+    let get = |name: &str| attributes.get(name).map(String::as_str);
+    assert_eq!(get("author"), Some("Jane Q Doe"));
+    assert_eq!(get("firstname"), Some("Jane"));
+    assert_eq!(get("middlename"), Some("Q"));
+    assert_eq!(get("lastname"), Some("Doe"));
+    assert_eq!(get("authorinitials"), Some("JQD"));
+    assert_eq!(get("email"), Some("jane@example.org"));
+    assert_eq!(get("author_2"), Some("Prince"));
+    assert_eq!(get("authorinitials_2"), Some("P"));
+    assert_eq!(get("lastname_2"), None);
+    assert_eq!(get("authors"), Some("Jane Q Doe, Prince"));
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use rascii::{
+    ast::{
+        BlockContent, BreakKind, Document, Inline, UndelimitedBlockContent, visit_mut::VisitorMut,
+    },
+    checkpoint_iterator::CheckpointIterator,
+    parser::{
+        events::{Event, Events, Tag},
+        traits::DocParser,
+    },
+    subs::{Substitutor, inlines},
+};
+
+fn parse(text: &str) -> Document<'static> {
+    CheckpointIterator::new(text.chars())
+        .parse_document()
+        .unwrap()
+}
+
+#[test]
+fn parses_breaks() {
+    let document = parse("One\n\n'''\n\n[.fancy]\n<<< \n---\n***\nTwo\n'''\n\n''''\n");
+    let contents: Vec<_> = document
+        .content
+        .blocks
+        .iter()
+        .map(|block| &block.content)
+        .collect();
+    assert!(matches!(contents[0], BlockContent::Undelimited(_)));
+    assert_eq!(contents[1], &BlockContent::Break(BreakKind::Thematic));
+    assert_eq!(contents[2], &BlockContent::Break(BreakKind::Page));
+    assert_eq!(document.content.blocks[2].attributes[0].key, ".fancy");
+    assert_eq!(contents[3], &BlockContent::Break(BreakKind::Thematic));
+    assert_eq!(contents[4], &BlockContent::Break(BreakKind::Thematic));
+    // A break line following a paragraph is part of its text
+    assert_eq!(
+        contents[5],
+        &BlockContent::Undelimited(vec![UndelimitedBlockContent::Text("Two\n'''".into())])
+    );
+    assert_eq!(contents[6], &BlockContent::Break(BreakKind::Thematic));
+}
+
+#[test]
+fn break_events() {
+    let events: Vec<_> = Events::new(CheckpointIterator::new("<<<\n".chars()))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        events,
+        [
+            Event::Start(Tag::Break(BreakKind::Page)),
+            Event::End(Tag::Break(BreakKind::Page)),
+        ]
+    );
+}
+
+#[test]
+fn renders_breaks() {
+    let rendered: Vec<_> = [BreakKind::Thematic, BreakKind::Page]
+        .into_iter()
+        .map(|kind| (kind.to_html(), kind.to_docbook(), kind.to_roff()))
+        .collect();
+    assert_eq!(
+        rendered,
+        [
+            (
+                "<hr>\n",
+                "<simpara><?asciidoc-hr?></simpara>\n",
+                ".sp\n\\l'\\n(.lu'\n.sp\n"
+            ),
+            (
+                "<div style=\"page-break-after: always;\"></div>\n",
+                "<simpara><?asciidoc-pagebreak?></simpara>\n",
+                ".bp\n"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn hard_breaks() {
+    let mut document =
+        parse("One +\ntwo\n\n[%hardbreaks]\nOne\ntwo +\nthree\n\n[source%hardbreaks]\n<a>\nb\n");
+    Substitutor::new(&HashMap::new()).visit_document_mut(&mut document);
+    let texts: Vec<_> = document
+        .content
+        .blocks
+        .iter()
+        .map(|block| match &block.content {
+            BlockContent::Undelimited(content) => match &content[..] {
+                [UndelimitedBlockContent::Text(text)] => text.to_string(),
+                content => panic!("Expected a paragraph, got {content:?}"),
+            },
+            content => panic!("Expected a paragraph, got {content:?}"),
+        })
+        .collect();
+    assert_eq!(
+        texts,
+        ["One<br>\ntwo", "One<br>\ntwo<br>\nthree", "&lt;a&gt;\nb"]
+    );
+    assert_eq!(
+        inlines("a +\nb \\+ +"),
+        [
+            Inline::Text("a".into()),
+            Inline::HardBreak,
+            Inline::Text("\nb \\+".into()),
+            Inline::HardBreak,
+        ]
+    );
+    assert_eq!(
+        inlines("a +\r\nb"),
+        [
+            Inline::Text("a".into()),
+            Inline::HardBreak,
+            Inline::Text("\r\nb".into()),
+        ]
+    );
+}
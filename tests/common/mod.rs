@@ -0,0 +1,106 @@
+//! Helpers shared by the randomized test suites
+#![allow(dead_code)]
+
+/// Lines and fragments of AsciiDoc covering the syntax both alternative parsers must agree on
+/// with the hand written one
+pub const SNIPPETS: &[&str] = &[
+    "\n",
+    "\n\n",
+    "text",
+    "x\ny\n",
+    "= T\n",
+    "== New\n",
+    "=== Sub\n",
+    "* a\n",
+    "** b\n",
+    "  * z\n",
+    ". one\n",
+    "1. x\n",
+    "a. x\n",
+    "- y\n",
+    "* [x] done\n",
+    "- [ ] todo\n",
+    "ii) r\n",
+    "IV) R\n",
+    "3. n\n",
+    "[lowergreek%reversed,start=2]\n",
+    "+\n",
+    "a:: b\n",
+    "c::\n",
+    "d::: e\n",
+    "  f:::: g\n",
+    "h;; i\n",
+    "j :: k:: l\n",
+    "----\n",
+    "....\n",
+    "====\n",
+    "****\n",
+    "____\n",
+    "++++\n",
+    "////\n",
+    "--\n",
+    "'''\n",
+    "---\n",
+    "***\n",
+    "<<<\n",
+    "// comment\n",
+    "[[id]]\n",
+    "[[x,y]]\n",
+    "[source]\n",
+    "[qanda]\n",
+    "[appendix]\n",
+    "= Part\n",
+    "toc::[]\n",
+    "image::a.png[Alt, width=300]\n",
+    "video:::b[]\n",
+    "audio::c d[]\n",
+    "[quote, a]\n",
+    "[a=\"b, c\",d]\n",
+    ".Title\n",
+    ":name: value\n",
+    ":doctype: book\n",
+    ":toc!:\n",
+    "Jane Doe\n",
+    "Mary_Sue van_der_Berg <m@x.org>; J. R. R. Tolkien\n",
+    ":author: Jane Q Doe\n",
+    ":email: jane@example.org\n",
+    ":authors!:\n",
+    "v1.0, 2020-01-01: r\n",
+    "Version 1.0-rc.2, October 2, 2019\n",
+    ":revnumber: 2\n",
+    "\r\n",
+];
+
+/// Linear congruential generator, seeded so that failures are reproducible
+pub struct Random(u64);
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Number in `0..n`
+    pub fn below(&mut self, n: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) as usize % n
+    }
+
+    /// Char boundary of `text` at or before `at`
+    pub fn floor_boundary(text: &str, mut at: usize) -> usize {
+        while !text.is_char_boundary(at) {
+            at -= 1;
+        }
+        at
+    }
+
+    /// Inserts fewer than `max` snippets at random char boundaries of `text`
+    pub fn insert_snippets(&mut self, text: &mut String, snippets: &[&str], max: usize) {
+        for _ in 0..self.below(max) {
+            let at = Self::floor_boundary(text, self.below(text.len() + 1));
+            text.insert_str(at, snippets[self.below(snippets.len())]);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use rascii::{
+    checkpoint_iterator::CheckpointIterator,
+    cst::{EditError, TextEdit, TextRange},
+    parser::traits::DocSyntaxParser,
+};
+
+fn assert_lossless(text: &str) {
+    let tree = CheckpointIterator::new(text.chars())
+        .parse_syntax_tree()
+        .unwrap();
+    assert_eq!(tree.to_string(), text);
+    assert_eq!(tree.text_len(), text.len());
+}
+
+#[test]
+fn round_trips_test_document() {
+    assert_lossless(&std::fs::read_to_string("test.adoc").unwrap());
+}
+
+#[test]
+fn round_trips_line_endings_and_whitespace() {
+    for text in [
+        "",
+        "\n\n\n",
+        "= Title\r\nJane Doe\r\n:name: value\r\n\r\n== Section\r\n\r\nText\r\n* item\r\n",
+        "= Title  \n:name:   value \t\n\n== Section \t\n\nText   \n  indented  \n\t\n",
+        "Text without a newline at the end",
+        "Text\r",
+        "== Section\n\n----\ncode\r\n----\n\n* item\r\n+\r\ncontinued\r\n",
+    ] {
+        assert_lossless(text);
+    }
+}
+
+#[test]
+fn round_trips_comments() {
+    for text in [
+        "// line comment\nText // not a comment\n",
+        "////\nblock *comment*\n\n== Not a section\n////\nText\n",
+        "= Title\n// comment in the header\n:name: value\n\n////\r\ncomment\r\n////\r\n",
+    ] {
+        assert_lossless(text);
+    }
+}
+
+#[test]
+fn round_trips_unterminated_blocks() {
+    for text in [
+        "----\nunterminated listing\n\n== Section\n",
+        "====\nexample\n\n....\nliteral\n",
+        "////\nunterminated comment",
+        "[source]\n----\r\ncode\r\n",
+        "|===\n| cell\n",
+    ] {
+        assert_lossless(text);
+    }
+}
+
+#[test]
+fn rejects_invalid_edits() {
+    let mut text = "héllo".to_string();
+    for error in [
+        EditError::OutOfBounds(TextRange::new(3, 1)),
+        EditError::OutOfBounds(TextRange::new(0, 9)),
+        EditError::NotCharBoundary(TextRange::new(2, 3)),
+    ] {
+        let (EditError::OutOfBounds(range)
+        | EditError::NotCharBoundary(range)
+        | EditError::Overlapping(range)) = error;
+        assert_eq!(
+            TextEdit::apply_all(&mut text, &[TextEdit::new(range, "x")]),
+            Err(error)
+        );
+    }
+    let edits = [
+        TextEdit::new(TextRange::new(0, 1), "H"),
+        TextEdit::new(TextRange::new(3, 5), "y"),
+        TextEdit::new(TextRange::new(4, 6), "z"),
+    ];
+    assert_eq!(
+        TextEdit::apply_all(&mut text, &edits),
+        Err(EditError::Overlapping(TextRange::new(3, 5)))
+    );
+    assert_eq!(text, "héllo");
+    TextEdit::apply_all(&mut text, &edits[..2]).unwrap();
+    assert_eq!(text, "Héyo");
+}
@@ -0,0 +1,159 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use rascii::{
+    ast::{BlockContent, UndelimitedBlockContent, visit_mut::VisitorMut},
+    checkpoint_iterator::CheckpointIterator,
+    media::{Media, data_uri, mime_type},
+    parser::traits::DocParser,
+    subs::Substitutor,
+};
+
+const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0";
+
+/// Document directory with `images/dot.png` and `images/logo.svg`, and `outside.png` next to
+/// it
+fn docdir(name: &str) -> PathBuf {
+    let root = env::temp_dir().join(name);
+    let docdir = root.join("doc");
+    fs::create_dir_all(docdir.join("images")).unwrap();
+    fs::write(docdir.join("images/dot.png"), PNG).unwrap();
+    fs::write(docdir.join("images/logo.svg"), "<svg/>").unwrap();
+    fs::write(docdir.join("images/notes.txt"), "Not an image").unwrap();
+    fs::write(root.join("outside.png"), PNG).unwrap();
+    docdir
+}
+
+fn attributes(docdir: &Path, extra: &[(&str, &str)]) -> HashMap<String, String> {
+    [
+        ("docdir", docdir.to_str().unwrap()),
+        ("imagesdir", "images"),
+        ("data-uri", ""),
+    ]
+    .iter()
+    .chain(extra)
+    .map(|(name, value)| (name.to_string(), value.to_string()))
+    .collect()
+}
+
+fn image_html(source: &str, attributes: &HashMap<String, String>) -> String {
+    let document = CheckpointIterator::new(source.chars())
+        .parse_document()
+        .unwrap();
+    Media::new(&document.content.blocks[0])
+        .unwrap()
+        .to_html(attributes)
+}
+
+#[test]
+fn mime_types() {
+    let types: Vec<_> = [
+        PNG,
+        b"\xff\xd8\xff\xe0",
+        b"GIF89a",
+        b"RIFF\0\0\0\0WEBPVP8 ",
+        b"\0\0\0\x1cftypavif",
+        b"\xef\xbb\xbf<?xml version=\"1.0\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\"/>",
+        b"<html></html>",
+        b"",
+    ]
+    .iter()
+    .map(|bytes| mime_type(bytes))
+    .collect();
+    assert_eq!(
+        types,
+        [
+            Some("image/png"),
+            Some("image/jpeg"),
+            Some("image/gif"),
+            Some("image/webp"),
+            Some("image/avif"),
+            Some("image/svg+xml"),
+            None,
+            None,
+        ]
+    );
+}
+
+#[test]
+fn non_ascii_svg() {
+    // The first kilobyte ends in the middle of an é
+    let svg = format!("<svg><text>{}</text></svg>", "é".repeat(1000));
+    assert_eq!(mime_type(svg.as_bytes()), Some("image/svg+xml"));
+    let docdir = docdir("rascii-data-uri-svg");
+    fs::write(docdir.join("images/text.svg"), &svg).unwrap();
+    let attributes = attributes(&docdir, &[]);
+    assert!(
+        data_uri("images/text.svg", &attributes)
+            .is_some_and(|uri| uri.starts_with("data:image/svg+xml;base64,"))
+    );
+}
+
+#[test]
+fn embedded_images() {
+    let docdir = docdir("rascii-data-uri");
+    let attributes = attributes(&docdir, &[]);
+    assert_eq!(
+        data_uri("images/logo.svg", &attributes).as_deref(),
+        Some("data:image/svg+xml;base64,PHN2Zy8+")
+    );
+    assert_eq!(
+        image_html("image::dot.png[Dot]\n", &attributes),
+        "<div class=\"imageblock\">\n<div class=\"content\">\n\
+         <img src=\"data:image/png;base64,iVBORw0KGgoA\" alt=\"Dot\">\n</div>\n</div>\n"
+    );
+    // Missing files, unknown types and URLs keep their path
+    for source in [
+        "image::missing.png[]\n",
+        "image::notes.txt[]\n",
+        "image::https://example.org/a.png[]\n",
+    ] {
+        assert!(
+            !image_html(source, &attributes).contains("data:"),
+            "{source}"
+        );
+    }
+
+    let mut document = CheckpointIterator::new("An image:logo.svg[Logo] inline\n".chars())
+        .parse_document()
+        .unwrap();
+    Substitutor::new(&attributes).visit_document_mut(&mut document);
+    assert_eq!(
+        document.content.blocks[0].content,
+        BlockContent::Undelimited(vec![UndelimitedBlockContent::Text(
+            "An <span class=\"image\">\
+             <img src=\"data:image/svg+xml;base64,PHN2Zy8+\" alt=\"Logo\"></span> inline"
+                .into()
+        )])
+    );
+
+    let mut attributes = attributes;
+    attributes.remove("data-uri");
+    assert!(image_html("image::dot.png[]\n", &attributes).contains("src=\"images/dot.png\""));
+}
+
+#[test]
+fn safe_mode() {
+    let docdir = docdir("rascii-data-uri-safe");
+    for (mode, embedded) in [
+        (None, false),
+        (Some("safe"), false),
+        (Some("server"), false),
+        (Some("unsafe"), true),
+    ] {
+        let extra: Vec<_> = mode
+            .map(|mode| ("safe-mode-name", mode))
+            .into_iter()
+            .collect();
+        let attributes = attributes(&docdir, &extra);
+        assert_eq!(
+            data_uri("../outside.png", &attributes).is_some(),
+            embedded,
+            "{mode:?}"
+        );
+        assert!(data_uri("images/dot.png", &attributes).is_some());
+    }
+}
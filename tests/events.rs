@@ -0,0 +1,54 @@
+use rascii::{
+    ast::ListKind,
+    checkpoint_iterator::CheckpointIterator,
+    cst::TextRange,
+    parser::{
+        builder::build_document,
+        events::{Event, Events, Tag},
+    },
+};
+
+#[test]
+fn ranges() {
+    let text = "== S\n\npara\ngraph\n\n* a\n* b\n";
+    let mut events = Events::new(CheckpointIterator::new(text.chars()));
+    let ranges: Vec<_> = events
+        .with_ranges()
+        .map(Result::unwrap)
+        .filter_map(|(event, range)| match event {
+            Event::Start(tag) | Event::End(tag) if !matches!(tag, Tag::Heading { .. }) => {
+                Some((tag, &text[range.start..range.end]))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        ranges,
+        [
+            (Tag::Section { level: 2 }, "== S\n"),
+            (Tag::Paragraph, "para\ngraph\n"),
+            (Tag::Paragraph, "para\ngraph\n"),
+            (Tag::List(ListKind::Unordered), "* a\n* b\n"),
+            (Tag::ListItem, "* a\n* b\n"),
+            (Tag::ListItem, "* a\n* b\n"),
+            (Tag::ListItem, "* a\n* b\n"),
+            (Tag::ListItem, "* a\n* b\n"),
+            (Tag::List(ListKind::Unordered), "* a\n* b\n"),
+            (Tag::Section { level: 2 }, ""),
+        ]
+    );
+}
+
+#[test]
+fn build_errors_have_ranges() {
+    let events = [
+        (Event::Start(Tag::Paragraph), TextRange::new(0, 5)),
+        (Event::End(Tag::ListItem), TextRange::new(5, 9)),
+    ];
+    let error = build_document(events.into_iter().map(Ok)).unwrap_err();
+    assert_eq!((error.start, error.end), (5, 9));
+
+    let events = [(Event::Start(Tag::Paragraph), TextRange::new(3, 8))];
+    let error = build_document(events.into_iter().map(Ok)).unwrap_err();
+    assert_eq!((error.start, error.end), (8, 8));
+}
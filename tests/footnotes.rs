@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use rascii::{
+    ast::{BlockContent, Document, UndelimitedBlockContent, visit_mut::VisitorMut},
+    checkpoint_iterator::CheckpointIterator,
+    footnotes::{Footnote, FootnoteMacro, Footnotes, footnotes},
+    parser::traits::DocParser,
+    subs::Substitutor,
+};
+
+fn parse(text: &str) -> Document<'static> {
+    CheckpointIterator::new(text.chars())
+        .parse_document()
+        .unwrap()
+}
+
+fn paragraphs(document: &Document<'_>) -> Vec<String> {
+    document
+        .content
+        .blocks
+        .iter()
+        .filter_map(|block| match &block.content {
+            BlockContent::Undelimited(content) => match &content[..] {
+                [UndelimitedBlockContent::Text(text)] => Some(text.to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn macros() {
+    let text = "A footnote:[One.] B footnote:disclaimer[Two [x\\]] C footnote:disclaimer[] \
+                D footnote:[] \\footnote:[Escaped]";
+    let found: Vec<_> = footnotes(text)
+        .into_iter()
+        .map(|(range, footnote)| (&text[range], footnote))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (
+                "footnote:[One.]",
+                FootnoteMacro {
+                    name: None,
+                    text: Some("One."),
+                }
+            ),
+            (
+                "footnote:disclaimer[Two [x\\]]",
+                FootnoteMacro {
+                    name: Some("disclaimer"),
+                    text: Some("Two [x\\]"),
+                }
+            ),
+            (
+                "footnote:disclaimer[]",
+                FootnoteMacro {
+                    name: Some("disclaimer"),
+                    text: None,
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn numbering_and_reuse() {
+    let mut document = parse(
+        "First footnote:[A *bold* note.] and footnote:legal[Terms apply.]\n\n\
+         ----\nfootnote:[Verbatim]\n----\n\n\
+         Again footnote:legal[], footnote:missing[] and \\footnote:[not a note].\n",
+    );
+    let attributes = HashMap::new();
+    let mut substitutor = Substitutor::new(&attributes);
+    substitutor.visit_document_mut(&mut document);
+    let texts = paragraphs(&document);
+    assert_eq!(
+        texts[0],
+        "First <sup class=\"footnote\">[<a id=\"_footnoteref_1\" class=\"footnote\" \
+         href=\"#_footnotedef_1\" title=\"View footnote.\">1</a>]</sup> and \
+         <sup class=\"footnote\" id=\"_footnote_legal\">[<a id=\"_footnoteref_2\" \
+         class=\"footnote\" href=\"#_footnotedef_2\" title=\"View footnote.\">2</a>]</sup>"
+    );
+    assert_eq!(
+        texts[1],
+        "Again <sup class=\"footnoteref\">[<a class=\"footnote\" href=\"#_footnotedef_2\" \
+         title=\"View footnote.\">2</a>]</sup>, <sup class=\"footnoteref red\" \
+         title=\"Unresolved footnote reference.\">[missing]</sup> and footnote:[not a note]."
+    );
+    assert_eq!(
+        substitutor.footnotes().entries,
+        [
+            Footnote {
+                number: 1,
+                name: None,
+                text: "A <strong>bold</strong> note.".into(),
+            },
+            Footnote {
+                number: 2,
+                name: Some("legal".into()),
+                text: "Terms apply.".into(),
+            },
+        ]
+    );
+    assert_eq!(
+        substitutor.footnotes().to_html(),
+        "<div id=\"footnotes\">\n<hr>\n\
+         <div class=\"footnote\" id=\"_footnotedef_1\">\n\
+         <a href=\"#_footnoteref_1\">1</a>. A <strong>bold</strong> note.\n</div>\n\
+         <div class=\"footnote\" id=\"_footnotedef_2\">\n\
+         <a href=\"#_footnoteref_2\">2</a>. Terms apply.\n</div>\n</div>\n"
+    );
+    assert_eq!(Footnotes::default().to_html(), "");
+}
+
+#[test]
+fn docbook() {
+    let mut footnotes = Footnotes::default();
+    assert_eq!(
+        footnotes
+            .replace_docbook("A footnote:[One] B footnote:n[Two] C footnote:n[]")
+            .unwrap(),
+        "A <footnote><simpara>One</simpara></footnote> \
+         B <footnote xml:id=\"_footnote_n\"><simpara>Two</simpara></footnote> \
+         C <footnoteref linkend=\"_footnote_n\"/>"
+    );
+    assert_eq!(footnotes.entries.len(), 2);
+    assert_eq!(footnotes.replace_docbook("No footnotes"), None);
+}
@@ -0,0 +1,41 @@
+#![cfg(feature = "pest")]
+
+mod common;
+
+use rascii::{
+    checkpoint_iterator::CheckpointIterator,
+    parser::{grammar::GrammarParser, traits::DocParser},
+};
+
+use common::{Random, SNIPPETS};
+
+/// Both backends must give the same document, or both fail
+fn assert_same(text: &str) {
+    let hand = CheckpointIterator::new(text.chars()).parse_document();
+    let grammar = GrammarParser::parse_document(text);
+    match (hand, grammar) {
+        (Ok(hand), Ok(grammar)) => assert_eq!(hand, grammar, "for {text:?}"),
+        (Err(_), Err(_)) => {}
+        (hand, grammar) => panic!("for {text:?}\nhand: {hand:?}\ngrammar: {grammar:?}"),
+    }
+}
+
+#[test]
+fn test_document() {
+    assert_same(&std::fs::read_to_string("test.adoc").unwrap());
+}
+
+#[test]
+fn matches_hand_written_parser() {
+    let base = std::fs::read_to_string("test.adoc").unwrap();
+    let mut random = Random::new(1);
+    for _ in 0..1000 {
+        let mut text = if random.below(2) == 0 {
+            base.clone()
+        } else {
+            String::new()
+        };
+        random.insert_snippets(&mut text, SNIPPETS, 25);
+        assert_same(&text);
+    }
+}
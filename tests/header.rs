@@ -0,0 +1,54 @@
+use rascii::{
+    ast::{Doctype, DocumentHeader, TocPlacement},
+    checkpoint_iterator::CheckpointIterator,
+    parser::traits::DocParser,
+};
+
+fn header(text: &str) -> DocumentHeader<'static> {
+    CheckpointIterator::new(text.chars())
+        .parse_document()
+        .unwrap()
+        .header
+        .unwrap()
+}
+
+#[test]
+fn metadata() {
+    let header = header(
+        ":doctype: book\n= The Title: A Subtitle\nJane Doe\n\
+         :description: A book about  things\n:keywords: asciidoc, parser,, rust \n\
+         :lang: fr\n:toc: left\n:custom-attr: value\n:sectnums:\n:icons!:\n",
+    );
+    assert_eq!(header.doctype(), Doctype::Book);
+    assert_eq!(header.main_title(), Some("The Title"));
+    assert_eq!(header.subtitle(), Some("A Subtitle"));
+    assert_eq!(header.description(), Some("A book about  things"));
+    assert_eq!(header.keywords(), ["asciidoc", "parser", "rust"]);
+    assert_eq!(header.lang(), Some("fr"));
+    assert_eq!(header.toc(), Some(TocPlacement::Left));
+    assert_eq!(header.attribute("custom-attr"), Some("value"));
+    assert_eq!(header.attribute("sectnums"), Some(""));
+    assert_eq!(header.attribute("icons"), None);
+    assert_eq!(header.attributes.len(), 8);
+    assert_eq!(header.attributes[7].key, "icons");
+    assert_eq!(header.attributes[7].value, None);
+}
+
+#[test]
+fn defaults() {
+    let header = header("= Title\n:toc:\n:toc: sideways\n:toc!:\n:doctype: unknown\n");
+    assert_eq!(header.doctype(), Doctype::Article);
+    assert_eq!(header.main_title(), Some("Title"));
+    assert_eq!(header.subtitle(), None);
+    assert_eq!(header.description(), None);
+    assert!(header.keywords().is_empty());
+    assert_eq!(header.toc(), None);
+
+    let header = self::header("= Title\n:toc:\n");
+    assert_eq!(header.toc(), Some(TocPlacement::Auto));
+    // Only the last `: ` separates the subtitle
+    let header = self::header("= Part 1: Setup: The Basics\n:doctype: manpage\n");
+    assert_eq!(header.main_title(), Some("Part 1: Setup"));
+    assert_eq!(header.subtitle(), Some("The Basics"));
+    assert_eq!(header.doctype(), Doctype::Manpage);
+}
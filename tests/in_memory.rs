@@ -0,0 +1,77 @@
+#![cfg(feature = "nom")]
+
+mod common;
+
+use std::borrow::Cow;
+
+use rascii::{
+    ast::{BlockContent, ListContent, UndelimitedBlockContent},
+    checkpoint_iterator::CheckpointIterator,
+    parser::{
+        in_memory::InMemoryParser,
+        traits::{AsciiDocParser, DocParser},
+    },
+};
+
+use common::{Random, SNIPPETS};
+
+/// Both parsers must give the same document, or both fail
+fn assert_same(text: &str) {
+    let hand = CheckpointIterator::new(text.chars()).parse_document();
+    let in_memory = InMemoryParser {}.try_to_ast(text);
+    match (hand, in_memory) {
+        (Ok(hand), Ok(in_memory)) => assert_eq!(hand, in_memory, "for {text:?}"),
+        (Err(_), Err(_)) => {}
+        (hand, in_memory) => panic!("for {text:?}\nhand: {hand:?}\nin memory: {in_memory:?}"),
+    }
+}
+
+#[test]
+fn matches_hand_written_parser() {
+    let base = std::fs::read_to_string("test.adoc").unwrap();
+    assert_same(&base);
+    let mut random = Random::new(2);
+    for _ in 0..1000 {
+        let mut text = if random.below(2) == 0 {
+            base.clone()
+        } else {
+            String::new()
+        };
+        random.insert_snippets(&mut text, SNIPPETS, 25);
+        assert_same(&text);
+    }
+}
+
+#[test]
+fn borrows_text() {
+    let text = "= Title\n\nFirst line\nsecond line\n\nTrailing  \nspace\n\n* item\n  continued\n";
+    let document = InMemoryParser {}.try_to_ast(text).unwrap();
+    let title = document.header.unwrap().title.unwrap().text;
+    assert!(matches!(title, Cow::Borrowed("Title")));
+
+    let blocks = document.content.blocks;
+    let BlockContent::Undelimited(content) = &blocks[0].content else {
+        panic!("Expected a paragraph, got {:?}", blocks[0]);
+    };
+    assert!(matches!(
+        content[..],
+        [UndelimitedBlockContent::Text(Cow::Borrowed(
+            "First line\nsecond line"
+        ))]
+    ));
+    // Trailing whitespace is dropped, so the lines are copied
+    let BlockContent::Undelimited(content) = &blocks[1].content else {
+        panic!("Expected a paragraph, got {:?}", blocks[1]);
+    };
+    assert!(matches!(
+        &content[..],
+        [UndelimitedBlockContent::Text(Cow::Owned(text))] if text == "Trailing\nspace"
+    ));
+    let BlockContent::List(items) = &blocks[2].content else {
+        panic!("Expected a list, got {:?}", blocks[2]);
+    };
+    let [ListContent::UnorderedList(item)] = &items[..] else {
+        panic!("Expected one item, got {items:?}");
+    };
+    assert!(matches!(&item.text, Cow::Owned(text) if text == "item\ncontinued"));
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use rascii::{
+    checkpoint_iterator::CheckpointIterator,
+    cst::{SyntaxNode, TextEdit, TextRange},
+    parser::{incremental::reparse, traits::DocSyntaxParser},
+};
+
+use common::Random;
+
+fn parse(text: &str) -> SyntaxNode {
+    CheckpointIterator::new(text.chars())
+        .parse_syntax_tree()
+        .unwrap()
+}
+
+/// Every edit must give the same tree as parsing the edited text from scratch
+#[test]
+fn matches_full_parse() {
+    let snippets = [
+        "",
+        "\n",
+        "\n\n",
+        "text",
+        "== New\n",
+        "=== Sub\n",
+        "* item\n",
+        "** nested\n",
+        ". one\n",
+        "----\n",
+        "====\n",
+        "// comment\n",
+        "[[id]]\n",
+        ".Title\n",
+        ":name: value\n",
+        "--\n",
+    ];
+    let base = std::fs::read_to_string("test.adoc").unwrap();
+    let mut random = Random::new(1);
+    for _ in 0..200 {
+        let mut text = base.clone();
+        let mut tree = parse(&text);
+        for _ in 0..5 {
+            let start = Random::floor_boundary(&text, random.below(text.len() + 1));
+            let end = Random::floor_boundary(&text, (start + random.below(20)).min(text.len()));
+            let edit = TextEdit::new(
+                TextRange::new(start, end),
+                snippets[random.below(snippets.len())],
+            );
+            TextEdit::apply_all(&mut text, std::slice::from_ref(&edit)).unwrap();
+            reparse(&mut tree, &edit, &text).unwrap();
+            assert_eq!(tree, parse(&text), "after {edit:?}");
+            assert_eq!(tree.to_string(), text, "after {edit:?}");
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+
+use rascii::{
+    ast::{
+        Attribute, Block, BlockContent, DelimitedBlockContent, DelimitedBlockKind,
+        DescriptionListContent, Document, ListContent, ListNumbering, NumberingStyle,
+        UndelimitedBlockContent, UnorderedListContent, visit_mut::VisitorMut,
+    },
+    checkpoint_iterator::CheckpointIterator,
+    cst::TextRange,
+    diagnostics::{self, Diagnostic},
+    parser::traits::{DocParser, DocSyntaxParser},
+    subs::Substitutor,
+};
+
+fn parse(text: &str) -> Document<'static> {
+    CheckpointIterator::new(text.chars())
+        .parse_document()
+        .unwrap()
+}
+
+fn items<'d>(document: &'d Document<'_>, block: usize) -> &'d [ListContent<'d>] {
+    match &document.content.blocks[block].content {
+        BlockContent::List(items) => items,
+        content => panic!("Expected a list, got {content:?}"),
+    }
+}
+
+fn description<'a>(term: &'a str, text: &'a str, sublist: Vec<ListContent<'a>>) -> ListContent<'a> {
+    ListContent::DescriptionList(DescriptionListContent {
+        term: term.into(),
+        text: text.into(),
+        blocks: Vec::new(),
+        sublist,
+    })
+}
+
+#[test]
+fn description_lists() {
+    let document = parse(
+        "CPU:: The brain\nof the computer\nRAM ::\n  Short term memory\n\
+         Storage::\nDisk::: Spinning\nSSD::: Solid\n* fast\n\
+         Network:: Cables\n    Copper;; Cheap\n\n\
+         [horizontal]\nA:: B\n\n\
+         [qanda]\nWhat is rascii?::\nA parser for *AsciiDoc*.\n\n\
+         std::vec::Vec is not a term\n",
+    );
+    assert_eq!(
+        items(&document, 0),
+        [
+            description("CPU", "The brain\nof the computer", Vec::new()),
+            description("RAM", "Short term memory", Vec::new()),
+            description(
+                "Storage",
+                "",
+                vec![
+                    description("Disk", "Spinning", Vec::new()),
+                    description(
+                        "SSD",
+                        "Solid",
+                        vec![ListContent::UnorderedList(UnorderedListContent {
+                            checked: None,
+                            text: "fast".into(),
+                            blocks: Vec::new(),
+                            sublist: Vec::new(),
+                        })]
+                    ),
+                ]
+            ),
+            description(
+                "Network",
+                "Cables",
+                vec![description("Copper", "Cheap", Vec::new())]
+            ),
+        ]
+    );
+    assert_eq!(document.content.blocks[1].style(), Some("horizontal"));
+    assert_eq!(items(&document, 1), [description("A", "B", Vec::new())]);
+    assert_eq!(document.content.blocks[2].style(), Some("qanda"));
+    assert!(matches!(
+        document.content.blocks[3].content,
+        BlockContent::Undelimited(_)
+    ));
+}
+
+#[test]
+fn substitutes_terms() {
+    let mut document = parse("*Bold* <term>:: _Emphasis_\n");
+    Substitutor::new(&HashMap::new()).visit_document_mut(&mut document);
+    assert_eq!(
+        items(&document, 0),
+        [description(
+            "<strong>Bold</strong> &lt;term&gt;",
+            "<em>Emphasis</em>",
+            Vec::new()
+        )]
+    );
+}
+
+fn paragraph(text: &str) -> Block<'_> {
+    Block {
+        heading: None,
+        title: None,
+        attributes: Vec::new(),
+        content: BlockContent::Undelimited(vec![UndelimitedBlockContent::Text(text.into())]),
+    }
+}
+
+#[test]
+fn checklists() {
+    let document = parse("* [x] done\n* [*] also done\n* [ ] todo\n* [y] text\n* [x]\n");
+    let checked: Vec<_> = items(&document, 0)
+        .iter()
+        .map(|item| match item {
+            ListContent::UnorderedList(item) => (item.checked, item.text.as_ref()),
+            item => panic!("Expected an unordered item, got {item:?}"),
+        })
+        .collect();
+    assert_eq!(
+        checked,
+        [
+            (Some(true), "done"),
+            (Some(true), "also done"),
+            (Some(false), "todo"),
+            (None, "[y] text"),
+            (None, "[x]"),
+        ]
+    );
+    // Only unordered lists are checklists
+    let document = parse(". [x] one\n");
+    let [ListContent::OrderedList(item)] = items(&document, 0) else {
+        panic!("Expected an ordered item");
+    };
+    assert_eq!(item.text, "[x] one");
+}
+
+#[test]
+fn attached_blocks() {
+    let document = parse(
+        "* Install\n+\nRun the installer\nand wait.\n+\n[source]\n----\n$ make\n----\n\
+         ** Nested\n+\n--\nNOTE: Inside an open block\n--\n\
+         * Next\n+\n* Not attached\n",
+    );
+    let [
+        ListContent::UnorderedList(install),
+        ListContent::UnorderedList(next),
+    ] = items(&document, 0)
+    else {
+        panic!("Expected two items, got {:?}", items(&document, 0));
+    };
+    let listing = Block {
+        attributes: vec![Attribute {
+            key: "source".into(),
+            value: None,
+        }],
+        content: BlockContent::Delimited(
+            DelimitedBlockKind::Listing,
+            vec![DelimitedBlockContent::Text("$ make".into())],
+        ),
+        ..paragraph("")
+    };
+    assert_eq!(install.text, "Install");
+    assert_eq!(
+        install.blocks,
+        [paragraph("Run the installer\nand wait."), listing]
+    );
+    let [ListContent::UnorderedList(nested)] = &install.sublist[..] else {
+        panic!("Expected a nested item, got {:?}", install.sublist);
+    };
+    let open = Block {
+        content: BlockContent::Delimited(
+            DelimitedBlockKind::Open,
+            vec![DelimitedBlockContent::Block(paragraph(
+                "NOTE: Inside an open block",
+            ))],
+        ),
+        ..paragraph("")
+    };
+    assert_eq!(nested.blocks, [open]);
+    // Lists can't be attached, the `+` ends the list instead
+    assert!(next.blocks.is_empty());
+    assert_eq!(document.content.blocks[1], paragraph("+\n* Not attached"));
+
+    let document = parse("Term::\n+\nDescribed by a paragraph\n");
+    let [ListContent::DescriptionList(item)] = items(&document, 0) else {
+        panic!("Expected a description");
+    };
+    assert_eq!(item.text, "");
+    assert_eq!(item.blocks, [paragraph("Described by a paragraph")]);
+}
+
+#[test]
+fn numbering() {
+    let numbering = |item: &ListContent<'_>| match item {
+        ListContent::OrderedList(item) => (item.style, item.number),
+        item => panic!("Expected an ordered item, got {item:?}"),
+    };
+    let document = parse(". One\n.. Nested\n... Deeper\n");
+    let [ListContent::OrderedList(first)] = items(&document, 0) else {
+        panic!("Expected a single item");
+    };
+    assert_eq!((first.style, first.number), (NumberingStyle::Arabic, None));
+    let [ListContent::OrderedList(nested)] = &first.sublist[..] else {
+        panic!("Expected a nested item");
+    };
+    assert_eq!(nested.style, NumberingStyle::LowerAlpha);
+    assert_eq!(
+        numbering(&nested.sublist[0]),
+        (NumberingStyle::LowerRoman, None)
+    );
+    assert_eq!(
+        document.content.blocks[0].list_numbering(),
+        Some(ListNumbering {
+            style: NumberingStyle::Arabic,
+            start: 1,
+            reversed: false,
+        })
+    );
+
+    let document = parse("[loweralpha%reversed, start=4]\n. Four\n");
+    assert_eq!(
+        document.content.blocks[0].list_numbering(),
+        Some(ListNumbering {
+            style: NumberingStyle::LowerAlpha,
+            start: 4,
+            reversed: true,
+        })
+    );
+    let document = parse("b. Two\nc. Three\n");
+    assert_eq!(
+        document.content.blocks[0].list_numbering(),
+        Some(ListNumbering {
+            style: NumberingStyle::LowerAlpha,
+            start: 2,
+            reversed: false,
+        })
+    );
+
+    let document = parse("ii) Two\niii) Three\nIV) Nested\n");
+    let roman: Vec<_> = items(&document, 0).iter().map(numbering).collect();
+    assert_eq!(
+        roman,
+        [
+            (NumberingStyle::LowerRoman, Some(2)),
+            (NumberingStyle::LowerRoman, Some(3)),
+        ]
+    );
+    let [_, ListContent::OrderedList(three)] = items(&document, 0) else {
+        panic!("Expected two items");
+    };
+    assert_eq!(
+        numbering(&three.sublist[0]),
+        (NumberingStyle::UpperRoman, Some(4))
+    );
+    assert_eq!(parse("* One\n").content.blocks[0].list_numbering(), None);
+
+    assert_eq!(NumberingStyle::UpperRoman.format(1994), "MCMXCIV");
+    assert_eq!(NumberingStyle::LowerAlpha.format(27), "a");
+    assert_eq!(NumberingStyle::LowerGreek.format(18), "σ");
+    assert_eq!(NumberingStyle::LowerGreek.html_type(), None);
+}
+
+#[test]
+fn numbering_out_of_sequence() {
+    let tree = CheckpointIterator::new("1. One\n3. Three\n  4. Four\n\nb. Two\nd. Four\n".chars())
+        .parse_syntax_tree()
+        .unwrap();
+    assert_eq!(
+        diagnostics::check(&tree),
+        [
+            Diagnostic::warning(
+                TextRange::new(7, 9),
+                "List item out of sequence: expected '2.', got '3.'".to_string()
+            ),
+            Diagnostic::warning(
+                TextRange::new(34, 36),
+                "List item out of sequence: expected 'c.', got 'd.'".to_string()
+            ),
+        ]
+    );
+}
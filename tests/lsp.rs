@@ -0,0 +1,308 @@
+use std::thread::{self, JoinHandle};
+
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
+use lsp_types::{
+    ClientCapabilities, CompletionParams, CompletionResponse, DidChangeTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentSymbolParams, DocumentSymbolResponse, GotoDefinitionParams,
+    GotoDefinitionResponse, Hover, HoverContents, HoverParams, InitializeParams, InitializedParams,
+    Position, PublishDiagnosticsParams, Range, TextDocumentContentChangeEvent,
+    TextDocumentIdentifier, TextDocumentItem, TextDocumentPositionParams, Url,
+    VersionedTextDocumentIdentifier,
+    notification::{
+        DidChangeTextDocument, DidOpenTextDocument, Exit, Initialized, Notification as _,
+        PublishDiagnostics,
+    },
+    request::{
+        Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest, Initialize, Request as _,
+        Shutdown,
+    },
+};
+use rascii::lsp::line_index::LineIndex;
+use serde::{Serialize, de::DeserializeOwned};
+
+const DOCUMENT: &str = "= Manual
+:product: Rascii
+:includedir: partials
+
+[[intro]]
+== Introduction
+
+Welcome to {product}, see <<usage>>.
+
+[#usage]
+=== Usage
+
+include::{includedir}/usage.adoc[]
+
+----
+unterminated
+
+== Reference
+";
+
+struct Client {
+    connection: Connection,
+    next_id: i32,
+}
+
+impl Client {
+    /// Client of a server running on another thread, once initialized
+    fn start() -> (Self, JoinHandle<()>) {
+        let (server, connection) = Connection::memory();
+        let server = thread::spawn(move || rascii::lsp::run(server).unwrap());
+        let mut client = Client {
+            connection,
+            next_id: 0,
+        };
+        let _: serde_json::Value = client.request(
+            Initialize::METHOD,
+            InitializeParams {
+                capabilities: ClientCapabilities::default(),
+                ..Default::default()
+            },
+        );
+        client.notify(Initialized::METHOD, InitializedParams {});
+        (client, server)
+    }
+
+    fn request<P: Serialize, R: DeserializeOwned>(&mut self, method: &str, params: P) -> R {
+        self.next_id += 1;
+        let id = RequestId::from(self.next_id);
+        self.connection
+            .sender
+            .send(Request::new(id.clone(), method.to_string(), params).into())
+            .unwrap();
+        loop {
+            match self.connection.receiver.recv().unwrap() {
+                Message::Response(Response {
+                    id: r,
+                    result,
+                    error,
+                }) if r == id => {
+                    assert!(error.is_none(), "{error:?}");
+                    return serde_json::from_value(result.unwrap()).unwrap();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn notify<P: Serialize>(&self, method: &str, params: P) {
+        self.connection
+            .sender
+            .send(Notification::new(method.to_string(), params).into())
+            .unwrap();
+    }
+
+    fn diagnostics(&self) -> PublishDiagnosticsParams {
+        match self.connection.receiver.recv().unwrap() {
+            Message::Notification(n) if n.method == PublishDiagnostics::METHOD => {
+                serde_json::from_value(n.params).unwrap()
+            }
+            message => panic!("Expected diagnostics, got {message:?}"),
+        }
+    }
+
+    fn completion(&mut self, uri: &Url, line: u32, character: u32) -> Vec<String> {
+        let response: Option<CompletionResponse> = self.request(
+            Completion::METHOD,
+            CompletionParams {
+                text_document_position: Client::position(uri, line, character),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+                context: None,
+            },
+        );
+        match response {
+            Some(CompletionResponse::Array(items)) => items.into_iter().map(|i| i.label).collect(),
+            response => panic!("Expected completion items, got {response:?}"),
+        }
+    }
+
+    fn position(uri: &Url, line: u32, character: u32) -> TextDocumentPositionParams {
+        TextDocumentPositionParams::new(
+            TextDocumentIdentifier::new(uri.clone()),
+            Position::new(line, character),
+        )
+    }
+}
+
+#[test]
+fn in_process_client() {
+    let (mut client, server) = Client::start();
+
+    let uri = Url::parse("file:///docs/manual.adoc").unwrap();
+    client.notify(
+        DidOpenTextDocument::METHOD,
+        DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(
+                uri.clone(),
+                "asciidoc".to_string(),
+                1,
+                DOCUMENT.to_string(),
+            ),
+        },
+    );
+    let diagnostics = client.diagnostics();
+    assert_eq!(diagnostics.diagnostics.len(), 1);
+    assert_eq!(diagnostics.diagnostics[0].range.start, Position::new(14, 0));
+
+    let symbols: Option<DocumentSymbolResponse> = client.request(
+        DocumentSymbolRequest::METHOD,
+        DocumentSymbolParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        },
+    );
+    let Some(DocumentSymbolResponse::Nested(symbols)) = symbols else {
+        panic!("Expected nested symbols, got {symbols:?}");
+    };
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].name, "Introduction");
+    let children = symbols[0].children.as_ref().unwrap();
+    assert_eq!(children[0].name, "Usage");
+
+    let definition = |client: &mut Client, line, character| -> Option<GotoDefinitionResponse> {
+        client.request(
+            GotoDefinition::METHOD,
+            GotoDefinitionParams {
+                text_document_position_params: Client::position(&uri, line, character),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            },
+        )
+    };
+    let Some(GotoDefinitionResponse::Scalar(xref)) = definition(&mut client, 7, 30) else {
+        panic!("Expected a definition for the xref");
+    };
+    assert_eq!(xref.range.start, Position::new(9, 1));
+    let Some(GotoDefinitionResponse::Scalar(include)) = definition(&mut client, 12, 3) else {
+        panic!("Expected a definition for the include");
+    };
+    assert_eq!(include.uri.as_str(), "file:///docs/partials/usage.adoc");
+
+    let hover: Option<Hover> = client.request(
+        HoverRequest::METHOD,
+        HoverParams {
+            text_document_position_params: Client::position(&uri, 7, 13),
+            work_done_progress_params: Default::default(),
+        },
+    );
+    let Some(HoverContents::Markup(markup)) = hover.map(|h| h.contents) else {
+        panic!("Expected a hover for the attribute reference");
+    };
+    assert_eq!(markup.value, "`{product}` = `Rascii`");
+
+    let completion =
+        |client: &mut Client, line, character| client.completion(&uri, line, character);
+    assert_eq!(completion(&mut client, 7, 12), ["product", "includedir"]);
+    assert_eq!(completion(&mut client, 7, 28), ["intro", "usage"]);
+
+    // Closing the block only reparses the end of the document
+    client.notify(
+        DidChangeTextDocument::METHOD,
+        DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri.clone(), 2),
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: Some(Range::new(Position::new(16, 0), Position::new(16, 0))),
+                range_length: None,
+                text: "----\n".to_string(),
+            }],
+        },
+    );
+    assert!(client.diagnostics().diagnostics.is_empty());
+    assert_eq!(
+        completion(&mut client, 7, 28),
+        ["intro", "usage", "_reference"]
+    );
+
+    let _: () = client.request(Shutdown::METHOD, ());
+    client.notify(Exit::METHOD, ());
+    server.join().unwrap();
+}
+
+#[test]
+fn crlf_completion() {
+    let (mut client, server) = Client::start();
+    let uri = Url::parse("file:///docs/crlf.adoc").unwrap();
+    client.notify(
+        DidOpenTextDocument::METHOD,
+        DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(
+                uri.clone(),
+                "asciidoc".to_string(),
+                1,
+                "= Doc\r\n:name: value\r\n\r\nSee {na\r\nNext\r\n".to_string(),
+            ),
+        },
+    );
+    client.diagnostics();
+    // Positions past the end of the line are at its end, before the \r\n
+    for character in [7, 8, 100] {
+        assert_eq!(
+            client.completion(&uri, 3, character),
+            ["name"],
+            "{character}"
+        );
+    }
+
+    let _: () = client.request(Shutdown::METHOD, ());
+    client.notify(Exit::METHOD, ());
+    server.join().unwrap();
+}
+
+#[test]
+fn line_endings() {
+    let index = LineIndex::new("a\r\nb\rc\nd");
+    let lines: Vec<_> = [0, 3, 5, 7]
+        .into_iter()
+        .map(|offset| (index.position(offset), index.line_at(offset).0))
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            (Position::new(0, 0), "a"),
+            (Position::new(1, 0), "b"),
+            (Position::new(2, 0), "c"),
+            (Position::new(3, 0), "d"),
+        ]
+    );
+    assert_eq!(index.offset(Position::new(1, 5)), 4);
+}
+
+#[test]
+fn invalid_change() {
+    let (mut client, server) = Client::start();
+    let uri = Url::parse("file:///docs/invalid.adoc").unwrap();
+    client.notify(
+        DidOpenTextDocument::METHOD,
+        DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(
+                uri.clone(),
+                "asciidoc".to_string(),
+                1,
+                ":name: value\n\nSee {na\n".to_string(),
+            ),
+        },
+    );
+    client.diagnostics();
+    // The range ends before it starts, the change is dropped
+    client.notify(
+        DidChangeTextDocument::METHOD,
+        DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri.clone(), 2),
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: Some(Range::new(Position::new(2, 4), Position::new(0, 0))),
+                range_length: None,
+                text: "x".to_string(),
+            }],
+        },
+    );
+    client.diagnostics();
+    assert_eq!(client.completion(&uri, 2, 7), ["name"]);
+
+    let _: () = client.request(Shutdown::METHOD, ());
+    client.notify(Exit::METHOD, ());
+    server.join().unwrap();
+}
@@ -0,0 +1,318 @@
+use std::{collections::HashMap, env, fs, process::Command};
+
+use rascii::{
+    ast::{BlockContent, Document, DocumentHeader, UndelimitedBlockContent, visit_mut::VisitorMut},
+    checkpoint_iterator::CheckpointIterator,
+    diagnostics,
+    media::{Audio, Image, Media, Video, VideoService, inline_images},
+    parser::traits::{DocParser, DocSyntaxParser},
+    subs::Substitutor,
+};
+
+fn parse(text: &str) -> Document<'static> {
+    CheckpointIterator::new(text.chars())
+        .parse_document()
+        .unwrap()
+}
+
+fn media(document: &Document<'_>) -> Vec<Media> {
+    document
+        .content
+        .blocks
+        .iter()
+        .filter_map(Media::new)
+        .collect()
+}
+
+fn attributes(document: &Document<'_>) -> HashMap<String, String> {
+    document
+        .header
+        .as_ref()
+        .map(DocumentHeader::attribute_values)
+        .unwrap_or_default()
+}
+
+#[test]
+fn images() {
+    let document = parse(
+        "= Doc\n:imagesdir: images/\n\n\
+         .Overview\n[#arch.wide]\n\
+         image::arch-overview.png[Architecture, 300, 200, link=https://example.org, align=center]\n\n\
+         image::https://example.org/logo_small.svg[float=right]\n\n\
+         Text\n",
+    );
+    let found = media(&document);
+    assert_eq!(
+        found,
+        [
+            Media::Image(Image {
+                target: "arch-overview.png".into(),
+                alt: "Architecture".into(),
+                width: Some("300".into()),
+                height: Some("200".into()),
+                link: Some("https://example.org".into()),
+                align: Some("center".into()),
+                float: None,
+                title: Some("Overview".into()),
+                id: Some("arch".into()),
+                role: Some("wide".into()),
+            }),
+            Media::Image(Image {
+                target: "https://example.org/logo_small.svg".into(),
+                alt: "logo small".into(),
+                width: None,
+                height: None,
+                link: None,
+                align: None,
+                float: Some("right".into()),
+                title: None,
+                id: None,
+                role: None,
+            }),
+        ]
+    );
+    assert_eq!(found[0].local_target(), Some("arch-overview.png"));
+    assert_eq!(found[1].local_target(), None);
+    let attributes = attributes(&document);
+    assert_eq!(
+        found[0].to_html(&attributes),
+        "<div id=\"arch\" class=\"imageblock text-center wide\">\n<div class=\"content\">\n\
+         <a class=\"image\" href=\"https://example.org\">\
+         <img src=\"images/arch-overview.png\" alt=\"Architecture\" width=\"300\" height=\"200\">\
+         </a>\n</div>\n<div class=\"title\">Overview</div>\n</div>\n"
+    );
+    assert_eq!(
+        found[1].to_html(&attributes),
+        "<div class=\"imageblock right\">\n<div class=\"content\">\n\
+         <img src=\"https://example.org/logo_small.svg\" alt=\"logo small\">\n</div>\n</div>\n"
+    );
+    assert!(Media::new(&parse("toc::[]\n").content.blocks[0]).is_none());
+}
+
+#[test]
+fn videos_and_audio() {
+    let document = parse(
+        "= Doc\n:imagesdir: media\n\n\
+         video::intro.mp4[poster.png, 640, start=10, end=20, options=\"autoplay,loop\"]\n\n\
+         .Talk\nvideo::RvRhUHTV_8k[youtube, 640, 360, start=5, opts=autoplay]\n\n\
+         video::67480300[vimeo, options=loop]\n\n\
+         audio::podcast.mp3[options=nocontrols]\n",
+    );
+    let found = media(&document);
+    assert_eq!(
+        found[1],
+        Media::Video(Video {
+            target: "RvRhUHTV_8k".into(),
+            service: Some(VideoService::YouTube),
+            poster: None,
+            width: Some("640".into()),
+            height: Some("360".into()),
+            start: Some("5".into()),
+            end: None,
+            options: vec!["autoplay".into()],
+            title: Some("Talk".into()),
+            id: None,
+        })
+    );
+    assert_eq!(
+        found[3],
+        Media::Audio(Audio {
+            target: "podcast.mp3".into(),
+            start: None,
+            end: None,
+            options: vec!["nocontrols".into()],
+            title: None,
+            id: None,
+        })
+    );
+    assert_eq!(found[1].local_target(), None);
+    assert_eq!(found[3].local_target(), Some("podcast.mp3"));
+
+    let html: Vec<_> = found
+        .iter()
+        .map(|m| m.to_html(&attributes(&document)))
+        .collect();
+    assert_eq!(
+        html,
+        [
+            "<div class=\"videoblock\">\n<div class=\"content\">\n\
+             <video src=\"media/intro.mp4#t=10,20\" width=\"640\" poster=\"media/poster.png\" \
+             controls autoplay loop>\nYour browser does not support the video tag.\n</video>\n\
+             </div>\n</div>\n",
+            "<div class=\"videoblock\">\n<div class=\"title\">Talk</div>\n\
+             <div class=\"content\">\n<iframe width=\"640\" height=\"360\" \
+             src=\"https://www.youtube.com/embed/RvRhUHTV_8k?rel=0&amp;start=5&amp;autoplay=1\" \
+             frameborder=\"0\" allowfullscreen></iframe>\n</div>\n</div>\n",
+            "<div class=\"videoblock\">\n<div class=\"content\">\n\
+             <iframe src=\"https://player.vimeo.com/video/67480300?loop=1\" frameborder=\"0\" \
+             allowfullscreen></iframe>\n</div>\n</div>\n",
+            "<div class=\"audioblock\">\n<div class=\"content\">\n\
+             <audio src=\"media/podcast.mp3\">\nYour browser does not support the audio tag.\n\
+             </audio>\n</div>\n</div>\n",
+        ]
+    );
+}
+
+#[test]
+fn inline() {
+    let found: Vec<_> = inline_images("An image:icons/ok.svg[\"OK, fine\",16,role=icon]")
+        .into_iter()
+        .map(|(range, image)| (range, image.alt, image.width, image.role))
+        .collect();
+    assert_eq!(
+        found,
+        [(
+            3..46,
+            "OK, fine".to_string(),
+            Some("16".to_string()),
+            Some("icon".to_string())
+        )]
+    );
+
+    let mut document = parse(
+        "Status image:icons/ok.svg[OK,16,link=https://example.org] and \
+         image:https://example.org/x.png[float=left], not \\image:a.png[] or image::b.png[]\n",
+    );
+    let attributes = HashMap::from([("imagesdir".to_string(), "img".to_string())]);
+    Substitutor::new(&attributes).visit_document_mut(&mut document);
+    assert_eq!(
+        document.content.blocks[0].content,
+        BlockContent::Undelimited(vec![UndelimitedBlockContent::Text(
+            "Status <span class=\"image\"><a class=\"image\" href=\"https://example.org\">\
+             <img src=\"img/icons/ok.svg\" alt=\"OK\" width=\"16\"></a></span> and \
+             <span class=\"image left\"><img src=\"https://example.org/x.png\" alt=\"x\"></span>, \
+             not image:a.png[] or image::b.png[]"
+                .into()
+        )])
+    );
+}
+
+#[test]
+fn missing_files() {
+    let text = "= Doc\n:imagesdir: images\n\nimage::here.png[]\n\nimage::gone.png[]\n\n\
+                video::abc[youtube]\n\naudio::gone.mp3[]\n\nSee image:gone-too.svg[].\n\n\
+                ----\nimage:listing.png[]\n----\n";
+    let tree = CheckpointIterator::new(text.chars())
+        .parse_syntax_tree()
+        .unwrap();
+    let mut checked = Vec::new();
+    let diagnostics = diagnostics::check_media(&tree, |path| {
+        checked.push(path.to_string());
+        path == "images/here.png"
+    });
+    assert_eq!(
+        checked,
+        [
+            "images/here.png",
+            "images/gone.png",
+            "images/gone.mp3",
+            "images/gone-too.svg"
+        ]
+    );
+    let messages: Vec<_> = diagnostics
+        .iter()
+        .map(|d| (&text[d.range.start..d.range.end], d.message.as_str()))
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (
+                "image::gone.png[]\n",
+                "Missing image file 'images/gone.png'"
+            ),
+            (
+                "audio::gone.mp3[]\n",
+                "Missing audio file 'images/gone.mp3'"
+            ),
+            (
+                "image:gone-too.svg[]",
+                "Missing image file 'images/gone-too.svg'"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn check_media_command() {
+    let directory = env::temp_dir().join("rascii-media");
+    fs::create_dir_all(directory.join("images")).unwrap();
+    fs::write(directory.join("images/here.png"), "").unwrap();
+    let index = directory.join("index.adoc");
+    fs::write(
+        &index,
+        "= Doc\n:imagesdir: images\n\nimage::here.png[]\n\nimage::gone.png[]\n",
+    )
+    .unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_rascii"))
+            .args(args)
+            .arg(&index)
+            .output()
+            .unwrap()
+    };
+    assert!(run(&["check"]).status.success());
+    let output = run(&["check", "--media"]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "{}:6:1: warning: Missing image file 'images/gone.png'\n",
+            index.display()
+        )
+    );
+}
+
+#[test]
+fn escapes_attribute_values() {
+    let document = parse(
+        "= Doc\n:imagesdir: im\"g\n\n\
+         image::a\"onerror=\"x.png[Alt,link=https://x\"onclick=\"y]\n\n\
+         [#a\"b]\nimage::https://x/a.png[A\"lt,1\"w,2\"h,align=c\"a,float=f\"l,role=r\"o]\n\n\
+         video::v\"x.mp4[p\"o.png, width=1\" onplay=\"x, start=1\"s]\n\n\
+         video::y\"t[youtube, start=2\"s, opts=loop]\n\n\
+         video::v\"m[vimeo, start=3\"s]\n\n\
+         [#a\"u]\naudio::a\"u.mp3[end=4\"e]\n",
+    );
+    let html: Vec<_> = media(&document)
+        .iter()
+        .map(|m| m.to_html(&attributes(&document)))
+        .collect();
+    assert_eq!(
+        html,
+        [
+            "<div class=\"imageblock\">\n<div class=\"content\">\n\
+             <a class=\"image\" href=\"https://x&quot;onclick=&quot;y\">\
+             <img src=\"im&quot;g/a&quot;onerror=&quot;x.png\" alt=\"Alt\"></a>\n</div>\n</div>\n",
+            "<div id=\"a&quot;b\" class=\"imageblock f&quot;l text-c&quot;a r&quot;o\">\n\
+             <div class=\"content\">\n<img src=\"https://x/a.png\" alt=\"A&quot;lt\" \
+             width=\"1&quot;w\" height=\"2&quot;h\">\n</div>\n</div>\n",
+            "<div class=\"videoblock\">\n<div class=\"content\">\n\
+             <video src=\"im&quot;g/v&quot;x.mp4#t=1&quot;s\" width=\"1&quot; onplay=&quot;x\" \
+             poster=\"im&quot;g/p&quot;o.png\" controls>\n\
+             Your browser does not support the video tag.\n</video>\n</div>\n</div>\n",
+            "<div class=\"videoblock\">\n<div class=\"content\">\n\
+             <iframe src=\"https://www.youtube.com/embed/y&quot;t?rel=0&amp;start=2&quot;s\
+             &amp;loop=1&amp;playlist=y&quot;t\" frameborder=\"0\" allowfullscreen></iframe>\n\
+             </div>\n</div>\n",
+            "<div class=\"videoblock\">\n<div class=\"content\">\n\
+             <iframe src=\"https://player.vimeo.com/video/v&quot;m#at=3&quot;s\" \
+             frameborder=\"0\" allowfullscreen></iframe>\n</div>\n</div>\n",
+            "<div id=\"a&quot;u\" class=\"audioblock\">\n<div class=\"content\">\n\
+             <audio src=\"im&quot;g/a&quot;u.mp3#t=0,4&quot;e\" controls>\n\
+             Your browser does not support the audio tag.\n</audio>\n</div>\n</div>\n",
+        ]
+    );
+
+    // The text of inline images already has its special characters escaped
+    let mut document = parse("An image:i\"n.png[A\"lt,1\"w,link=l\"k,role=r\"o] & more\n");
+    Substitutor::new(&HashMap::new()).visit_document_mut(&mut document);
+    assert_eq!(
+        document.content.blocks[0].content,
+        BlockContent::Undelimited(vec![UndelimitedBlockContent::Text(
+            "An <span class=\"image r&quot;o\"><a class=\"image\" href=\"l&quot;k\">\
+             <img src=\"i&quot;n.png\" alt=\"A&quot;lt\" width=\"1&quot;w\"></a></span> \
+             &amp; more"
+                .into()
+        )])
+    );
+}
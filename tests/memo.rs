@@ -0,0 +1,116 @@
+mod common;
+
+use std::rc::Rc;
+
+use rascii::{
+    checkpoint_iterator::CheckpointIterator,
+    parser::{
+        Rule,
+        traits::{DocParser, DocSyntaxParser, body::DocBlockParser},
+    },
+};
+
+use common::Random;
+
+/// Memoizing must not change the result of parsing
+#[test]
+fn matches_parse_without_memo() {
+    let snippets = [
+        "\n",
+        "\n\n",
+        "text",
+        "== New\n",
+        "=== Sub\n",
+        "* item\n",
+        "** nested\n",
+        ". one\n",
+        "----\n",
+        "====\n",
+        "// comment\n",
+        "[[id]]\n",
+        "[source]\n",
+        ".Title\n",
+        ":name: value\n",
+        "--\n",
+    ];
+    let base = std::fs::read_to_string("test.adoc").unwrap();
+    let mut random = Random::new(3);
+    for _ in 0..500 {
+        let mut text = base.clone();
+        random.insert_snippets(&mut text, &snippets, 25);
+        let tree = CheckpointIterator::new(text.chars()).parse_syntax_tree();
+        let memoized = CheckpointIterator::with_memo(text.chars()).parse_syntax_tree();
+        assert_eq!(format!("{tree:?}"), format!("{memoized:?}"), "for {text:?}");
+        let document = CheckpointIterator::new(text.chars()).parse_document();
+        let memoized = CheckpointIterator::with_memo(text.chars()).parse_document();
+        assert_eq!(
+            format!("{document:?}"),
+            format!("{memoized:?}"),
+            "for {text:?}"
+        );
+    }
+}
+
+#[test]
+fn counts_backtracks() {
+    // The metadata lines are parsed when looking for the end of the section, then for the block
+    let text = "== Section\n\n.Title\n[[id]]\n[source]\nSome text\n";
+    let mut iter = CheckpointIterator::new(text.chars());
+    iter.parse_syntax_tree().unwrap();
+    let mut memoized = CheckpointIterator::with_memo(text.chars());
+    memoized.parse_syntax_tree().unwrap();
+    for rule in [Rule::BlockTitle, Rule::SectionHeading] {
+        let stats = iter.rule_stats(rule);
+        let memoized = memoized.rule_stats(rule);
+        assert!(stats.backtracks > 0, "{rule:?}: {stats:?}");
+        assert_eq!(stats.memo_hits, 0, "{rule:?}: {stats:?}");
+        assert_eq!(
+            (stats.attempts, stats.backtracks),
+            (memoized.attempts, memoized.backtracks),
+            "{rule:?}"
+        );
+        assert!(memoized.memo_hits > 0, "{rule:?}: {memoized:?}");
+    }
+}
+
+#[test]
+fn counts_through_parse_document() {
+    let text = std::fs::read_to_string("test.adoc").unwrap();
+    let mut iter = CheckpointIterator::with_memo(text.chars());
+    iter.parse_document().unwrap();
+    let stats: Vec<_> = Rule::ALL
+        .iter()
+        .map(|&rule| iter.rule_stats(rule))
+        .collect();
+    let hits: usize = stats.iter().map(|stats| stats.memo_hits).sum();
+    let misses: usize = stats
+        .iter()
+        .map(|stats| stats.attempts - stats.memo_hits)
+        .sum();
+    assert!(hits > 0, "{stats:?}");
+    assert!(misses > 0, "{stats:?}");
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn shares_memoized_blocks() {
+    let text = "----\ncode\n----\nafter\n";
+    let mut iter = CheckpointIterator::with_memo(text.chars());
+    iter.push();
+    let first = iter
+        .memo_parse(
+            Rule::DelimitedBlock,
+            CheckpointIterator::parse_delimited_block,
+        )
+        .unwrap();
+    iter.pop().unwrap();
+    let second = iter
+        .memo_parse(
+            Rule::DelimitedBlock,
+            CheckpointIterator::parse_delimited_block,
+        )
+        .unwrap();
+    assert!(Rc::ptr_eq(&first, &second));
+    assert_eq!(iter.rule_stats(Rule::DelimitedBlock).memo_hits, 1);
+    assert_eq!(iter.collect::<String>(), "after\n");
+}
@@ -0,0 +1,96 @@
+use chrono::NaiveDate;
+use rascii::{
+    ast::{Document, Revision},
+    checkpoint_iterator::CheckpointIterator,
+    cst::TextRange,
+    diagnostics::{self, Diagnostic},
+    parser::traits::{DocParser, DocSyntaxParser},
+};
+
+fn parse(text: &str) -> Document<'static> {
+    CheckpointIterator::new(text.chars())
+        .parse_document()
+        .unwrap()
+}
+
+fn revision(line: &str) -> Revision<'static> {
+    parse(&format!("= Title\nJane Doe\n{line}\n"))
+        .header
+        .and_then(|h| h.revision)
+        .unwrap()
+}
+
+fn date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+#[test]
+fn revision_lines() {
+    let revision = revision("v1.0, 2020-01-31: Initial release");
+    assert_eq!(revision.number.as_deref(), Some("1.0"));
+    assert_eq!(revision.date.as_deref(), Some("2020-01-31"));
+    assert_eq!(revision.parsed_date, date(2020, 1, 31));
+    assert_eq!(revision.remark.as_deref(), Some("Initial release"));
+
+    let revision = self::revision("Version 1.0-rc.2, October 2, 2019: Second draft");
+    assert_eq!(revision.number.as_deref(), Some("1.0-rc.2"));
+    assert_eq!(revision.date.as_deref(), Some("October 2, 2019"));
+    assert_eq!(revision.parsed_date, date(2019, 10, 2));
+    assert_eq!(revision.remark.as_deref(), Some("Second draft"));
+
+    let revision = self::revision("v2.1");
+    assert_eq!(revision.number.as_deref(), Some("2.1"));
+    assert_eq!(revision.date, None);
+
+    let revision = self::revision("Q3 2020: Draft");
+    assert_eq!(revision.number, None);
+    assert_eq!(revision.date.as_deref(), Some("Q3 2020"));
+    assert_eq!(revision.parsed_date, None);
+    assert_eq!(revision.remark.as_deref(), Some("Draft"));
+
+    let revision = self::revision("1.0, 2020-13-45");
+    assert_eq!(revision.date.as_deref(), Some("2020-13-45"));
+    assert_eq!(revision.parsed_date, None);
+    assert_eq!(revision.remark, None);
+}
+
+#[test]
+fn invalid_dates() {
+    let tree = CheckpointIterator::new("= Title\nJane Doe\n1.0, 2020-13-45\n".chars())
+        .parse_syntax_tree()
+        .unwrap();
+    assert_eq!(
+        diagnostics::check(&tree),
+        [Diagnostic::warning(
+            TextRange::new(17, 33),
+            "Invalid revision date '2020-13-45'".to_string()
+        )]
+    );
+    assert_eq!(Revision::parse_date("31 Jan 2020"), date(2020, 1, 31));
+    assert_eq!(Revision::parse_date("Jan 31, 2020"), date(2020, 1, 31));
+    assert_eq!(Revision::parse_date("someday"), None);
+}
+
+#[test]
+fn revision_attributes() {
+    let header = parse(":revnumber: 1.2\n:revdate: 2021-05-01\n:revremark: Fixes\n:revremark!:\n")
+        .header
+        .unwrap();
+    let revision = header.revision.as_ref().unwrap();
+    assert_eq!(revision.number.as_deref(), Some("1.2"));
+    assert_eq!(revision.parsed_date, date(2021, 5, 1));
+    assert_eq!(revision.remark, None);
+    let attributes = header.revision_attributes();
+    assert_eq!(attributes.get("revnumber").map(String::as_str), Some("1.2"));
+    assert_eq!(
+        attributes.get("revdate").map(String::as_str),
+        Some("2021-05-01")
+    );
+    assert!(!attributes.contains_key("revremark"));
+
+    // The revision line takes precedence over the attribute entries
+    let header = parse("= Title\nJane Doe\nv3\n:revnumber: 4\n")
+        .header
+        .unwrap();
+    assert_eq!(header.revision.unwrap().number.as_deref(), Some("3"));
+}
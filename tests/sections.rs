@@ -0,0 +1,211 @@
+use rascii::{
+    ast::{Block, BlockContent, Document, SectionContent, SectionKind},
+    checkpoint_iterator::CheckpointIterator,
+    diagnostics,
+    parser::traits::{DocParser, DocSyntaxParser},
+};
+
+fn parse(text: &str) -> Document<'static> {
+    CheckpointIterator::new(text.chars())
+        .parse_document()
+        .unwrap()
+}
+
+fn warnings(text: &str) -> Vec<String> {
+    let tree = CheckpointIterator::new(text.chars())
+        .parse_syntax_tree()
+        .unwrap();
+    diagnostics::check(&tree)
+        .into_iter()
+        .map(|d| d.message)
+        .collect()
+}
+
+fn sections<'d>(block: &'d Block<'d>) -> Vec<&'d Block<'d>> {
+    match &block.content {
+        BlockContent::Section(content) => content
+            .iter()
+            .filter_map(|c| match c {
+                SectionContent::Block(b) if b.section_kind().is_some() => Some(b),
+                _ => None,
+            })
+            .collect(),
+        content => panic!("Expected a section, got {content:?}"),
+    }
+}
+
+const BOOK: &str = "= Manual\n:doctype: book\n\n\
+    [preface]\n== Preface\nText\n\n\
+    = Part One\n\n\
+    [partintro]\n== Introduction\nText\n\n\
+    == Chapter\nText\n\n\
+    [appendix]\n= Appendices\n\n\
+    == First\n\n\
+    [glossary]\n== Glossary\n\n\
+    [index]\n== Index\n";
+
+#[test]
+fn parts_and_special_sections() {
+    let document = parse(BOOK);
+    let kinds: Vec<_> = document
+        .content
+        .blocks
+        .iter()
+        .map(Block::section_kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            Some(SectionKind::Preface),
+            Some(SectionKind::Part),
+            Some(SectionKind::Appendix),
+        ]
+    );
+    let part: Vec<_> = sections(&document.content.blocks[1])
+        .into_iter()
+        .map(|b| b.section_kind().unwrap())
+        .collect();
+    assert_eq!(part, [SectionKind::PartIntro, SectionKind::Section]);
+    let appendix: Vec<_> = sections(&document.content.blocks[2])
+        .into_iter()
+        .map(|b| b.section_kind().unwrap())
+        .collect();
+    assert_eq!(
+        appendix,
+        [
+            SectionKind::Section,
+            SectionKind::Glossary,
+            SectionKind::Index,
+        ]
+    );
+    assert!(SectionKind::Index.is_special());
+    assert!(!SectionKind::Part.is_special());
+    assert!(warnings(BOOK).is_empty(), "{:?}", warnings(BOOK));
+}
+
+#[test]
+fn misplaced_sections() {
+    assert_eq!(
+        warnings(
+            "= Article\n\n[preface]\n== Preface\n\n== Body\n\n\
+             [abstract]\n=== Abstract\n\n= Part\n\n== Text\n\n[partintro]\n== Intro\n"
+        ),
+        [
+            "Preface sections are only allowed in books",
+            "Abstract sections must be at level 1",
+            "Level 0 sections (parts) are only allowed in books",
+            "Part introduction sections are only allowed at the start of a part",
+        ]
+    );
+    assert_eq!(
+        warnings(
+            "= Book\n:doctype: book\n\n[colophon]\n= Colophon\n\n\
+             = Part\n\n== Chapter\n\n[partintro]\n== Intro\n\n[glossary]\n=== Terms\n"
+        ),
+        [
+            "Colophon sections can't be parts",
+            "Part introduction sections are only allowed at the start of a part",
+        ]
+    );
+    assert_eq!(
+        warnings("= Article\n\n=== Deep\n"),
+        ["Section title out of sequence: expected level 1, got level 2"]
+    );
+}
+
+/// Id and number of every section, in document order
+fn headings(document: &Document<'_>) -> Vec<(String, Option<String>)> {
+    fn collect(block: &Block<'_>, headings: &mut Vec<(String, Option<String>)>) {
+        if let Some(heading) = &block.heading {
+            headings.push((
+                heading.id.as_deref().unwrap_or_default().to_string(),
+                heading.number.clone(),
+            ));
+        }
+        if block.section_kind().is_some() {
+            for child in sections(block) {
+                collect(child, headings);
+            }
+        }
+    }
+    let mut headings = Vec::new();
+    for block in &document.content.blocks {
+        collect(block, &mut headings);
+    }
+    headings
+}
+
+#[test]
+fn generated_ids() {
+    let document = parse(
+        "= Title\n\n== Introduction\n\n=== *Bold* & Co. -- Notes\n\n== Introduction\n\n\
+         [[intro_3]]\n== Explicit\n\n[#custom.role]\n== Shorthand\n\n== Introduction\n",
+    );
+    assert_eq!(
+        headings(&document),
+        [
+            ("_introduction".into(), None),
+            ("_bold_co_notes".into(), None),
+            ("_introduction_2".into(), None),
+            ("intro_3".into(), None),
+            ("custom".into(), None),
+            ("_introduction_3".into(), None),
+        ]
+    );
+    let document =
+        parse("= Title\n:idprefix:\n:idseparator: -\n\n== Getting Started\n\n== Getting Started\n");
+    assert_eq!(
+        headings(&document),
+        [
+            ("getting-started".into(), None),
+            ("getting-started-2".into(), None)
+        ]
+    );
+    let document = parse("= Title\n:sectids!:\n\n[[kept]]\n== One\n\n== Two\n");
+    let ids: Vec<_> = headings(&document).into_iter().map(|(id, _)| id).collect();
+    assert_eq!(ids, ["kept", ""]);
+}
+
+#[test]
+fn section_numbers() {
+    let number = |n: &str| Some(n.to_string());
+    let document = parse(
+        "= Title\n:sectnums:\n:sectnumlevels: 2\n\n== One\n\n=== One One\n\n==== Deep\n\n\
+         === One Two\n\n[abstract]\n== Abstract\n\n== Two\n\n\
+         [appendix]\n== Extra\n\n=== Details\n\n[appendix]\n== More\n",
+    );
+    let numbers: Vec<_> = headings(&document).into_iter().map(|(_, n)| n).collect();
+    assert_eq!(
+        numbers,
+        [
+            number("1"),
+            number("1.1"),
+            None,
+            number("1.2"),
+            None,
+            number("2"),
+            number("A"),
+            number("A.1"),
+            number("B"),
+        ]
+    );
+    let book: Vec<_> = headings(&parse(
+        &BOOK.replace(":doctype: book", ":doctype: book\n:sectnums:"),
+    ))
+    .into_iter()
+    .map(|(_, n)| n)
+    .collect();
+    assert_eq!(
+        book,
+        [
+            None,
+            None,
+            None,
+            number("1"),
+            number("A"),
+            number("A.1"),
+            None,
+            None
+        ]
+    );
+}
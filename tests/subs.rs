@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use rascii::{
+    ast::{
+        BlockContent, CharacterReference, DelimitedBlockContent, Document, Inline, Replacement,
+        UndelimitedBlockContent, visit_mut::VisitorMut,
+    },
+    checkpoint_iterator::CheckpointIterator,
+    parser::traits::DocParser,
+    subs::{self, Substitution, Substitutor, inlines, parse_subs},
+};
+
+fn substitute(text: &str) -> Document<'static> {
+    let mut document = CheckpointIterator::new(text.chars())
+        .parse_document()
+        .unwrap();
+    let attributes = HashMap::from([("name".to_string(), "Value".to_string())]);
+    Substitutor::new(&attributes).visit_document_mut(&mut document);
+    document
+}
+
+/// Text of each block of the document
+fn texts(document: &Document<'_>) -> Vec<String> {
+    document
+        .content
+        .blocks
+        .iter()
+        .map(|block| match &block.content {
+            BlockContent::Undelimited(content) => match &content[..] {
+                [UndelimitedBlockContent::Text(text)] => text.to_string(),
+                content => panic!("Expected a paragraph, got {content:?}"),
+            },
+            BlockContent::Delimited(_, content) => match &content[..] {
+                [DelimitedBlockContent::Text(text)] => text.to_string(),
+                content => panic!("Expected a verbatim block, got {content:?}"),
+            },
+            content => panic!("Expected a paragraph or verbatim block, got {content:?}"),
+        })
+        .collect()
+}
+
+#[test]
+fn normal_substitutions() {
+    let document = substitute(
+        "Some *bold* & _emphasis_ in a snake_case_name, `mono` and **un**constrained\n\
+         {name}, {missing} and \\{name} (C) -- x^2^ it's word--word...\n\
+         See https://example.com. or link:index.html[the index] +\n\
+         A pass:[<u>raw</u>], +++<i>raw</i>+++ and +<b>escaped</b>+\n",
+    );
+    assert_eq!(
+        texts(&document),
+        [
+            "Some <strong>bold</strong> &amp; <em>emphasis</em> in a snake_case_name, \
+             <code>mono</code> and <strong>un</strong>constrained\n\
+             Value, {missing} and {name} &#169;&#8201;&#8212;&#8201;x<sup>2</sup> \
+             it&#8217;s word&#8212;&#8203;word&#8230;&#8203;\n\
+             See <a href=\"https://example.com\" class=\"bare\">https://example.com</a>. \
+             or <a href=\"index.html\">the index</a><br>\n\
+             A <u>raw</u>, <i>raw</i> and &lt;b&gt;escaped&lt;/b&gt;"
+        ]
+    );
+}
+
+#[test]
+fn block_defaults_and_subs_attribute() {
+    let document = substitute(
+        ".Title with *strong* <x>\n[subs=none]\nA *not bold* <x>\n\n\
+         ----\n<b>*x*</b> {name}\n----\n\n\
+         [subs=\"+attributes\"]\n----\n<b>*x*</b> {name}\n----\n\n\
+         ++++\n<b>*x*</b> {name}\n++++\n\n\
+         [subs=\"-quotes\"]\nA *x* (C) <y>\n\n\
+         [source]\n*x* <y>\n",
+    );
+    let blocks = &document.content.blocks;
+    assert_eq!(
+        blocks[0].title.as_deref(),
+        Some("Title with <strong>strong</strong> &lt;x&gt;")
+    );
+    assert_eq!(
+        texts(&document),
+        [
+            "A *not bold* <x>",
+            "&lt;b&gt;*x*&lt;/b&gt; {name}",
+            "&lt;b&gt;*x*&lt;/b&gt; Value",
+            "<b>*x*</b> {name}",
+            "A *x* &#169; &lt;y&gt;",
+            "*x* &lt;y&gt;",
+        ]
+    );
+}
+
+#[test]
+fn parses_subs() {
+    use Substitution::*;
+    assert_eq!(
+        parse_subs("quotes, macros", &Substitution::NORMAL),
+        [Quotes, Macros]
+    );
+    assert_eq!(
+        parse_subs("+attributes,-c", &Substitution::VERBATIM),
+        [Attributes]
+    );
+    assert_eq!(
+        parse_subs("q+,unknown", &Substitution::VERBATIM),
+        [Quotes, SpecialCharacters]
+    );
+    assert_eq!(parse_subs("none", &Substitution::NORMAL), []);
+    assert_eq!(parse_subs("n", &[]), Substitution::NORMAL);
+}
+
+#[test]
+fn replacements_as_inlines() {
+    let inlines = inlines("(C) 2024 -- it's a->b... word--word &#169; &amp; \\(TM) & x");
+    assert_eq!(
+        inlines,
+        [
+            Inline::Replacement(Replacement::Copyright),
+            Inline::Text(" 2024".into()),
+            Inline::Replacement(Replacement::EmDash { spaced: true }),
+            Inline::Text("it".into()),
+            Inline::Replacement(Replacement::Apostrophe),
+            Inline::Text("s a".into()),
+            Inline::Replacement(Replacement::RightArrow),
+            Inline::Text("b".into()),
+            Inline::Replacement(Replacement::Ellipsis),
+            Inline::Text(" word".into()),
+            Inline::Replacement(Replacement::EmDash { spaced: false }),
+            Inline::Text("word ".into()),
+            Inline::CharacterReference(CharacterReference::Decimal(169)),
+            Inline::Text(" ".into()),
+            Inline::CharacterReference(CharacterReference::Named("amp".into())),
+            Inline::Text(" (TM) & x".into()),
+        ]
+    );
+    // The references of the source are kept when the special characters are escaped
+    let document = substitute("&#169; &amp; & (R)\n");
+    assert_eq!(texts(&document), ["&#169; &amp; &amp; &#174;"]);
+}
+
+#[test]
+fn replacements_without_special_characters() {
+    use Substitution::*;
+    let attributes = HashMap::new();
+    let text = "a -> b <= c <- d => e &#169; (C)";
+    assert_eq!(
+        subs::substitute(text.into(), &[Replacements], &attributes),
+        "a &#8594; b &#8656; c &#8592; d &#8658; e &#169; &#169;"
+    );
+    assert_eq!(
+        subs::substitute(text.into(), &[SpecialCharacters, Replacements], &attributes),
+        "a &#8594; b &#8656; c &#8592; d &#8658; e &#169; &#169;"
+    );
+    let document = substitute("[subs=\"replacements\"]\n<b>a -> b</b>\n");
+    assert_eq!(texts(&document), ["<b>a &#8594; b</b>"]);
+}
+
+#[test]
+fn escapes_quotes_in_links() {
+    let document = substitute(
+        "link:a\"b[x] https://example.org/\"onclick=\"x and xref:c\"d[y]\n\n\
+         [subs=macros]\nlink:<i>\"[z]\n",
+    );
+    assert_eq!(
+        texts(&document),
+        [
+            "<a href=\"a&quot;b\">x</a> <a href=\"https://example.org/&quot;onclick=&quot;x\" \
+             class=\"bare\">https://example.org/\"onclick=\"x</a> and \
+             <a href=\"#c&quot;d\">y</a>",
+            "<a href=\"<i>&quot;\">z</a>",
+        ]
+    );
+}
+
+#[test]
+fn crlf_hard_breaks() {
+    let attributes = HashMap::new();
+    assert_eq!(
+        subs::substitute(
+            "One +\r\ntwo\r\nthree +".into(),
+            &Substitution::NORMAL,
+            &attributes
+        ),
+        "One<br>\r\ntwo\r\nthree<br>"
+    );
+    let mut document =
+        CheckpointIterator::new("[%hardbreaks]\r\nOne\r\ntwo +\r\nthree\r\n".chars())
+            .parse_document()
+            .unwrap();
+    Substitutor::new(&attributes).visit_document_mut(&mut document);
+    // The text of a document has its line endings normalized
+    assert_eq!(texts(&document), ["One<br>\ntwo<br>\nthree"]);
+}
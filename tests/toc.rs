@@ -0,0 +1,120 @@
+use std::{env, fs, process::Command};
+
+use rascii::{
+    ast::{BlockContent, BlockMacro, Document, TocPlacement},
+    checkpoint_iterator::CheckpointIterator,
+    parser::traits::DocParser,
+    toc::Toc,
+};
+
+fn parse(text: &str) -> Document<'static> {
+    CheckpointIterator::new(text.chars())
+        .parse_document()
+        .unwrap()
+}
+
+const GUIDE: &str = "= Guide\n:sectnums:\n:toc: left\n:toc-title: Contents\n\n\
+    == *Getting* started\n\n=== Install & run\n\n==== Too deep\n\n\
+    [appendix]\n== Options...\n";
+
+#[test]
+fn entries() {
+    let toc = Toc::new(&parse(GUIDE));
+    assert_eq!(toc.title, "Contents");
+    assert_eq!(toc.placement, Some(TocPlacement::Left));
+    assert_eq!(toc.levels, 2);
+    let entries: Vec<_> = toc
+        .iter()
+        .map(|(depth, e)| (depth, e.id.clone().unwrap(), e.text()))
+        .collect();
+    assert_eq!(
+        entries,
+        [
+            (0, "_getting_started".into(), "1. Getting started".into()),
+            (1, "_install_run".into(), "1.1. Install & run".into()),
+            (0, "_options".into(), "A. Options\u{2026}".into()),
+        ]
+    );
+    assert_eq!(
+        toc.to_string(),
+        "1. Getting started\n  1.1. Install & run\nA. Options\u{2026}\n"
+    );
+
+    let toc = Toc::new(&parse("= Plain\n:toclevels: 1\n\n== One\n\n=== Hidden\n"));
+    assert_eq!(toc.placement, None);
+    assert_eq!(toc.title, "Table of Contents");
+    assert_eq!(toc.to_string(), "One\n");
+}
+
+#[test]
+fn renderings() {
+    let toc = Toc::new(&parse(GUIDE));
+    assert_eq!(
+        toc.to_html(),
+        "<div id=\"toc\" class=\"toc2\">\n<div id=\"toctitle\">Contents</div>\n\
+         <ul class=\"sectlevel1\">\n\
+         <li><a href=\"#_getting_started\">1. Getting started</a>\n\
+         <ul class=\"sectlevel2\">\n\
+         <li><a href=\"#_install_run\">1.1. Install &amp; run</a></li>\n\
+         </ul>\n</li>\n\
+         <li><a href=\"#_options\">A. Options\u{2026}</a></li>\n\
+         </ul>\n</div>\n"
+    );
+    assert_eq!(
+        toc.to_markdown(),
+        "- [1. Getting started](#_getting_started)\n  \
+         - [1.1. Install & run](#_install_run)\n\
+         - [A. Options\u{2026}](#_options)\n"
+    );
+    assert_eq!(toc.to_docbook(), "<?asciidoc-toc?>\n");
+}
+
+#[test]
+fn escapes_html() {
+    let toc = Toc::new(&parse(
+        "= Doc\n:toc-title: <Contents>\n\n[#a\"b]\n== A <b>\n",
+    ));
+    assert_eq!(
+        toc.to_html(),
+        "<div id=\"toc\" class=\"toc\">\n<div id=\"toctitle\">&lt;Contents&gt;</div>\n\
+         <ul class=\"sectlevel1\">\n\
+         <li><a href=\"#a&quot;b\">A &lt;b&gt;</a></li>\n\
+         </ul>\n</div>\n"
+    );
+}
+
+#[test]
+fn macro_placement() {
+    let document = parse("= Manual\n:toc: macro\n\nIntro\n\ntoc::[]\n\n== Usage\n");
+    let toc = Toc::new(&document);
+    let blocks = &document.content.blocks;
+    assert_eq!(
+        blocks[1].content,
+        BlockContent::Macro(BlockMacro {
+            name: "toc".into(),
+            target: "".into(),
+            attributes: Vec::new(),
+        })
+    );
+    let placed: Vec<_> = blocks.iter().map(|b| toc.is_placed_at(b)).collect();
+    assert_eq!(placed, [false, true, false]);
+
+    let document = parse("= Manual\n:toc:\n\ntoc::[]\n");
+    assert!(!Toc::new(&document).is_placed_at(&document.content.blocks[0]));
+}
+
+#[test]
+fn outline_command() {
+    let path = env::temp_dir().join("rascii-outline.adoc");
+    fs::write(&path, GUIDE).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rascii"))
+        .arg("outline")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        Toc::new(&parse(GUIDE)).to_string()
+    );
+}
@@ -0,0 +1,92 @@
+use std::fs;
+
+use rascii::{
+    ast::{
+        Block, BlockContent, Document, SectionHeading, UndelimitedBlockContent,
+        visit::{Visitor, walk_block},
+        visit_mut::{VisitorMut, walk_section_heading_mut},
+    },
+    checkpoint_iterator::CheckpointIterator,
+    parser::traits::DocParser,
+};
+
+fn parse(text: &str) -> Document<'static> {
+    CheckpointIterator::new(text.chars())
+        .parse_document()
+        .unwrap()
+}
+
+/// Text of the paragraphs of a document, in order
+#[derive(Default)]
+struct Paragraphs(Vec<String>);
+
+impl Visitor for Paragraphs {
+    fn visit_block(&mut self, block: &Block<'_>) {
+        if let BlockContent::Undelimited(content) = &block.content {
+            for content in content {
+                if let UndelimitedBlockContent::Text(text) = content {
+                    self.0.push(text.to_string());
+                }
+            }
+        }
+        walk_block(self, block);
+    }
+}
+
+/// Levels of the section headings of a document, in order
+#[derive(Default)]
+struct Levels(Vec<usize>);
+
+impl Visitor for Levels {
+    fn visit_section_heading(&mut self, heading: &SectionHeading<'_>) {
+        self.0.push(heading.level);
+    }
+}
+
+struct Demote;
+
+impl VisitorMut for Demote {
+    fn visit_section_heading_mut(&mut self, heading: &mut SectionHeading<'_>) {
+        heading.level += 1;
+        walk_section_heading_mut(self, heading);
+    }
+}
+
+#[test]
+fn visits_nested_paragraphs() {
+    let document = parse(
+        "= Doc\n\nPreamble.\n\n== Section\n\nIn a section.\n\n=== Nested\n\n\
+         In a nested section.\n\n====\nIn an example block.\n\n* item\n+\nAttached to an item.\n\
+         ====\n\n* item\n** nested item\n+\nAttached to a nested item.\n\n\
+         term:: description\n+\nAttached to a description.\n",
+    );
+    let mut paragraphs = Paragraphs::default();
+    paragraphs.visit_document(&document);
+    assert_eq!(
+        paragraphs.0,
+        [
+            "Preamble.",
+            "In a section.",
+            "In a nested section.",
+            "In an example block.",
+            "Attached to an item.",
+            "Attached to a nested item.",
+            "Attached to a description.",
+        ]
+    );
+}
+
+#[test]
+fn demotes_headings() {
+    let mut document = parse(&fs::read_to_string("test.adoc").unwrap());
+    let mut before = Levels::default();
+    before.visit_document(&document);
+    Demote.visit_document_mut(&mut document);
+    let mut after = Levels::default();
+    after.visit_document(&document);
+    assert!(before.0.len() > 1, "{:?}", before.0);
+    assert_eq!(
+        after.0,
+        before.0.iter().map(|level| level + 1).collect::<Vec<_>>()
+    );
+}
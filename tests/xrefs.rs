@@ -0,0 +1,243 @@
+use std::{collections::HashMap, env, fs, process::Command};
+
+use rascii::{
+    ast::{BlockContent, Document, SectionContent, UndelimitedBlockContent, visit_mut::VisitorMut},
+    checkpoint_iterator::CheckpointIterator,
+    diagnostics::{self, Diagnostic},
+    parser::traits::{DocParser, DocSyntaxParser},
+    subs::Substitutor,
+    xref::{self, Anchor, AnchorKind, Catalog, InlineAnchor, Xref, XrefResolver},
+};
+
+fn parse(text: &str) -> Document<'static> {
+    CheckpointIterator::new(text.chars())
+        .parse_document()
+        .unwrap()
+}
+
+fn messages(diagnostics: &[Diagnostic], text: &str) -> Vec<(String, String)> {
+    diagnostics
+        .iter()
+        .map(|d| {
+            let range = d.range.start..d.range.end;
+            (text[range].to_string(), d.message.clone())
+        })
+        .collect()
+}
+
+const MANUAL: &str = "= Manual\n\n\
+    == Getting started\n\nSee <<usage>>, <<tips,the tips>> and xref:guide.adoc#setup[].\n\n\
+    [[usage]]\n== Usage\n\n[#tips]\n.Useful tips\nA [[note,Note]]paragraph.\n\n\
+    [reftext=Table]\n[[table]]\n|===\n|===\n\n[bibliography]\n== References\n\n\
+    * [[[pp]]] Pragmatic Programmer\n* [[[gof,GoF]]] Design Patterns\n";
+
+#[test]
+fn scanning() {
+    let text = "<<intro>>, <<other.adoc#usage, Usage>>, xref:guide.adoc[] \\<<no>> <<<x";
+    let found: Vec<_> = xref::xrefs(text).into_iter().map(|(_, x)| x).collect();
+    assert_eq!(
+        found,
+        [
+            Xref {
+                path: None,
+                id: Some("intro"),
+                text: None,
+            },
+            Xref {
+                path: Some("other.adoc"),
+                id: Some("usage"),
+                text: Some("Usage"),
+            },
+            Xref {
+                path: Some("guide.adoc"),
+                id: None,
+                text: None,
+            },
+        ]
+    );
+    assert_eq!(found[1].href(), "other.html#usage");
+    assert_eq!(found[2].href(), "guide.html");
+
+    let text = "[[a]] [[b,Bee]] anchor:c[Sea] [[[bib,Label]]] \\[[d]] [[1]]";
+    let anchors: Vec<_> = xref::inline_anchors(text)
+        .into_iter()
+        .map(|(range, anchor)| (&text[range], anchor))
+        .collect();
+    assert_eq!(
+        anchors,
+        [
+            (
+                "[[a]]",
+                InlineAnchor {
+                    id: "a",
+                    reftext: None,
+                    bibliography: false,
+                }
+            ),
+            (
+                "[[b,Bee]]",
+                InlineAnchor {
+                    id: "b",
+                    reftext: Some("Bee"),
+                    bibliography: false,
+                }
+            ),
+            (
+                "anchor:c[Sea]",
+                InlineAnchor {
+                    id: "c",
+                    reftext: Some("Sea"),
+                    bibliography: false,
+                }
+            ),
+            (
+                "[[[bib,Label]]]",
+                InlineAnchor {
+                    id: "bib",
+                    reftext: Some("Label"),
+                    bibliography: true,
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn catalog() {
+    let catalog = Catalog::new(&parse(MANUAL));
+    assert_eq!(catalog.title.as_deref(), Some("Manual"));
+    let anchor = |id: &str| catalog.anchors.get(id).cloned();
+    let anchor_of = |kind, reftext: &str| {
+        Some(Anchor {
+            kind,
+            reftext: Some(reftext.to_string()),
+        })
+    };
+    assert_eq!(
+        anchor("_getting_started"),
+        anchor_of(AnchorKind::Section, "Getting started")
+    );
+    assert_eq!(anchor("usage"), anchor_of(AnchorKind::Section, "Usage"));
+    assert_eq!(anchor("tips"), anchor_of(AnchorKind::Block, "Useful tips"));
+    assert_eq!(anchor("table"), anchor_of(AnchorKind::Block, "Table"));
+    assert_eq!(anchor("note"), anchor_of(AnchorKind::Inline, "Note"));
+    assert_eq!(anchor("pp"), anchor_of(AnchorKind::Bibliography, "[pp]"));
+    assert_eq!(anchor("gof"), anchor_of(AnchorKind::Bibliography, "[GoF]"));
+    assert_eq!(catalog.anchors.len(), 8);
+    assert!(catalog.duplicates.is_empty());
+
+    let catalog = Catalog::new(&parse(
+        "[[a]]\nOne [[a]]\n\n----\n[[a]]\n----\n\n[#b]\nTwo anchor:b[]\n",
+    ));
+    assert_eq!(catalog.anchors["b"].reftext, None);
+    assert_eq!(catalog.duplicates, ["a", "b"]);
+}
+
+#[test]
+fn resolution() {
+    let mut document = parse(MANUAL);
+    let guide = parse("= Guide\n\n[[setup]]\n== Setting up\n");
+    let (catalog, guide) = (Catalog::new(&document), Catalog::new(&guide));
+    XrefResolver::new(&catalog)
+        .with_document("guide.adoc", &guide)
+        .visit_document_mut(&mut document);
+    Substitutor::new(&HashMap::new()).visit_document_mut(&mut document);
+    let BlockContent::Section(content) = &document.content.blocks[0].content else {
+        panic!("Expected a section");
+    };
+    let SectionContent::Block(paragraph) = &content[0] else {
+        panic!("Expected a paragraph");
+    };
+    assert_eq!(
+        paragraph.content,
+        BlockContent::Undelimited(vec![UndelimitedBlockContent::Text(
+            "See <a href=\"#usage\">Usage</a>, <a href=\"#tips\">the tips</a> and \
+             <a href=\"guide.html#setup\">Setting up</a>."
+                .into()
+        )])
+    );
+
+    let mut document =
+        parse("[[a]]\nAn [[b]]anchor, <<missing>>, \\<<a>> and xref:a[x\\] y] [[[c]]]\n");
+    Substitutor::new(&HashMap::new()).visit_document_mut(&mut document);
+    assert_eq!(
+        document.content.blocks[0].content,
+        BlockContent::Undelimited(vec![UndelimitedBlockContent::Text(
+            "An <a id=\"b\"></a>anchor, <a href=\"#missing\">[missing]</a>, &lt;&lt;a&gt;&gt; \
+             and <a href=\"#a\">x] y</a> <a id=\"c\"></a>[c]"
+                .into()
+        )])
+    );
+}
+
+#[test]
+fn unresolved_and_duplicate_ids() {
+    let text = "= Doc\n\n[[intro]]\n== Intro\n\nSee <<intro>>, <<_details>> and <<nowhere>>.\n\n\
+                == Details\n\n[#intro]\nAgain [[twice]] and [[twice]].\n\n\
+                ----\n<<code>> [[intro]]\n----\n\nxref:other.adoc#x[] xref:gone.adoc[]\n";
+    let tree = CheckpointIterator::new(text.chars())
+        .parse_syntax_tree()
+        .unwrap();
+    assert_eq!(
+        messages(&diagnostics::check(&tree), text),
+        [
+            ("#intro".into(), "Duplicate id 'intro'".into()),
+            ("twice".into(), "Duplicate id 'twice'".into()),
+            (
+                "<<nowhere>>".into(),
+                "Unresolved reference to 'nowhere'".into()
+            ),
+        ]
+    );
+    let external = diagnostics::check_external(&tree, |path| {
+        (path == "other.adoc").then(|| vec!["y".to_string()])
+    });
+    assert_eq!(
+        messages(&external, text),
+        [
+            (
+                "xref:other.adoc#x[]".into(),
+                "Unresolved reference to 'other.adoc#x'".into()
+            ),
+            (
+                "xref:gone.adoc[]".into(),
+                "Could not read the referenced document 'gone.adoc'".into()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn check_command() {
+    let directory = env::temp_dir().join("rascii-check");
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("guide.adoc"), "= Guide\n\n== Setup\n").unwrap();
+    let index = directory.join("index.adoc");
+    let run = |text: &str| {
+        fs::write(&index, text).unwrap();
+        Command::new(env!("CARGO_BIN_EXE_rascii"))
+            .arg("check")
+            .arg(&index)
+            .output()
+            .unwrap()
+    };
+
+    let output = run("= Index\n\nSee xref:guide.adoc#_setup[].\n");
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let output = run("= Index\n\nSee <<a>> and\nthé xref:guide.adoc#b[].\n");
+    assert!(!output.status.success());
+    let path = index.display();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "{path}:3:5: warning: Unresolved reference to 'a'\n\
+             {path}:4:5: warning: Unresolved reference to 'guide.adoc#b'\n"
+        )
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "2 problems found\n"
+    );
+}